@@ -0,0 +1,79 @@
+//bloom mip chain. the scene is thresholded into the first level, each level is a filtered half of the one above,
+//then they're added back up the chain so the first level ends up holding the whole glow
+
+#import fullscreen
+
+struct Bloom {
+    //x threshold, y soft knee, z upsample filter radius in source texels
+    params: vec4<f32>
+}
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+@group(0) @binding(2)
+var<uniform> bloom: Bloom;
+
+//13 taps over a 4x4 texel area, weighted so a single bright texel doesn't flicker as it moves
+fn downsample(uv: vec2<f32>) -> vec3<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source));
+
+    let a = textureSampleLevel(source, source_sampler, uv + texel * vec2(-2.0, 2.0), 0.0).rgb;
+    let b = textureSampleLevel(source, source_sampler, uv + texel * vec2(0.0, 2.0), 0.0).rgb;
+    let c = textureSampleLevel(source, source_sampler, uv + texel * vec2(2.0, 2.0), 0.0).rgb;
+    let d = textureSampleLevel(source, source_sampler, uv + texel * vec2(-2.0, 0.0), 0.0).rgb;
+    let e = textureSampleLevel(source, source_sampler, uv, 0.0).rgb;
+    let f = textureSampleLevel(source, source_sampler, uv + texel * vec2(2.0, 0.0), 0.0).rgb;
+    let g = textureSampleLevel(source, source_sampler, uv + texel * vec2(-2.0, -2.0), 0.0).rgb;
+    let h = textureSampleLevel(source, source_sampler, uv + texel * vec2(0.0, -2.0), 0.0).rgb;
+    let i = textureSampleLevel(source, source_sampler, uv + texel * vec2(2.0, -2.0), 0.0).rgb;
+    let j = textureSampleLevel(source, source_sampler, uv + texel * vec2(-1.0, 1.0), 0.0).rgb;
+    let k = textureSampleLevel(source, source_sampler, uv + texel * vec2(1.0, 1.0), 0.0).rgb;
+    let l = textureSampleLevel(source, source_sampler, uv + texel * vec2(-1.0, -1.0), 0.0).rgb;
+    let m = textureSampleLevel(source, source_sampler, uv + texel * vec2(1.0, -1.0), 0.0).rgb;
+
+    return e * 0.125 + (a + c + g + i) * 0.03125 + (b + d + f + h) * 0.0625 + (j + k + l + m) * 0.125;
+}
+
+//keeps what's above the threshold, with a soft knee so the cut doesn't show
+@fragment
+fn fs_prefilter(in: VertexOutput) -> @location(0) vec4<f32> {
+    //the odd huge value (the sun through a gap) would otherwise smear across the whole chain
+    let colour = min(downsample(in.uv), vec3(64.0));
+
+    let threshold = bloom.params.x;
+    let knee = bloom.params.y;
+    let brightness = max(colour.r, max(colour.g, colour.b));
+
+    var soft = clamp(brightness - threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 1e-4);
+    let contribution = max(soft, brightness - threshold) / max(brightness, 1e-4);
+
+    return vec4(colour * contribution, 1.0);
+}
+
+@fragment
+fn fs_downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4(downsample(in.uv), 1.0);
+}
+
+//3x3 tent, blended additively onto the level above
+@fragment
+fn fs_upsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    let offset = bloom.params.z / vec2<f32>(textureDimensions(source));
+
+    var total = textureSampleLevel(source, source_sampler, in.uv, 0.0).rgb * 4.0;
+    total += textureSampleLevel(source, source_sampler, in.uv + offset * vec2(-1.0, 0.0), 0.0).rgb * 2.0;
+    total += textureSampleLevel(source, source_sampler, in.uv + offset * vec2(1.0, 0.0), 0.0).rgb * 2.0;
+    total += textureSampleLevel(source, source_sampler, in.uv + offset * vec2(0.0, -1.0), 0.0).rgb * 2.0;
+    total += textureSampleLevel(source, source_sampler, in.uv + offset * vec2(0.0, 1.0), 0.0).rgb * 2.0;
+    total += textureSampleLevel(source, source_sampler, in.uv + offset * vec2(-1.0, -1.0), 0.0).rgb;
+    total += textureSampleLevel(source, source_sampler, in.uv + offset * vec2(1.0, -1.0), 0.0).rgb;
+    total += textureSampleLevel(source, source_sampler, in.uv + offset * vec2(-1.0, 1.0), 0.0).rgb;
+    total += textureSampleLevel(source, source_sampler, in.uv + offset * vec2(1.0, 1.0), 0.0).rgb;
+
+    return vec4(total / 16.0, 1.0);
+}
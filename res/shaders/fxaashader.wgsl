@@ -0,0 +1,146 @@
+//fxaa 3.11 quality, after timothy lottes. finds the direction of the edge through each pixel from the luma the
+//composite pass left in alpha, walks along it to both ends, then resamples part way across it
+
+#import fullscreen
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+//contrast below which nothing is touched, absolute for dark areas and relative to the brightest neighbour
+const EDGE_THRESHOLD_MIN = 0.0312;
+const EDGE_THRESHOLD_MAX = 0.125;
+//how much single pixel detail gets blurred away, 0 to 1
+const SUBPIXEL_QUALITY = 0.75;
+const SEARCH_STEPS = 12;
+
+//the search along the edge takes longer strides the further it goes
+fn search_stride(i: i32) -> f32 {
+    if (i < 5) {
+        return 1.0;
+    }
+    if (i == 5) {
+        return 1.5;
+    }
+    if (i < 10) {
+        return 2.0;
+    }
+    if (i == 10) {
+        return 4.0;
+    }
+    return 8.0;
+}
+
+fn luma(uv: vec2<f32>) -> f32 {
+    return textureSampleLevel(source, source_sampler, uv, 0.0).a;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source));
+    let uv = in.uv;
+    let centre = textureSampleLevel(source, source_sampler, uv, 0.0);
+
+    //uv y points down the screen
+    let luma_centre = centre.a;
+    let luma_up = luma(uv + texel * vec2(0.0, -1.0));
+    let luma_down = luma(uv + texel * vec2(0.0, 1.0));
+    let luma_left = luma(uv + texel * vec2(-1.0, 0.0));
+    let luma_right = luma(uv + texel * vec2(1.0, 0.0));
+
+    let luma_min = min(luma_centre, min(min(luma_up, luma_down), min(luma_left, luma_right)));
+    let luma_max = max(luma_centre, max(max(luma_up, luma_down), max(luma_left, luma_right)));
+    let range = luma_max - luma_min;
+
+    if (range < max(EDGE_THRESHOLD_MIN, luma_max * EDGE_THRESHOLD_MAX)) {
+        return vec4(centre.rgb, 1.0);
+    }
+
+    let luma_up_left = luma(uv + texel * vec2(-1.0, -1.0));
+    let luma_up_right = luma(uv + texel * vec2(1.0, -1.0));
+    let luma_down_left = luma(uv + texel * vec2(-1.0, 1.0));
+    let luma_down_right = luma(uv + texel * vec2(1.0, 1.0));
+
+    let up_down = luma_up + luma_down;
+    let left_right = luma_left + luma_right;
+    let left_corners = luma_up_left + luma_down_left;
+    let right_corners = luma_up_right + luma_down_right;
+    let up_corners = luma_up_left + luma_up_right;
+    let down_corners = luma_down_left + luma_down_right;
+
+    let edge_horizontal = abs(-2.0 * luma_left + left_corners) + abs(-2.0 * luma_centre + up_down) * 2.0 + abs(-2.0 * luma_right + right_corners);
+    let edge_vertical = abs(-2.0 * luma_up + up_corners) + abs(-2.0 * luma_centre + left_right) * 2.0 + abs(-2.0 * luma_down + down_corners);
+    let is_horizontal = edge_horizontal >= edge_vertical;
+
+    //the neighbours either side of the edge, the first one towards negative uv
+    let luma_negative = select(luma_left, luma_up, is_horizontal);
+    let luma_positive = select(luma_right, luma_down, is_horizontal);
+    let gradient_negative = luma_negative - luma_centre;
+    let gradient_positive = luma_positive - luma_centre;
+    let negative_steepest = abs(gradient_negative) >= abs(gradient_positive);
+    let gradient_scaled = 0.25 * max(abs(gradient_negative), abs(gradient_positive));
+
+    //a step across the edge, towards the side it's steepest on
+    var step_length = select(texel.x, texel.y, is_horizontal);
+    var luma_local_average = 0.5 * (luma_positive + luma_centre);
+    if (negative_steepest) {
+        step_length = -step_length;
+        luma_local_average = 0.5 * (luma_negative + luma_centre);
+    }
+
+    //start on the edge itself, half a pixel over
+    var edge_uv = uv;
+    if (is_horizontal) {
+        edge_uv.y += step_length * 0.5;
+    } else {
+        edge_uv.x += step_length * 0.5;
+    }
+
+    let along = select(vec2(0.0, texel.y), vec2(texel.x, 0.0), is_horizontal);
+    var uv_negative = edge_uv - along;
+    var uv_positive = edge_uv + along;
+    var luma_end_negative = luma(uv_negative) - luma_local_average;
+    var luma_end_positive = luma(uv_positive) - luma_local_average;
+    var reached_negative = abs(luma_end_negative) >= gradient_scaled;
+    var reached_positive = abs(luma_end_positive) >= gradient_scaled;
+
+    for (var i = 1; i < SEARCH_STEPS && !(reached_negative && reached_positive); i++) {
+        if (!reached_negative) {
+            uv_negative -= along * search_stride(i);
+            luma_end_negative = luma(uv_negative) - luma_local_average;
+            reached_negative = abs(luma_end_negative) >= gradient_scaled;
+        }
+        if (!reached_positive) {
+            uv_positive += along * search_stride(i);
+            luma_end_positive = luma(uv_positive) - luma_local_average;
+            reached_positive = abs(luma_end_positive) >= gradient_scaled;
+        }
+    }
+
+    let distance_negative = select(uv.y - uv_negative.y, uv.x - uv_negative.x, is_horizontal);
+    let distance_positive = select(uv_positive.y - uv.y, uv_positive.x - uv.x, is_horizontal);
+    let negative_closer = distance_negative < distance_positive;
+    let edge_length = distance_negative + distance_positive;
+
+    //only blend if the nearer end goes the other way to the centre, otherwise this pixel is on the far side of the edge
+    let centre_smaller = luma_centre < luma_local_average;
+    let luma_end = select(luma_end_positive, luma_end_negative, negative_closer);
+    var offset = select(0.0, 0.5 - min(distance_negative, distance_positive) / edge_length, (luma_end < 0.0) != centre_smaller);
+
+    //single pixel features have no edge to walk, blend them by how far they stand out from the 3x3 average
+    let luma_average = (2.0 * (up_down + left_right) + left_corners + right_corners) / 12.0;
+    let subpixel = clamp(abs(luma_average - luma_centre) / range, 0.0, 1.0);
+    let subpixel_smooth = (-2.0 * subpixel + 3.0) * subpixel * subpixel;
+    offset = max(offset, subpixel_smooth * subpixel_smooth * SUBPIXEL_QUALITY);
+
+    var final_uv = uv;
+    if (is_horizontal) {
+        final_uv.y += offset * step_length;
+    } else {
+        final_uv.x += offset * step_length;
+    }
+
+    return vec4(textureSampleLevel(source, source_sampler, final_uv, 0.0).rgb, 1.0);
+}
@@ -0,0 +1,58 @@
+//analytic single scattering sky. no ray marching, optical depths come from an air mass approximation
+//so anything that has to match the sky (the sky pass, fog) can evaluate it per pixel
+
+//sea level scattering per km and scale heights in km
+const RAYLEIGH_SCATTERING = vec3<f32>(0.0058, 0.0135, 0.0331);
+const RAYLEIGH_HEIGHT = 8.0;
+const MIE_SCATTERING = 0.004;
+const MIE_HEIGHT = 1.2;
+const MIE_G = 0.76;
+
+const SUN_INTENSITY = 22.0;
+const MOON_INTENSITY = 0.35;
+
+const PI = 3.14159265;
+
+//how much thicker the atmosphere is along this direction than straight up, about 38 at the horizon
+fn air_mass(cos_zenith: f32) -> f32 {
+    let c = clamp(cos_zenith, 0.0, 1.0);
+    return 1.0 / (c + 0.15 * pow(93.885 - degrees(acos(c)), -1.253));
+}
+
+fn optical_depth(cos_zenith: f32) -> vec3<f32> {
+    return (RAYLEIGH_SCATTERING * RAYLEIGH_HEIGHT + MIE_SCATTERING * MIE_HEIGHT) * air_mass(cos_zenith);
+}
+
+//colour of the light that makes it down through the atmosphere, reddens as the body sets
+fn transmittance(direction: vec3<f32>) -> vec3<f32> {
+    return exp(-optical_depth(direction.y));
+}
+
+fn rayleigh_phase(mu: f32) -> f32 {
+    return 3.0 / (16.0 * PI) * (1.0 + mu * mu);
+}
+
+fn mie_phase(mu: f32) -> f32 {
+    let g2 = MIE_G * MIE_G;
+    return (1.0 - g2) / (4.0 * PI * pow(1.0 + g2 - 2.0 * MIE_G * mu, 1.5));
+}
+
+//light scattered towards the viewer along view_dir by a light source in light_dir
+fn inscattering(view_dir: vec3<f32>, light_dir: vec3<f32>, intensity: f32) -> vec3<f32> {
+    let mu = dot(view_dir, light_dir);
+    let mass = air_mass(view_dir.y);
+    let rayleigh = RAYLEIGH_SCATTERING * RAYLEIGH_HEIGHT * mass;
+    let mie = MIE_SCATTERING * MIE_HEIGHT * mass;
+    let extinction = rayleigh + mie;
+
+    let scattered = (rayleigh * rayleigh_phase(mu) + mie * mie_phase(mu)) / max(extinction, vec3(1e-4)) * (1.0 - exp(-extinction));
+    //the air mass clamps at the horizon, fade out by hand as the light sinks below it
+    let above = smoothstep(-0.2, 0.02, light_dir.y);
+    return scattered * transmittance(light_dir) * intensity * above;
+}
+
+//sky colour from the sun, the moon opposite it and a faint floor so night isn't pitch black
+fn atmosphere(view_dir: vec3<f32>, sun_dir: vec3<f32>) -> vec3<f32> {
+    let dir = normalize(vec3(view_dir.x, max(view_dir.y, 0.0), view_dir.z));
+    return inscattering(dir, sun_dir, SUN_INTENSITY) + inscattering(dir, -sun_dir, MOON_INTENSITY) + vec3(0.0015, 0.0025, 0.006);
+}
@@ -0,0 +1,54 @@
+//distance fog, part of the camera uniform. it fades to the sky colour behind each point so terrain melts into the
+//horizon, or to a flat colour when the camera is in water or a cave
+
+#import atmosphere
+
+struct Fog {
+    //xyz the sun for the sky colour, w the mode: 0 linear, 1 exponential, 2 height
+    sun_direction: vec4<f32>,
+    //start and end distance, density for the exponential modes, height falloff
+    params: vec4<f32>,
+    //x the height the height fog has the plain exponential density at
+    height: vec4<f32>,
+    //rgb colour of the medium around the camera, a how much it replaces the sky colour
+    medium: vec4<f32>
+}
+
+fn fog_amount(fog: Fog, worldpos: vec3<f32>, view_pos: vec3<f32>) -> f32 {
+    let offset = worldpos - view_pos;
+    let distance = length(offset);
+    let start = fog.params.x;
+    let end = fog.params.y;
+    let density = fog.params.z;
+    let travelled = max(distance - start, 0.0);
+
+    var amount: f32;
+    switch u32(fog.sun_direction.w) {
+        case 1u: {
+            amount = 1.0 - exp(-density * travelled);
+        }
+        case 2u: {
+            //density falls off exponentially with height, integrated along the fogged part of the ray
+            let falloff = fog.params.w;
+            let start_y = view_pos.y + offset.y * min(start / max(distance, 1e-4), 1.0);
+            let rise = falloff * offset.y * travelled / max(distance, 1e-4);
+            let along = select(1.0, (1.0 - exp(-rise)) / rise, abs(rise) > 1e-4);
+            amount = 1.0 - exp(-density * exp(-falloff * (start_y - fog.height.x)) * along * travelled);
+        }
+        default: {
+            amount = clamp(travelled / max(end - start, 1e-4), 0.0, 1.0);
+        }
+    }
+
+    //whatever the mode, nothing shows at the very edge of the view distance
+    return max(amount, smoothstep(end * 0.9, end, distance));
+}
+
+fn fog_colour(fog: Fog, worldpos: vec3<f32>, view_pos: vec3<f32>) -> vec3<f32> {
+    let sky = atmosphere(normalize(worldpos - view_pos), normalize(fog.sun_direction.xyz));
+    return mix(sky, fog.medium.rgb, fog.medium.a);
+}
+
+fn apply_fog(fog: Fog, colour: vec3<f32>, worldpos: vec3<f32>, view_pos: vec3<f32>) -> vec3<f32> {
+    return mix(colour, fog_colour(fog, worldpos, view_pos), fog_amount(fog, worldpos, view_pos));
+}
@@ -0,0 +1,20 @@
+//one triangle that covers the screen, for passes that shade every pixel
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    //0,0 top left like texture coordinates
+    @location(0) uv: vec2<f32>,
+    @location(1) ndc: vec2<f32>
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    let corner = vec2(f32((index << 1u) & 2u), f32(index & 2u));
+    out.ndc = corner * 2.0 - 1.0;
+    out.uv = vec2(corner.x, 1.0 - corner.y);
+    out.clip_position = vec4(out.ndc, 0.0, 1.0);
+
+    return out;
+}
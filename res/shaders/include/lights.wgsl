@@ -0,0 +1,111 @@
+//group 3 the light manager's spot and point lights, the nearest ones in view. their shadows share one atlas of layers,
+//a spot light has one and a point light one per cube face in the order +x, -x, +y, -y, +z, -z
+
+struct Light {
+    //xyz position, w range
+    position_range: vec4<f32>,
+    //rgb colour, w intensity
+    color_intensity: vec4<f32>,
+    //xyz spot direction, w cosine of the outer cone
+    direction_cone: vec4<f32>,
+    //x cosine of the inner cone, y first shadow layer or -1, z 1 for spot lights, w shadow texel size per unit of distance
+    params: vec4<f32>
+}
+
+struct Lights {
+    lights: array<Light, 32>,
+    shadow_matrices: array<mat4x4<f32>, 24>,
+    //x lights in use
+    count: vec4<u32>
+}
+
+@group(3) @binding(3)
+var<uniform> lights: Lights;
+
+@group(3) @binding(4)
+var light_shadow_texture: texture_depth_2d_array;
+
+@group(3) @binding(5)
+var light_shadow_sampler: sampler_comparison;
+
+//1 fully lit, 0 fully shadowed. 3x3 pcf in the light's layer, or the cube face the point is on for point lights
+fn light_shadow(light: Light, worldpos: vec3<f32>, normal: vec3<f32>) -> f32 {
+    if (light.params.y < 0.0) {
+        return 1.0;
+    }
+
+    let from_light = worldpos - light.position_range.xyz;
+    var layer = i32(light.params.y);
+
+    if (light.params.z < 0.5) {
+        let a = abs(from_light);
+        if (a.x >= a.y && a.x >= a.z) {
+            layer += select(0, 1, from_light.x < 0.0);
+        } else if (a.y >= a.z) {
+            layer += select(2, 3, from_light.y < 0.0);
+        } else {
+            layer += select(4, 5, from_light.z < 0.0);
+        }
+    }
+
+    //pushed out along the normal by a texel or so, texels get bigger further from the light
+    let offset_pos = worldpos + normal * (length(from_light) * light.params.w * 1.5 + 0.02);
+    let clip = lights.shadow_matrices[layer] * vec4(offset_pos, 1.0);
+    let ndc = clip.xyz / clip.w;
+    let uv = vec2(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+
+    if (clip.w <= 0.0 || ndc.z > 1.0) {
+        return 1.0;
+    }
+
+    let dims = vec2<f32>(textureDimensions(light_shadow_texture));
+
+    var lit = 0.0;
+    for (var x = -1; x <= 1; x++) {
+        for (var y = -1; y <= 1; y++) {
+            lit += textureSampleCompareLevel(light_shadow_texture, light_shadow_sampler, uv + vec2(f32(x), f32(y)) / dims, layer, ndc.z);
+        }
+    }
+    return lit / 9.0;
+}
+
+//light reaching a surface from every local light, to multiply its albedo by. normal is the geometry's, surface_normal
+//the normal mapped one
+fn local_light(worldpos: vec3<f32>, normal: vec3<f32>, surface_normal: vec3<f32>) -> vec3<f32> {
+    var total = vec3(0.0);
+
+    for (var i = 0u; i < lights.count.x; i++) {
+        let light = lights.lights[i];
+        let to_light = light.position_range.xyz - worldpos;
+        let distance = length(to_light);
+        let range = light.position_range.w;
+
+        if (distance >= range) {
+            continue;
+        }
+
+        let direction = to_light / max(distance, 0.0001);
+        let diffuse = max(dot(surface_normal, direction), 0.0);
+
+        if (diffuse <= 0.0 || dot(normal, direction) <= 0.0) {
+            continue;
+        }
+
+        //inverse square, eased down to nothing at the range
+        let window = saturate(1.0 - pow(distance / range, 4.0));
+        let falloff = window * window / (distance * distance + 1.0);
+
+        var cone = 1.0;
+        if (light.params.z > 0.5) {
+            cone = smoothstep(light.direction_cone.w, light.params.x, dot(-direction, light.direction_cone.xyz));
+        }
+
+        if (cone <= 0.0) {
+            continue;
+        }
+
+        total += light.color_intensity.rgb * light.color_intensity.w * diffuse * falloff * cone * light_shadow(light, worldpos, normal);
+    }
+
+    return total;
+}
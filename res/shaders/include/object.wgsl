@@ -0,0 +1,101 @@
+//what the static and skinned object shaders share: inputs, lighting and the fragment stage
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) tangent: vec3<f32>,
+    @location(4) bitangent: vec3<f32>,
+    //diffuse, normal, emissive
+    @location(5) texture_indices: vec3<u32>
+}
+
+struct InstanceInput {
+    @location(6) m0: vec4<f32>,
+    @location(7) m1: vec4<f32>,
+    @location(8) m2: vec4<f32>,
+    @location(9) m3: vec4<f32>,
+    @location(10) n0: vec3<f32>,
+    @location(11) n1: vec3<f32>,
+    @location(12) n2: vec3<f32>,
+    @location(15) joint_offset: u32
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) world_position: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+    //world directions of +u and of image-up, for normal maps (green up)
+    @location(3) tangent: vec3<f32>,
+    @location(4) bitangent: vec3<f32>,
+    @location(5) texture_indices: vec3<u32>
+};
+
+//every animated object's pose this frame, each instance starts at its joint_offset
+@group(2) @binding(0)
+var<storage, read> joint_palette: array<mat4x4<f32>>;
+
+#import textures
+#import sun_shadows
+#import lights
+#import fog
+
+struct Camera {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    screendims: vec4<u32>,
+    fog: Fog
+}
+
+@group(1) @binding(0)
+var<uniform> camera: Camera;
+
+//emissive texels land above 1 in the hdr target so bloom picks them up
+const EMISSIVE_STRENGTH = 4.0;
+
+fn instance_model_matrix(instance: InstanceInput) -> mat4x4<f32> {
+    return mat4x4<f32>(instance.m0, instance.m1, instance.m2, instance.m3);
+}
+
+fn instance_normal_matrix(instance: InstanceInput) -> mat3x3<f32> {
+    return mat3x3<f32>(instance.n0, instance.n1, instance.n2);
+}
+
+//everything after the vertex has been placed in the world
+fn object_vertex(model: VertexInput, world_position: vec4<f32>, normal: vec3<f32>, tangent: vec3<f32>, bitangent: vec3<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * world_position;
+    out.tex_coords = model.tex_coords;
+    out.world_position = world_position.xyz;
+    out.normal = normalize(normal);
+    out.tangent = normalize(tangent);
+    out.bitangent = normalize(bitangent);
+    out.texture_indices = model.texture_indices;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv_ddx = dpdx(in.tex_coords);
+    let uv_ddy = dpdy(in.tex_coords);
+
+    let diffuse_color = sample_diffuse_grad(in.texture_indices.x, in.tex_coords, uv_ddx, uv_ddy);
+    let normal_sample = sample_normal_grad(in.texture_indices.y, in.tex_coords, uv_ddx, uv_ddy).xyz * 2.0 - 1.0;
+    let emissive_color = sample_emissive_grad(in.texture_indices.z, in.tex_coords, uv_ddx, uv_ddy);
+
+    let normal = normalize(in.normal);
+    let surface_normal = normalize(normalize(in.tangent) * normal_sample.x + normalize(in.bitangent) * normal_sample.y + normal * normal_sample.z);
+
+    //models have no block light, so sky light is all they get. faces turned from the sun keep some of it
+    let shadow = sun_shadow(in.world_position, normal);
+    let direct = max(dot(surface_normal, sun.direction.xyz), 0.0) * shadow;
+    let sunlight_factor = 0.1 + 0.9 * sun.sky_light.x * (0.45 + 0.55 * direct);
+
+    let local = local_light(in.world_position, normal, surface_normal);
+
+    let lit = diffuse_color.rgb * (sunlight_factor + local) + emissive_color.rgb * emissive_color.a * EMISSIVE_STRENGTH;
+
+    return vec4(apply_fog(camera.fog, lit, in.world_position, camera.view_pos.xyz), diffuse_color.a);
+}
@@ -0,0 +1,28 @@
+//what the static and skinned object shadow shaders share. they draw into the same layers as shadowshader.wgsl,
+//the layer's view projection is group 0 and the renderer's joint palette group 1
+
+struct VertexInput {
+    @location(0) position: vec3<f32>
+}
+
+struct InstanceInput {
+    @location(6) m0: vec4<f32>,
+    @location(7) m1: vec4<f32>,
+    @location(8) m2: vec4<f32>,
+    @location(9) m3: vec4<f32>,
+    @location(15) joint_offset: u32
+}
+
+struct ShadowView {
+    view_proj: mat4x4<f32>
+}
+
+@group(0) @binding(0)
+var<uniform> shadow_view: ShadowView;
+
+@group(1) @binding(0)
+var<storage, read> joint_palette: array<mat4x4<f32>>;
+
+fn instance_model_matrix(instance: InstanceInput) -> mat4x4<f32> {
+    return mat4x4<f32>(instance.m0, instance.m1, instance.m2, instance.m3);
+}
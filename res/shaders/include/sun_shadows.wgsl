@@ -0,0 +1,51 @@
+//group 3 the sun (or the moon at night) and its cascaded shadow maps, each cascade covers a slice of the camera
+//frustum further out than the last
+
+struct SunShadows {
+    cascades: array<mat4x4<f32>, 3>,
+    //xyz points towards the sun, or the moon when the sun is down
+    direction: vec4<f32>,
+    //world size of one shadow texel in each cascade
+    texel_sizes: vec4<f32>,
+    //x scales the sunlight baked into vertices for the time of day
+    sky_light: vec4<f32>
+}
+
+@group(3) @binding(0)
+var<uniform> sun: SunShadows;
+
+@group(3) @binding(1)
+var sun_shadow_texture: texture_depth_2d_array;
+
+@group(3) @binding(2)
+var sun_shadow_sampler: sampler_comparison;
+
+//1 fully lit, 0 fully shadowed. 3x3 pcf in the first cascade that contains the point
+fn sun_shadow(worldpos: vec3<f32>, normal: vec3<f32>) -> f32 {
+    if (dot(normal, sun.direction.xyz) <= 0.0) {
+        return 0.0;
+    }
+
+    let dims = vec2<f32>(textureDimensions(sun_shadow_texture));
+
+    for (var i = 0u; i < 3u; i++) {
+        //pushed out along the normal by a texel so flat ground doesn't shadow itself
+        let offset_pos = worldpos + normal * sun.texel_sizes[i] * 1.5;
+        let clip = sun.cascades[i] * vec4(offset_pos, 1.0);
+        let uv = vec2(clip.x * 0.5 + 0.5, 0.5 - clip.y * 0.5);
+
+        if (any(uv < vec2(0.02)) || any(uv > vec2(0.98)) || clip.z > 1.0) {
+            continue;
+        }
+
+        var lit = 0.0;
+        for (var x = -1; x <= 1; x++) {
+            for (var y = -1; y <= 1; y++) {
+                lit += textureSampleCompareLevel(sun_shadow_texture, sun_shadow_sampler, uv + vec2(f32(x), f32(y)) / dims, i, clip.z);
+            }
+        }
+        return lit / 9.0;
+    }
+
+    return 1.0;
+}
@@ -0,0 +1,39 @@
+//flip-book animation for diffuse textures, frames of an animation sit at consecutive texture indices
+
+struct TextureAnimationGlobals {
+    time: f32
+}
+
+struct TextureAnimation {
+    frames: u32,
+    frame_time: f32,
+    interpolate: u32,
+    _padding: u32
+}
+
+@group(0) @binding(6)
+var<uniform> texture_animation_globals: TextureAnimationGlobals;
+
+@group(0) @binding(7)
+var<storage, read> diffuse_animations: array<TextureAnimation>;
+
+struct AnimatedFrame {
+    current: u32,
+    next: u32,
+    blend: f32
+}
+
+fn resolve_diffuse_frame(index: u32) -> AnimatedFrame {
+    let animation = diffuse_animations[index];
+
+    var out: AnimatedFrame;
+
+    let progress = texture_animation_globals.time / max(animation.frame_time, 0.0001);
+    let frame = u32(floor(progress)) % max(animation.frames, 1u);
+
+    out.current = index + frame;
+    out.next = index + (frame + 1u) % max(animation.frames, 1u);
+    out.blend = select(0.0, fract(progress), animation.interpolate != 0u && animation.frames > 1u);
+
+    return out;
+}
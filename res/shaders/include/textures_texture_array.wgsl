@@ -0,0 +1,50 @@
+//group 0 surface textures, every texture of a type is a layer of one texture array sharing a sampler
+
+#import texture_animation
+
+@group(0) @binding(0)
+var diffuse_texture_array: texture_2d_array<f32>;
+
+@group(0) @binding(1)
+var diffuse_sampler: sampler;
+
+@group(0) @binding(2)
+var normal_texture_array: texture_2d_array<f32>;
+
+@group(0) @binding(3)
+var normal_sampler: sampler;
+
+@group(0) @binding(4)
+var emissive_texture_array: texture_2d_array<f32>;
+
+@group(0) @binding(5)
+var emissive_sampler: sampler;
+
+fn sample_diffuse(index: u32, uv: vec2<f32>) -> vec4<f32> {
+    let frame = resolve_diffuse_frame(index);
+    let current = textureSample(diffuse_texture_array, diffuse_sampler, uv, frame.current);
+    let next = textureSample(diffuse_texture_array, diffuse_sampler, uv, frame.next);
+    return mix(current, next, frame.blend);
+}
+
+fn sample_diffuse_level(index: u32, uv: vec2<f32>, level: f32) -> vec4<f32> {
+    let frame = resolve_diffuse_frame(index);
+    let current = textureSampleLevel(diffuse_texture_array, diffuse_sampler, uv, frame.current, level);
+    let next = textureSampleLevel(diffuse_texture_array, diffuse_sampler, uv, frame.next, level);
+    return mix(current, next, frame.blend);
+}
+
+fn sample_diffuse_grad(index: u32, uv: vec2<f32>, ddx: vec2<f32>, ddy: vec2<f32>) -> vec4<f32> {
+    let frame = resolve_diffuse_frame(index);
+    let current = textureSampleGrad(diffuse_texture_array, diffuse_sampler, uv, frame.current, ddx, ddy);
+    let next = textureSampleGrad(diffuse_texture_array, diffuse_sampler, uv, frame.next, ddx, ddy);
+    return mix(current, next, frame.blend);
+}
+
+fn sample_normal_grad(index: u32, uv: vec2<f32>, ddx: vec2<f32>, ddy: vec2<f32>) -> vec4<f32> {
+    return textureSampleGrad(normal_texture_array, normal_sampler, uv, index, ddx, ddy);
+}
+
+fn sample_emissive_grad(index: u32, uv: vec2<f32>, ddx: vec2<f32>, ddy: vec2<f32>) -> vec4<f32> {
+    return textureSampleGrad(emissive_texture_array, emissive_sampler, uv, index, ddx, ddy);
+}
@@ -0,0 +1,124 @@
+//far terrain tiles, positions are in world space already
+//data: u32 (3 face_normal, 8 diffuse from bit 8)
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) data: u32
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) diffuse_texture_index: u32,
+    @location(2) worldpos: vec3<f32>
+};
+
+#import textures
+#import sun_shadows
+#import fog
+
+struct Camera {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    screendims: vec4<u32>,
+    fog: Fog
+}
+
+@group(1) @binding(0)
+var<uniform> camera: Camera;
+
+//chunks around the origin, nonzero where the full meshes are ready and the tiles should step aside
+struct LodGlobals {
+    mask_origin: vec2<i32>,
+    mask_width: u32,
+    _padding: u32
+}
+
+@group(2) @binding(0)
+var<uniform> lod: LodGlobals;
+
+@group(2) @binding(1)
+var<storage, read> ready_chunks: array<u32>;
+
+@vertex
+fn vs_main(vertex: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    var normal: vec3<f32>;
+
+    switch extractBits(vertex.data, 0u, 3u) {
+        case 0u: {
+            normal = vec3(0f, 1f, 0f);
+        }
+        case 1u: {
+            normal = vec3(0f, -1f, 0f);
+        }
+        case 2u: {
+            normal = vec3(1f, 0f, 0f);
+        }
+        case 3u: {
+            normal = vec3(-1f, 0f, 0f);
+        }
+        //front faces -z, back +z like BlockFace
+        case 4u: {
+            normal = vec3(0f, 0f, -1f);
+        }
+        case 5u: {
+            normal = vec3(0f, 0f, 1f);
+        }
+        default: {
+            normal = vec3(0f, 1f, 0f);
+        }
+    }
+
+    out.normal = normal;
+    out.diffuse_texture_index = extractBits(vertex.data, 8u, 8u);
+    out.clip_position = camera.view_proj * vec4<f32>(vertex.position, 1.0);
+    out.worldpos = vertex.position;
+
+    return out;
+}
+
+fn chunk_ready(worldpos: vec3<f32>) -> bool {
+    let chunk = vec2<i32>(floor(worldpos.xz / 16.0)) - lod.mask_origin;
+    if (any(chunk < vec2(0)) || any(chunk >= vec2(i32(lod.mask_width)))) {
+        return false;
+    }
+    return ready_chunks[u32(chunk.x) + u32(chunk.y) * lod.mask_width] != 0u;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var tileUV: vec2<f32>;
+    let absNormal = abs(in.normal);
+    var texCoord: vec2<f32>;
+
+    //same mapping as surfaceshader so the tiles line up with the blocks they stand in for
+    if (absNormal.x > absNormal.y && absNormal.x > absNormal.z) {
+        tileUV = vec2(in.worldpos.z, in.worldpos.y);
+        texCoord = fract(1 - tileUV);
+    } else if (absNormal.y > absNormal.x && absNormal.y > absNormal.z) {
+        tileUV = vec2(in.worldpos.x, in.worldpos.z);
+        texCoord = fract(tileUV);
+    } else {
+        tileUV = vec2(in.worldpos.x, in.worldpos.y);
+        texCoord = fract(1 - tileUV);
+    }
+
+    let uvDdx = dpdx(tileUV);
+    let uvDdy = dpdy(tileUV);
+
+    let diffuse_color = sample_diffuse_grad(in.diffuse_texture_index, texCoord, uvDdx, uvDdy);
+
+    //faces sit on column edges, step inside the column before looking up its chunk
+    if (chunk_ready(in.worldpos - in.normal * 0.01)) {
+        discard;
+    }
+
+    //the tiles have no baked light, treat them as open sky like the surface they stand in for
+    let shadow = sun_shadow(in.worldpos, in.normal);
+    let sunlight_factor = 0.1 + 0.9 * sun.sky_light.x * mix(0.45, 1.0, shadow);
+
+    return vec4(apply_fog(camera.fog, diffuse_color.rgb * sunlight_factor, in.worldpos, camera.view_pos.xyz), 1.0);
+}
@@ -0,0 +1,8 @@
+//render storage object depth from the sun or a local light
+
+#import object_shadow
+
+@vertex
+fn vs_bake(model: VertexInput, instance: InstanceInput) -> @builtin(position) vec4<f32> {
+    return shadow_view.view_proj * instance_model_matrix(instance) * vec4<f32>(model.position, 1.0);
+}
@@ -0,0 +1,21 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>
+}
+
+struct Camera {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>
+}
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+@vertex
+fn vs_main(vertex: VertexInput) -> @builtin(position) vec4<f32> {
+    return camera.view_proj * vec4<f32>(vertex.position, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 0.85, 0.2, 1.0);
+}
@@ -0,0 +1,92 @@
+//final blit from the hdr scene: bloom, exposure, tonemapping, colour grading and gamma, each of which can be off.
+//when fxaa runs after it, perceptual luma goes out in alpha for it to find edges with
+
+#import fullscreen
+
+struct Post {
+    //x exposure, y bloom intensity, z gamma exponent
+    params: vec4<f32>,
+    //x tonemap operator (0 none, 1 reinhard, 2 aces, 3 filmic), y bloom on, z colour grading on, w luma in alpha
+    modes: vec4<u32>
+}
+
+@group(0) @binding(0)
+var scene: texture_2d<f32>;
+
+@group(0) @binding(1)
+var bloom_texture: texture_2d<f32>;
+
+@group(0) @binding(2)
+var linear_sampler: sampler;
+
+@group(0) @binding(3)
+var grading_lut: texture_3d<f32>;
+
+@group(0) @binding(4)
+var<uniform> post: Post;
+
+fn reinhard(colour: vec3<f32>) -> vec3<f32> {
+    return colour / (1.0 + colour);
+}
+
+//narkowicz's fit of the aces reference curve
+fn aces(colour: vec3<f32>) -> vec3<f32> {
+    return clamp((colour * (2.51 * colour + 0.03)) / (colour * (2.43 * colour + 0.59) + 0.14), vec3(0.0), vec3(1.0));
+}
+
+fn hable(x: vec3<f32>) -> vec3<f32> {
+    let a = 0.15;
+    let b = 0.50;
+    let c = 0.10;
+    let d = 0.20;
+    let e = 0.02;
+    let f = 0.30;
+    return ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f;
+}
+
+//uncharted 2's filmic curve, white at 11.2
+fn filmic(colour: vec3<f32>) -> vec3<f32> {
+    return hable(colour * 2.0) / hable(vec3(11.2));
+}
+
+fn tonemap(colour: vec3<f32>, mode: u32) -> vec3<f32> {
+    switch mode {
+        case 1u: {
+            return reinhard(colour);
+        }
+        case 2u: {
+            return aces(colour);
+        }
+        case 3u: {
+            return filmic(colour);
+        }
+        default: {
+            return clamp(colour, vec3(0.0), vec3(1.0));
+        }
+    }
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var colour = textureSampleLevel(scene, linear_sampler, in.uv, 0.0).rgb;
+
+    if (post.modes.y != 0u) {
+        colour += textureSampleLevel(bloom_texture, linear_sampler, in.uv, 0.0).rgb * post.params.y;
+    }
+
+    colour = tonemap(colour * post.params.x, post.modes.x);
+
+    if (post.modes.z != 0u) {
+        //luts are made against gamma encoded images. texel centres so the ends aren't blended with the border
+        let size = f32(textureDimensions(grading_lut).x);
+        let encoded = pow(colour, vec3(1.0 / 2.2));
+        colour = pow(textureSampleLevel(grading_lut, linear_sampler, encoded * ((size - 1.0) / size) + 0.5 / size, 0.0).rgb, vec3(2.2));
+    }
+
+    var alpha = 1.0;
+    if (post.modes.w != 0u) {
+        alpha = sqrt(dot(clamp(colour, vec3(0.0), vec3(1.0)), vec3(0.299, 0.587, 0.114)));
+    }
+
+    return vec4(pow(colour, vec3(post.params.z)), alpha);
+}
@@ -0,0 +1,30 @@
+//terrain depth from the sun, one pass per cascade. only the position bits of the surface vertices are needed
+
+struct VertexInput {
+    @location(0) d0: u32
+}
+
+struct SliceData {
+    position_sliced: vec4<i32>,
+}
+
+struct Cascade {
+    view_proj: mat4x4<f32>
+}
+
+@group(0) @binding(0)
+var<uniform> cascade: Cascade;
+
+@group(1) @binding(0)
+var<storage, read> slices: array<SliceData>;
+
+@vertex
+fn vs_bake(vertex: VertexInput, @builtin(instance_index) slot: u32) -> @builtin(position) vec4<f32> {
+    let position_sliced = slices[slot].position_sliced.xyz;
+
+    let x = i32(extractBits(vertex.d0, 0u, 5u));
+    let y = i32(extractBits(vertex.d0, 5u, 5u));
+    let z = i32(extractBits(vertex.d0, 10u, 5u));
+
+    return cascade.view_proj * vec4<f32>(vec3<f32>(position_sliced * 16 + vec3(x, y, z)), 1.0);
+}
@@ -0,0 +1,25 @@
+//render storage objects whose model has a skeleton. each vertex is moved by up to four joints of the object's pose
+//before the object's own transform
+
+#import object
+
+struct SkinInput {
+    @location(13) joints: vec4<u32>,
+    @location(14) weights: vec4<f32>
+}
+
+@vertex
+fn vs_main(model: VertexInput, instance: InstanceInput, skin: SkinInput) -> VertexOutput {
+    let joints = skin.joints + instance.joint_offset;
+    let skin_matrix = joint_palette[joints.x] * skin.weights.x
+        + joint_palette[joints.y] * skin.weights.y
+        + joint_palette[joints.z] * skin.weights.z
+        + joint_palette[joints.w] * skin.weights.w;
+
+    //joints don't scale unevenly, so the upper 3x3 is fine for directions
+    let skin_normal = mat3x3<f32>(skin_matrix[0].xyz, skin_matrix[1].xyz, skin_matrix[2].xyz);
+    let normal_matrix = instance_normal_matrix(instance) * skin_normal;
+    let world_position = instance_model_matrix(instance) * skin_matrix * vec4<f32>(model.position, 1.0);
+
+    return object_vertex(model, world_position, normal_matrix * model.normal, normal_matrix * model.tangent, normal_matrix * model.bitangent);
+}
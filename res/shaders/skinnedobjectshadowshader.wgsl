@@ -0,0 +1,19 @@
+//depth of objects whose model has a skeleton, posed the same way skinnedobjectshader.wgsl poses them
+
+#import object_shadow
+
+struct SkinInput {
+    @location(13) joints: vec4<u32>,
+    @location(14) weights: vec4<f32>
+}
+
+@vertex
+fn vs_bake(model: VertexInput, instance: InstanceInput, skin: SkinInput) -> @builtin(position) vec4<f32> {
+    let joints = skin.joints + instance.joint_offset;
+    let skin_matrix = joint_palette[joints.x] * skin.weights.x
+        + joint_palette[joints.y] * skin.weights.y
+        + joint_palette[joints.z] * skin.weights.z
+        + joint_palette[joints.w] * skin.weights.w;
+
+    return shadow_view.view_proj * instance_model_matrix(instance) * skin_matrix * vec4<f32>(model.position, 1.0);
+}
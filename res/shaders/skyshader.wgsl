@@ -0,0 +1,124 @@
+//fullscreen sky drawn before the terrain. every pixel turns back into a world ray through the inverse view projection
+
+#import fullscreen
+#import atmosphere
+
+struct Camera {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>
+}
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct Sky {
+    sun_direction: vec4<f32>,
+    //where the sun will be in six hours, with sun_direction it fixes the stars to the sun's orbit
+    sun_tangent: vec4<f32>,
+    //xy how far the wind has moved the clouds, z cloud coverage 0..1, w seconds for twinkling
+    clouds: vec4<f32>
+}
+
+@group(1) @binding(0)
+var<uniform> sky: Sky;
+
+const SUN_RADIUS = 0.03;
+const MOON_RADIUS = 0.04;
+const CLOUD_ALTITUDE = 320.0;
+const CLOUD_SCALE = 0.0025;
+
+fn hash3(p: vec3<f32>) -> f32 {
+    return fract(sin(dot(p, vec3(127.1, 311.7, 74.7))) * 43758.5453);
+}
+
+fn hash2(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+fn value_noise(p: vec2<f32>) -> f32 {
+    let cell = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+
+    let a = hash2(cell);
+    let b = hash2(cell + vec2(1.0, 0.0));
+    let c = hash2(cell + vec2(0.0, 1.0));
+    let d = hash2(cell + vec2(1.0, 1.0));
+
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+fn fbm(p: vec2<f32>) -> f32 {
+    var total = 0.0;
+    var amplitude = 0.5;
+    var q = p;
+    for (var i = 0; i < 5; i++) {
+        total += value_noise(q) * amplitude;
+        q = q * 2.03 + vec2(17.3, -9.1);
+        amplitude *= 0.5;
+    }
+    return total;
+}
+
+//a star field on a sphere that turns with the sun, at most one star per grid cell
+fn stars(dir: vec3<f32>) -> f32 {
+    let sun = sky.sun_direction.xyz;
+    let tangent = sky.sun_tangent.xyz;
+    let p = vec3(dot(dir, sun), dot(dir, tangent), dot(dir, cross(sun, tangent))) * 180.0;
+
+    let cell = floor(p);
+    if (hash3(cell) < 0.988) {
+        return 0.0;
+    }
+
+    let centre = cell + 0.25 + vec3(hash3(cell + 1.0), hash3(cell + 2.0), hash3(cell + 3.0)) * 0.5;
+    let brightness = hash3(cell + 4.0);
+    let twinkle = 0.75 + 0.25 * sin(sky.clouds.w * (1.5 + brightness * 3.0) + brightness * 40.0);
+
+    return smoothstep(0.3, 0.0, length(p - centre)) * (0.3 + brightness * 1.2) * twinkle;
+}
+
+fn disc(dir: vec3<f32>, centre: vec3<f32>, radius: f32) -> f32 {
+    let angle = acos(clamp(dot(dir, centre), -1.0, 1.0));
+    return smoothstep(radius, radius * 0.85, angle);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    //reversed depth puts the near plane at 1
+    let near = camera.inv_view_proj * vec4(in.ndc, 1.0, 1.0);
+    let dir = normalize(near.xyz / near.w - camera.view_pos.xyz);
+
+    let sun_dir = normalize(sky.sun_direction.xyz);
+    let moon_dir = -sun_dir;
+    let daylight = smoothstep(-0.15, 0.1, sun_dir.y);
+    let horizon = smoothstep(-0.02, 0.03, dir.y);
+
+    var colour = atmosphere(dir, sun_dir);
+
+    colour += vec3(stars(dir)) * (1.0 - daylight) * horizon;
+
+    //faint grey patches so the moon doesn't read as a flat disc
+    let moon_detail = 0.8 + 0.2 * value_noise((dir - moon_dir).xz * 90.0);
+    colour += vec3(0.9, 0.92, 1.0) * 2.0 * moon_detail * disc(dir, moon_dir, MOON_RADIUS) * horizon * (1.0 - daylight * 0.8);
+    colour += transmittance(sun_dir) * 60.0 * disc(dir, sun_dir, SUN_RADIUS) * horizon;
+
+    //a flat layer at a fixed altitude, sampled where the view ray crosses it
+    if (dir.y > 0.0 && camera.view_pos.y < CLOUD_ALTITUDE) {
+        let t = (CLOUD_ALTITUDE - camera.view_pos.y) / dir.y;
+        let p = (camera.view_pos.xz + dir.xz * t + sky.clouds.xy) * CLOUD_SCALE;
+
+        let coverage = sky.clouds.z;
+        let density = smoothstep(1.0 - coverage, 1.0 - coverage + 0.35, fbm(p));
+        //thin out towards the horizon where the layer is very far away
+        let fade = smoothstep(0.0, 0.2, dir.y);
+
+        let lit = transmittance(sun_dir) * daylight * 1.6 + transmittance(moon_dir) * (1.0 - daylight) * 0.04 + vec3(0.01);
+        let cloud = lit * (1.0 - 0.45 * density) + atmosphere(vec3(0.0, 1.0, 0.0), sun_dir) * 0.5;
+
+        colour = mix(colour, cloud, density * fade * 0.9);
+    }
+
+    return vec4(colour, 1.0);
+}
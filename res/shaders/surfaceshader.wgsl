@@ -1,6 +1,6 @@
 //chunk_position_orientation: u32 (4 posx, 4 posy, 4 posz, 3 face_normal, 2 uv_index)
 //textures: (8 diffuse, 8 normal, 8 emissive)
-//separate binding for chunk position!
+//chunk position comes from the mesh pool's slice data, indexed by instance
 
 struct VertexInput {
     @location(0) d0: u32,
@@ -8,10 +8,13 @@ struct VertexInput {
     @location(2) illumination: u32
 }
 
-struct ChunkData {
-    @location(3) position_sliced: vec3<i32>,
+struct SliceData {
+    position_sliced: vec4<i32>,
 }
 
+@group(2) @binding(0)
+var<storage, read> slices: array<SliceData>;
+
 const tex_coords: array<vec2<f32>, 4> = array(
     vec2(1.0, 1.0),
     vec2(0.0, 1.0),
@@ -20,8 +23,9 @@ const tex_coords: array<vec2<f32>, 4> = array(
 );
 
 @vertex
-fn vs_main(vertex: VertexInput, chunk_data: ChunkData) -> VertexOutput {
+fn vs_main(vertex: VertexInput, @builtin(instance_index) slot: u32) -> VertexOutput {
     var out: VertexOutput;
+    let position_sliced = slices[slot].position_sliced.xyz;
 
     var x = i32(extractBits(vertex.d0, 0u, 5u));
     var y = i32(extractBits(vertex.d0, 5u, 5u));
@@ -29,10 +33,9 @@ fn vs_main(vertex: VertexInput, chunk_data: ChunkData) -> VertexOutput {
 
     var normalid = extractBits(vertex.d0, 15u, 3u);
     var uvi = extractBits(vertex.d0, 18u, 2u);
-    var diffuse_texure_index = extractBits(vertex.d1, 0u, 16u);
-
-    var normal_texure_index = 0u;
-    var emissive_texure_index = 0u;
+    var diffuse_texure_index = extractBits(vertex.d1, 0u, 8u);
+    var normal_texure_index = extractBits(vertex.d1, 8u, 8u);
+    var emissive_texure_index = extractBits(vertex.d1, 16u, 8u);
 
     var uv = vec2(select(0.0, 1.0, uvi == 2 || uvi == 0), select(0.0, 1.0, uvi == 1 || uvi == 0));
 
@@ -51,11 +54,12 @@ fn vs_main(vertex: VertexInput, chunk_data: ChunkData) -> VertexOutput {
         case 3u: {
             normal = vec3(-1f, 0f, 0f);
         }
+        //front faces -z, back +z like BlockFace
         case 4u: {
-            normal = vec3(0f, 0f, 1f);
+            normal = vec3(0f, 0f, -1f);
         }
         case 5u: {
-            normal = vec3(0f, 0f, -1f);
+            normal = vec3(0f, 0f, 1f);
         }
         default: {
             normal = vec3(0f, 1f, 0f);
@@ -70,15 +74,15 @@ fn vs_main(vertex: VertexInput, chunk_data: ChunkData) -> VertexOutput {
     out.illumination = vertex.illumination;
     out.clip_position = camera.view_proj * 
     vec4<f32>(
-        f32(chunk_data.position_sliced.x * 16 + x), 
-        f32(chunk_data.position_sliced.y * 16 + y), 
-        f32(chunk_data.position_sliced.z * 16 + z), 
+        f32(position_sliced.x * 16 + x), 
+        f32(position_sliced.y * 16 + y), 
+        f32(position_sliced.z * 16 + z), 
         1.0
     );
     out.worldpos = vec3(
-        f32(chunk_data.position_sliced.x * 16 + x), 
-        f32(chunk_data.position_sliced.y * 16 + y), 
-        f32(chunk_data.position_sliced.z * 16 + z), 
+        f32(position_sliced.x * 16 + x), 
+        f32(position_sliced.y * 16 + y), 
+        f32(position_sliced.z * 16 + z), 
     );
 
     return out;
@@ -95,55 +99,75 @@ struct VertexOutput {
     @location(8) worldpos: vec3<f32>
 };
 
-@group(0) @binding(0)
-var diffuse_texture_array: binding_array<texture_2d<f32>>;
-
-@group(0) @binding(1)
-var diffuse_sampler_array: binding_array<sampler>;
-
-@group(0) @binding(2)
-var normal_texture_array: binding_array<texture_2d<f32>>;
-
-@group(0) @binding(3)
-var normal_sampler_array: binding_array<sampler>;
-
-@group(0) @binding(4)
-var emissive_texture_array: binding_array<texture_2d<f32>>;
-
-@group(0) @binding(5)
-var emissive_sampler_array: binding_array<sampler>;
+#import textures
+#import sun_shadows
+#import lights
+#import fog
 
 struct Camera {
     view_pos: vec4<f32>,
-    view_proj: mat4x4<f32>
+    view_proj: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    screendims: vec4<u32>,
+    fog: Fog
 }
 
 @group(1) @binding(0)
 var<uniform> camera: Camera;
 
+//emissive texels land above 1 in the hdr target so bloom picks them up
+const EMISSIVE_STRENGTH = 4.0;
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     var tileUV: vec2<f32>;
     let absNormal = abs(in.normal);
     var texCoord: vec2<f32>;
 
+    //world directions of +u and of image-up (-v) on this face, for normal maps (green up)
+    var tangent: vec3<f32>;
+    var bitangent: vec3<f32>;
+
     //check which axis it is
     if (absNormal.x > absNormal.y && absNormal.x > absNormal.z) {
         tileUV = vec2(in.worldpos.z, in.worldpos.y);
         texCoord = fract(1 - tileUV);
+        tangent = vec3(0.0, 0.0, -1.0);
+        bitangent = vec3(0.0, 1.0, 0.0);
     } else if (absNormal.y > absNormal.x && absNormal.y > absNormal.z) {
         tileUV = vec2(in.worldpos.x, in.worldpos.z);
         texCoord = fract(tileUV);
+        tangent = vec3(1.0, 0.0, 0.0);
+        bitangent = vec3(0.0, 0.0, -1.0);
     } else {
         tileUV = vec2(in.worldpos.x, in.worldpos.y);
         texCoord = fract(1 - tileUV);
+        tangent = vec3(-1.0, 0.0, 0.0);
+        bitangent = vec3(0.0, 1.0, 0.0);
     }
 
-    let diffuse_color = textureSampleLevel(diffuse_texture_array[in.diffuse_texture_index], diffuse_sampler_array[in.diffuse_texture_index], texCoord, 0.0).rgba;
+    //derivatives of the unwrapped coordinate, fract() jumps at block edges and would pick the smallest mip there
+    let uvDdx = dpdx(tileUV);
+    let uvDdy = dpdy(tileUV);
+
+    let diffuse_color = sample_diffuse_grad(in.diffuse_texture_index, texCoord, uvDdx, uvDdy).rgba;
+    let normal_sample = sample_normal_grad(in.normal_texture_index, texCoord, uvDdx, uvDdy).xyz * 2.0 - 1.0;
+    let emissive_color = sample_emissive_grad(in.emissive_texture_index, texCoord, uvDdx, uvDdy);
+
+    let surface_normal = normalize(tangent * normal_sample.x + bitangent * normal_sample.y + in.normal * normal_sample.z);
+
+    //the default normal map is flat, so this only changes shading where a block has a real one
+    let relief = clamp(1.0 + dot(surface_normal - in.normal, sun.direction.xyz), 0.0, 2.0);
 
     let sunlight = f32(extractBits(in.illumination, 24u, 4u));
 
-    let sunlight_factor = 0.1 + 0.9 * sunlight / 15.0;
+    //shadows only take away direct sun, light that reached here some other way stays
+    let shadow = sun_shadow(in.worldpos, in.normal);
+    let sunlight_factor = (0.1 + 0.9 * sunlight / 15.0 * sun.sky_light.x * mix(0.45, 1.0, shadow)) * relief;
+
+    let local = local_light(in.worldpos, in.normal, surface_normal);
+
+    let lit = diffuse_color.rgb * (sunlight_factor + local) + emissive_color.rgb * emissive_color.a * EMISSIVE_STRENGTH;
 
-    return vec4(diffuse_color.rgb * sunlight_factor * 1.0, diffuse_color.a);
+    return vec4(apply_fog(camera.fog, lit, in.worldpos, camera.view_pos.xyz), diffuse_color.a);
 }
\ No newline at end of file
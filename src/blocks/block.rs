@@ -1,6 +1,6 @@
 use cgmath::Vector3;
 use glam::{ivec3, IVec3};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use core::fmt::Debug;
 use std::ops::BitOrAssign;
 
@@ -21,7 +21,7 @@ pub fn create_block_default(block: Blocks, absolute_position: Vector3<i32>) -> B
     }
 }
 
-#[derive(PartialEq, Eq, Debug, Deserialize, Clone, Copy)]
+#[derive(PartialEq, Eq, Debug, Deserialize, Serialize, Clone, Copy)]
 #[serde(rename_all = "lowercase")]
 pub enum Blocks {
     AIR = 0,
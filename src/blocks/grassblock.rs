@@ -1,6 +1,6 @@
 use cgmath::Vector3;
 
-use crate::engine::texture_loader::get_indices_from_texture;
+use crate::engine::texture_loader::get_texture_set;
 
 use super::block::{Block, BlockFace};
 #[derive(Clone)]
@@ -52,17 +52,17 @@ impl Block for GrassBlock {
 
     fn get_surface_textures(&self, face: super::block::BlockFace) -> (usize, usize, usize) {
         
-        (match face {
+        match face {
             BlockFace::Top => {
-                get_indices_from_texture("grass-top")
+                get_texture_set("grass-top")
             },
             BlockFace::Bottom => {
-                get_indices_from_texture("dirt")
+                get_texture_set("dirt")
             },
             _ => {
-                get_indices_from_texture("grass-side")
+                get_texture_set("grass-side")
             }
-        }, 0, 0)
+        }
     }
 
     fn reset_light(&mut self) {
@@ -1,6 +1,6 @@
 use cgmath::Vector3;
 
-use crate::engine::texture_loader::get_indices_from_texture;
+use crate::engine::texture_loader::get_texture_set;
 
 use super::block::Block;
 #[derive(Clone)]
@@ -53,10 +53,10 @@ impl Block for LogBlock {
     fn get_surface_textures(&self, face: super::block::BlockFace) -> (usize, usize, usize) {
         match face {
             super::block::BlockFace::Top | super::block::BlockFace::Bottom => {
-                (get_indices_from_texture("log_top"), 0, 0)
+                get_texture_set("log_top")
             },
             _ => {
-                (get_indices_from_texture("log"), 0, 0)
+                get_texture_set("log")
             }
         }
     }
@@ -1,6 +1,6 @@
 use cgmath::Vector3;
 
-use crate::engine::texture_loader::get_indices_from_texture;
+use crate::engine::texture_loader::get_texture_set;
 
 use super::block::Block;
 #[derive(Clone)]
@@ -51,7 +51,7 @@ impl Block for StoneBlock {
     }
 
     fn get_surface_textures(&self, face: super::block::BlockFace) -> (usize, usize, usize) {
-        (get_indices_from_texture("stone"), 0, 0)
+        get_texture_set("stone")
     }
     fn reset_light(&mut self) {
         self.lights = [0, 0, 0];
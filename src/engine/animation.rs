@@ -0,0 +1,277 @@
+use std::sync::Arc;
+
+use cgmath::{InnerSpace, Quaternion, Vector3, Vector4, Zero};
+
+use super::{model::Model, skeleton::{Skeleton, Transform}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    //three values per key: in tangent, value, out tangent
+    CubicSpline
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelProperty {
+    Translation,
+    Rotation,
+    Scale
+}
+
+/**
+ keyframes for one property of one node. translations and scales leave w at 0, rotations are xyzw
+*/
+pub struct AnimationChannel {
+    pub node: usize,
+    pub property: ChannelProperty,
+    pub interpolation: Interpolation,
+    pub times: Vec<f32>,
+    pub values: Vec<Vector4<f32>>
+}
+
+impl AnimationChannel {
+    fn value(&self, key: usize) -> Vector4<f32> {
+        match self.interpolation {
+            Interpolation::CubicSpline => self.values[key * 3 + 1],
+            _ => self.values[key]
+        }
+    }
+
+    fn sample(&self, time: f32) -> Vector4<f32> {
+        let last = self.times.len() - 1;
+
+        if time <= self.times[0] {return self.value(0)};
+        if time >= self.times[last] {return self.value(last)};
+
+        let next = self.times.partition_point(|&t| t <= time);
+        let key = next - 1;
+        let span = self.times[next] - self.times[key];
+        let t = (time - self.times[key]) / span;
+
+        match self.interpolation {
+            Interpolation::Step => self.value(key),
+            Interpolation::Linear => {
+                let (a, b) = (self.value(key), self.value(next));
+
+                if self.property == ChannelProperty::Rotation {
+                    nlerp(a, b, t)
+                } else {
+                    a + (b - a) * t
+                }
+            },
+            Interpolation::CubicSpline => {
+                let (t2, t3) = (t * t, t * t * t);
+                let out_tangent = self.values[key * 3 + 2] * span;
+                let in_tangent = self.values[next * 3] * span;
+
+                let value = self.value(key) * (2. * t3 - 3. * t2 + 1.)
+                    + out_tangent * (t3 - 2. * t2 + t)
+                    + self.value(next) * (-2. * t3 + 3. * t2)
+                    + in_tangent * (t3 - t2);
+
+                if self.property == ChannelProperty::Rotation {value.normalize()} else {value}
+            }
+        }
+    }
+}
+
+/**
+ the shorter way round between two rotations
+*/
+fn nlerp(a: Vector4<f32>, b: Vector4<f32>, t: f32) -> Vector4<f32> {
+    let b = if a.dot(b) < 0. {-b} else {b};
+    (a + (b - a) * t).normalize()
+}
+
+fn to_quaternion(v: Vector4<f32>) -> Quaternion<f32> {
+    Quaternion::new(v.w, v.x, v.y, v.z)
+}
+
+fn from_quaternion(q: Quaternion<f32>) -> Vector4<f32> {
+    Vector4::new(q.v.x, q.v.y, q.v.z, q.s)
+}
+
+pub struct AnimationClip {
+    pub name: String,
+    pub duration: f32,
+    pub channels: Vec<AnimationChannel>
+}
+
+impl AnimationClip {
+    /**
+     overwrites the nodes this clip animates, everything else in the pose is left alone
+    */
+    pub fn sample(&self, time: f32, pose: &mut [Transform]) {
+        for channel in &self.channels {
+            let value = channel.sample(time);
+            let transform = &mut pose[channel.node];
+
+            match channel.property {
+                ChannelProperty::Translation => transform.translation = value.truncate(),
+                ChannelProperty::Rotation => transform.rotation = to_quaternion(value),
+                ChannelProperty::Scale => transform.scale = value.truncate()
+            }
+        }
+    }
+}
+
+struct PlayingClip {
+    clip: Arc<AnimationClip>,
+    time: f32,
+    speed: f32,
+    looping: bool,
+    weight: f32,
+    target_weight: f32,
+    //weight per second towards target_weight
+    fade_rate: f32
+}
+
+/**
+ plays a model's clips on one object. any number can run at once, each with a weight that fades towards a target,
+ and the pose is their weighted blend. whatever weight is left below 1 goes to the rest pose
+*/
+pub struct AnimationPlayer {
+    skeleton: Arc<Skeleton>,
+    clips: Vec<Arc<AnimationClip>>,
+    playing: Vec<PlayingClip>
+}
+
+impl AnimationPlayer {
+    pub fn new(model: &Model) -> Option<Self> {
+        Some(Self {
+            skeleton: model.skeleton.clone()?,
+            clips: model.animations.clone(),
+            playing: Vec::new()
+        })
+    }
+
+    /**
+     fades a clip in to full weight over fade seconds while everything else fades out
+    */
+    pub fn play(&mut self, name: &str, fade: f32, looping: bool) -> bool {
+        for playing in self.playing.iter_mut() {
+            playing.fade_to(0., fade);
+        }
+
+        self.blend(name, 1., fade, looping)
+    }
+
+    /**
+     fades a clip to a weight without touching the others, for layering clips on top of each other.
+     a clip that isn't playing yet starts from the beginning at 0
+    */
+    pub fn blend(&mut self, name: &str, weight: f32, fade: f32, looping: bool) -> bool {
+        if let Some(playing) = self.playing.iter_mut().find(|p| p.clip.name == name) {
+            playing.looping = looping;
+            playing.fade_to(weight, fade);
+            return true;
+        }
+
+        let Some(clip) = self.clips.iter().find(|clip| clip.name == name) else {return false};
+
+        let mut playing = PlayingClip {
+            clip: clip.clone(),
+            time: 0.,
+            speed: 1.,
+            looping,
+            weight: 0.,
+            target_weight: 0.,
+            fade_rate: 0.
+        };
+        playing.fade_to(weight, fade);
+        self.playing.push(playing);
+
+        true
+    }
+
+    pub fn stop(&mut self, name: &str, fade: f32) {
+        if let Some(playing) = self.playing.iter_mut().find(|p| p.clip.name == name) {
+            playing.fade_to(0., fade);
+        }
+    }
+
+    pub fn set_speed(&mut self, name: &str, speed: f32) {
+        if let Some(playing) = self.playing.iter_mut().find(|p| p.clip.name == name) {
+            playing.speed = speed;
+        }
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        for playing in self.playing.iter_mut() {
+            playing.time += dt * playing.speed;
+
+            if playing.looping && playing.clip.duration > 0. {
+                playing.time = playing.time.rem_euclid(playing.clip.duration);
+            }
+
+            let step = playing.fade_rate * dt;
+            playing.weight = if playing.weight < playing.target_weight {
+                (playing.weight + step).min(playing.target_weight)
+            } else {
+                (playing.weight - step).max(playing.target_weight)
+            };
+        }
+
+        //faded out clips are forgotten, playing them again starts them over
+        self.playing.retain(|p| p.weight > 0. || p.target_weight > 0.);
+    }
+
+    pub fn pose(&self) -> Vec<Transform> {
+        let rest = self.skeleton.rest_pose();
+        let total: f32 = self.playing.iter().map(|p| p.weight).sum();
+
+        if total <= 0. {return rest};
+
+        let mut translations = vec![Vector3::zero(); rest.len()];
+        let mut rotations = vec![Vector4::zero(); rest.len()];
+        let mut scales = vec![Vector3::zero(); rest.len()];
+
+        let mut accumulate = |pose: &[Transform], weight: f32| {
+            for (i, transform) in pose.iter().enumerate() {
+                let rotation = from_quaternion(transform.rotation);
+
+                translations[i] += transform.translation * weight;
+                scales[i] += transform.scale * weight;
+                let rotation = if rotations[i].dot(rotation) < 0. {-rotation} else {rotation};
+                rotations[i] += rotation * weight;
+            }
+        };
+
+        for playing in self.playing.iter().filter(|p| p.weight > 0.) {
+            let mut pose = rest.clone();
+            playing.clip.sample(playing.time, &mut pose);
+            accumulate(&pose, playing.weight);
+        }
+
+        let rest_weight = (1. - total).max(0.);
+        if rest_weight > 0. {
+            accumulate(&rest, rest_weight);
+        }
+
+        let total = total + rest_weight;
+
+        (0..rest.len()).map(|i| Transform {
+            translation: translations[i] / total,
+            rotation: to_quaternion(rotations[i].normalize()),
+            scale: scales[i] / total
+        }).collect()
+    }
+
+    pub fn palette(&self) -> Vec<[[f32; 4]; 4]> {
+        self.skeleton.palette(&self.pose())
+    }
+}
+
+impl PlayingClip {
+    fn fade_to(&mut self, weight: f32, fade: f32) {
+        self.target_weight = weight;
+
+        if fade <= 0. {
+            self.weight = weight;
+            self.fade_rate = 0.;
+        } else {
+            self.fade_rate = (weight - self.weight).abs() / fade;
+        }
+    }
+}
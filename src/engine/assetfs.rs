@@ -0,0 +1,171 @@
+use std::{env, fs::{self, File}, io::Read, path::{Component, Path, PathBuf}, sync::Mutex};
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use zip::ZipArchive;
+
+/**
+ a single mounted pack. directories are read straight from disk, zips are kept open for the lifetime of the game
+*/
+enum AssetPack {
+    Directory {
+        name: String,
+        root: PathBuf
+    },
+    Zip {
+        name: String,
+        archive: Mutex<ZipArchive<File>>
+    }
+}
+
+impl AssetPack {
+    fn open(path: &Path) -> anyhow::Result<Self> {
+        let name = path.file_name().and_then(|v| v.to_str()).unwrap_or("unnamed pack").to_owned();
+
+        if path.is_dir() {
+            return Ok(AssetPack::Directory {
+                name,
+                root: path.to_path_buf()
+            });
+        }
+
+        let archive = ZipArchive::new(File::open(path)?)?;
+
+        Ok(AssetPack::Zip {
+            name,
+            archive: Mutex::new(archive)
+        })
+    }
+
+    fn read(&self, logical_path: &str) -> Option<Vec<u8>> {
+        match self {
+            AssetPack::Directory { root, .. } => {
+                let full = logical_path.split('/').fold(root.clone(), |acc, part| acc.join(part));
+                fs::read(full).ok()
+            },
+            AssetPack::Zip { archive, .. } => {
+                let mut lock = archive.lock().unwrap();
+                let mut file = lock.by_name(logical_path).ok()?;
+                let mut out = Vec::with_capacity(file.size() as usize);
+                file.read_to_end(&mut out).ok()?;
+                Some(out)
+            }
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        match self {
+            AssetPack::Directory { name, .. } => name,
+            AssetPack::Zip { name, .. } => name
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PackList {
+    packs: Vec<String>
+}
+
+/**
+ resolves logical asset paths ("shaders/surfaceshader.wgsl") against the base pack and any user packs.
+ packs later in packs/packs.json override earlier ones, and all of them override the base pack
+*/
+pub struct AssetFs {
+    packs: Vec<AssetPack>
+}
+
+impl AssetFs {
+    fn mount_default() -> Self {
+        let root = env::current_dir().unwrap();
+
+        let mut packs = vec![AssetPack::Directory {
+            name: "base".to_owned(),
+            root: root.join("res")
+        }];
+
+        let pack_dir = root.join("packs");
+        let pack_list = pack_dir.join("packs.json");
+
+        if let Ok(bytes) = fs::read(&pack_list) {
+            let list: PackList = serde_json::from_slice(&bytes).expect("Invalid packs.json data");
+
+            for pack in list.packs {
+                match AssetPack::open(&pack_dir.join(&pack)) {
+                    Ok(p) => {
+                        println!("Mounted pack {}", p.get_name());
+                        packs.push(p);
+                    },
+                    Err(e) => println!("Unable to mount pack {}: {}", pack, e)
+                }
+            }
+        }
+
+        Self {
+            packs
+        }
+    }
+
+    pub fn read(&self, path: &str) -> anyhow::Result<Vec<u8>> {
+        let logical = normalize_asset_path(path)?;
+
+        self.packs.iter().rev().find_map(|pack| pack.read(&logical))
+            .ok_or_else(|| anyhow::anyhow!("Asset {} was not found in any pack", logical))
+    }
+
+    /**
+     writes into the highest priority directory pack, so read returns the new file afterwards.
+     zips can't be written to, so it fails if one above that pack has the same file
+    */
+    pub fn write(&self, path: &str, bytes: &[u8]) -> anyhow::Result<()> {
+        let logical = normalize_asset_path(path)?;
+
+        for pack in self.packs.iter().rev() {
+            match pack {
+                AssetPack::Directory { root, .. } => {
+                    let full = logical.split('/').fold(root.clone(), |acc, part| acc.join(part));
+                    if let Some(parent) = full.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(full, bytes)?;
+                    return Ok(());
+                },
+                AssetPack::Zip { name, .. } => if pack.read(&logical).is_some() {
+                    anyhow::bail!("Asset {} is overridden by zip pack {}, which can't be written to", logical, name);
+                }
+            }
+        }
+
+        anyhow::bail!("No writable pack is mounted for {}", logical)
+    }
+}
+
+static ASSET_FS: Lazy<AssetFs> = Lazy::new(AssetFs::mount_default);
+
+/**
+ forward slashes only, no "res/" prefix, so the same logical path works in every pack.
+ absolute paths and ".." are refused, they could reach outside of the pack
+*/
+pub fn normalize_asset_path(path: &str) -> anyhow::Result<String> {
+    let path = path.replace('\\', "/");
+
+    if Path::new(&path).components().any(|c| matches!(c, Component::ParentDir | Component::RootDir | Component::Prefix(_))) {
+        anyhow::bail!("Asset path {} must be relative and stay inside its pack", path);
+    }
+
+    Ok(path.strip_prefix("res/").unwrap_or(&path).to_owned())
+}
+
+pub fn read_asset(path: &str) -> anyhow::Result<Vec<u8>> {
+    ASSET_FS.read(path)
+}
+
+pub fn read_asset_to_string(path: &str) -> anyhow::Result<String> {
+    Ok(String::from_utf8(read_asset(path)?)?)
+}
+
+/**
+ where assets created in game (exported structures etc.) go, see AssetFs::write
+*/
+pub fn write_asset(path: &str, bytes: &[u8]) -> anyhow::Result<()> {
+    ASSET_FS.write(path, bytes)
+}
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail};
+use base64::Engine;
+use cgmath::{Matrix, Matrix3, Matrix4, Quaternion, SquareMatrix, Vector3, Vector4};
+use image::RgbaImage;
+
+use super::{animation::{AnimationChannel, AnimationClip, ChannelProperty, Interpolation}, assetfs::read_asset, material::Material, model_loader::{asset_directory, complete_vertices, register_colour_texture, resolve_path, PreloadedMesh, PreloadedModel}, skeleton::{Skeleton, SkeletonNode, Transform}, texture::SamplingOptions, texture_loader::{preload_texture, TextureType}, vertex::{ModelVertex, SkinVertex}};
+
+/**
+ reads a .gltf or .glb with its buffers and images. the default scene's node hierarchy becomes the model's skeleton
+ when there are skins or animations, otherwise every mesh is baked into model space at its node's rest transform
+*/
+pub fn load_gltf(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat, path: &str, sampling: SamplingOptions) -> anyhow::Result<PreloadedModel> {
+    let directory = asset_directory(path);
+    let gltf::Gltf { document, blob } = gltf::Gltf::from_slice(&read_asset(path)?)?;
+
+    let buffers = document.buffers().map(|buffer| {
+        let mut data = match buffer.source() {
+            gltf::buffer::Source::Bin => blob.clone().ok_or_else(|| anyhow!("glb has no binary chunk"))?,
+            gltf::buffer::Source::Uri(uri) => read_uri(directory, uri)?
+        };
+        //the binary chunk is padded to 4 bytes
+        data.truncate(buffer.length());
+        Ok(data)
+    }).collect::<anyhow::Result<Vec<Vec<u8>>>>()?;
+
+    let scene = document.default_scene().or_else(|| document.scenes().next()).ok_or_else(|| anyhow!("no scene"))?;
+
+    //parents first, so global transforms resolve in one pass
+    let mut nodes: Vec<SkeletonNode> = Vec::new();
+    let mut node_indices: HashMap<usize, usize> = HashMap::new();
+    let mut stack: Vec<(gltf::Node, Option<usize>)> = scene.nodes().map(|node| (node, None)).collect();
+
+    while let Some((node, parent)) = stack.pop() {
+        let (translation, rotation, scale) = node.transform().decomposed();
+        let rest = Transform {
+            translation: translation.into(),
+            rotation: Quaternion::new(rotation[3], rotation[0], rotation[1], rotation[2]),
+            scale: scale.into()
+        };
+
+        node_indices.insert(node.index(), nodes.len());
+        stack.extend(node.children().map(|child| (child, Some(nodes.len()))));
+        nodes.push(SkeletonNode::new(node.name().unwrap_or_default().to_owned(), parent, rest));
+    }
+
+    for skin in document.skins() {
+        let reader = skin.reader(|buffer| Some(&buffers[buffer.index()]));
+        let Some(inverse_binds) = reader.read_inverse_bind_matrices() else {continue};
+
+        for (joint, inverse_bind) in skin.joints().zip(inverse_binds) {
+            let Some(&index) = node_indices.get(&joint.index()) else {continue};
+            nodes[index].inverse_bind = Matrix4::from(inverse_bind);
+        }
+    }
+
+    let animations = document.animations().map(|animation| read_animation(&animation, &buffers, &node_indices)).collect::<Vec<AnimationClip>>();
+    let skeleton = Skeleton { nodes };
+    let rest_globals = skeleton.global_transforms(&skeleton.rest_pose());
+    let animated = document.skins().next().is_some() || !animations.is_empty();
+
+    let mut images: HashMap<(usize, &str, [u8; 4]), Option<String>> = HashMap::new();
+    let mut meshes = Vec::new();
+
+    for node in scene.nodes().flat_map(|root| descendants(root)) {
+        let Some(mesh) = node.mesh() else {continue};
+        let index = node_indices[&node.index()];
+
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                println!("Skipping a {:?} primitive of {}, only triangles are supported", primitive.mode(), path);
+                continue;
+            }
+
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let Some(positions) = reader.read_positions() else {continue};
+            let positions = positions.collect::<Vec<[f32; 3]>>();
+            let normals = reader.read_normals().map(|normals| normals.collect::<Vec<[f32; 3]>>());
+            let tex_coords = reader.read_tex_coords(0).map(|uvs| uvs.into_f32().collect::<Vec<[f32; 2]>>());
+
+            let mut indices = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..positions.len() as u32).collect::<Vec<u32>>()
+            };
+
+            //skinned meshes already sit in the bind pose, anything else is moved by its node
+            let (transform, skin) = match (node.skin(), animated) {
+                (Some(skin), _) => (Matrix4::identity(), read_skin(&reader, &skin, &node_indices, positions.len())),
+                //bound to its node with full weight, undoing the node's inverse bind so the palette lands it back in place
+                (None, true) => (skeleton.nodes[index].inverse_bind.invert().unwrap_or(Matrix4::identity()), Some(vec![SkinVertex {
+                    joints: [index as u32, 0, 0, 0],
+                    weights: [1., 0., 0., 0.]
+                }; positions.len()])),
+                (None, false) => (rest_globals[index], None)
+            };
+            let normal_transform = normal_matrix(&transform);
+
+            let mut vertices = positions.iter().enumerate().map(|(i, position)| ModelVertex {
+                position: (transform * Vector4::new(position[0], position[1], position[2], 1.)).truncate().into(),
+                //gltf's uvs already start at the top left
+                tex_coords: tex_coords.as_ref().map_or([0.; 2], |uvs| uvs[i]),
+                normal: normals.as_ref().map_or([0.; 3], |normals| (normal_transform * Vector3::from(normals[i])).into()),
+                tangent: [0.; 3],
+                bitangent: [0.; 3],
+                diffuse_texture_index: 0,
+                normal_texture_index: 0,
+                emissive_texture_index: 0
+            }).collect::<Vec<ModelVertex>>();
+
+            complete_vertices(&mut vertices, &mut indices, normals.is_some());
+
+            let material = primitive.material();
+            let pbr = material.pbr_metallic_roughness();
+            let base_color = pbr.base_color_factor();
+            let emissive = material.emissive_factor();
+
+            let mut sampling = sampling;
+            if material.alpha_mode() == gltf::material::AlphaMode::Mask {
+                sampling.alpha_cutoff = Some(material.alpha_cutoff().unwrap_or(0.5));
+            }
+
+            let mut register = |texture: gltf::Texture, factor: [f32; 4], texture_type: TextureType| {
+                let image = texture.source();
+                let kind = match texture_type {
+                    TextureType::Diffuse => "diffuse",
+                    TextureType::Normal => "normal",
+                    TextureType::Emissive => "emissive"
+                };
+                let key = (image.index(), kind, factor.map(|f| (f.clamp(0., 1.) * 255.).round() as u8));
+
+                images.entry(key).or_insert_with(|| {
+                    let alias = format!("{}#{}{}-{:02x}{:02x}{:02x}{:02x}", path, kind, key.0, key.2[0], key.2[1], key.2[2], key.2[3]);
+
+                    match read_image(directory, &image, &buffers) {
+                        Ok(mut pixels) => {
+                            tint(&mut pixels, factor);
+                            preload_texture(device, queue, format, path, &alias, texture_type, pixels, sampling);
+                            Some(alias)
+                        },
+                        Err(e) => {
+                            println!("Unable to load image {} of {}: {}", image.index(), path, e);
+                            None
+                        }
+                    }
+                }).clone()
+            };
+
+            //factors are baked into the textures, materials without a texture get a texel of the factor
+            let diffuse_texture = pbr.base_color_texture()
+                .and_then(|info| register(info.texture(), base_color, TextureType::Diffuse))
+                .unwrap_or_else(|| register_colour_texture(device, queue, format, [base_color[0], base_color[1], base_color[2]], TextureType::Diffuse));
+
+            let normal_texture = material.normal_texture()
+                .and_then(|info| register(info.texture(), [1.; 4], TextureType::Normal));
+
+            let emissive_texture = match material.emissive_texture() {
+                Some(info) => register(info.texture(), [emissive[0], emissive[1], emissive[2], 1.], TextureType::Emissive),
+                None if emissive.iter().any(|&e| e > 0.) => Some(register_colour_texture(device, queue, format, emissive, TextureType::Emissive)),
+                None => None
+            };
+
+            meshes.push(PreloadedMesh {
+                vertices,
+                indices,
+                skin,
+                diffuse_texture,
+                normal_texture,
+                emissive_texture,
+                material: Material {
+                    name: material.name().unwrap_or_default().to_owned(),
+                    base_color,
+                    metallic: pbr.metallic_factor(),
+                    roughness: pbr.roughness_factor(),
+                    emissive,
+                    double_sided: material.double_sided()
+                }
+            });
+        }
+    }
+
+    if meshes.is_empty() {
+        bail!("no triangle meshes in the default scene");
+    }
+
+    Ok(PreloadedModel {
+        meshes,
+        skeleton: animated.then_some(skeleton),
+        animations
+    })
+}
+
+fn descendants(node: gltf::Node) -> Vec<gltf::Node> {
+    let mut nodes = vec![node.clone()];
+    nodes.extend(node.children().flat_map(descendants));
+    nodes
+}
+
+/**
+ the inverse transpose, so normals stay perpendicular under non uniform scale
+*/
+fn normal_matrix(transform: &Matrix4<f32>) -> Matrix3<f32> {
+    let upper = Matrix3::from_cols(transform.x.truncate(), transform.y.truncate(), transform.z.truncate());
+    upper.invert().map_or(upper, |inverse| inverse.transpose())
+}
+
+/**
+ joint weights with the skin's joint list swapped for skeleton node indices
+*/
+fn read_skin<'a, 's, F>(reader: &gltf::mesh::Reader<'a, 's, F>, skin: &gltf::Skin, node_indices: &HashMap<usize, usize>, vertex_count: usize) -> Option<Vec<SkinVertex>>
+where F: Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]> {
+    let joint_nodes = skin.joints().map(|joint| node_indices.get(&joint.index()).copied().unwrap_or(0) as u32).collect::<Vec<u32>>();
+
+    let (Some(joints), Some(weights)) = (reader.read_joints(0), reader.read_weights(0)) else {
+        println!("A skinned mesh has no joints or weights, it won't move");
+        return None;
+    };
+
+    let skin = joints.into_u16().zip(weights.into_f32()).map(|(joints, weights)| {
+        let total: f32 = weights.iter().sum();
+
+        SkinVertex {
+            joints: joints.map(|joint| joint_nodes.get(joint as usize).copied().unwrap_or(0)),
+            weights: if total > 0. {weights.map(|w| w / total)} else {[1., 0., 0., 0.]}
+        }
+    }).collect::<Vec<SkinVertex>>();
+
+    (skin.len() == vertex_count).then_some(skin)
+}
+
+fn read_animation(animation: &gltf::Animation, buffers: &[Vec<u8>], node_indices: &HashMap<usize, usize>) -> AnimationClip {
+    let channels = animation.channels().filter_map(|channel| {
+        let node = *node_indices.get(&channel.target().node().index())?;
+        let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+        let times = reader.read_inputs()?.collect::<Vec<f32>>();
+
+        let (property, values) = match reader.read_outputs()? {
+            gltf::animation::util::ReadOutputs::Translations(values) =>
+                (ChannelProperty::Translation, values.map(|[x, y, z]| Vector4::new(x, y, z, 0.)).collect::<Vec<_>>()),
+            gltf::animation::util::ReadOutputs::Rotations(values) =>
+                (ChannelProperty::Rotation, values.into_f32().map(Vector4::from).collect()),
+            gltf::animation::util::ReadOutputs::Scales(values) =>
+                (ChannelProperty::Scale, values.map(|[x, y, z]| Vector4::new(x, y, z, 0.)).collect()),
+            gltf::animation::util::ReadOutputs::MorphTargetWeights(_) => return None
+        };
+
+        let interpolation = match channel.sampler().interpolation() {
+            gltf::animation::Interpolation::Step => Interpolation::Step,
+            gltf::animation::Interpolation::Linear => Interpolation::Linear,
+            gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline
+        };
+
+        let per_key = if interpolation == Interpolation::CubicSpline {3} else {1};
+        if times.is_empty() || values.len() != times.len() * per_key {return None};
+
+        Some(AnimationChannel { node, property, interpolation, times, values })
+    }).collect::<Vec<AnimationChannel>>();
+
+    AnimationClip {
+        name: animation.name().map_or_else(|| format!("animation{}", animation.index()), |name| name.to_owned()),
+        duration: channels.iter().filter_map(|channel| channel.times.last().copied()).fold(0., f32::max),
+        channels
+    }
+}
+
+/**
+ a buffer or image uri, either embedded base64 or a file next to the model
+*/
+fn read_uri(directory: &str, uri: &str) -> anyhow::Result<Vec<u8>> {
+    match uri.strip_prefix("data:") {
+        Some(data) => {
+            let (_, encoded) = data.split_once(";base64,").ok_or_else(|| anyhow!("only base64 data uris are supported"))?;
+            Ok(base64::engine::general_purpose::STANDARD.decode(encoded)?)
+        },
+        None => Ok(read_asset(&resolve_path(directory, &uri.replace("%20", " ")))?)
+    }
+}
+
+fn read_image(directory: &str, image: &gltf::Image, buffers: &[Vec<u8>]) -> anyhow::Result<RgbaImage> {
+    let bytes = match image.source() {
+        gltf::image::Source::View { view, .. } => buffers[view.buffer().index()][view.offset()..view.offset() + view.length()].to_vec(),
+        gltf::image::Source::Uri { uri, .. } => read_uri(directory, uri)?
+    };
+
+    Ok(image::load_from_memory(&bytes)?.to_rgba8())
+}
+
+/**
+ multiplies a material factor into its texture. gltf factors are linear, so colour is decoded from srgb around it
+*/
+fn tint(pixels: &mut RgbaImage, factor: [f32; 4]) {
+    if factor == [1.; 4] {return};
+
+    for pixel in pixels.pixels_mut() {
+        for c in 0..3 {
+            let linear = (pixel[c] as f32 / 255.).powf(2.2) * factor[c];
+            pixel[c] = (linear.clamp(0., 1.).powf(1. / 2.2) * 255.).round() as u8;
+        }
+        pixel[3] = (pixel[3] as f32 * factor[3]).round() as u8;
+    }
+}
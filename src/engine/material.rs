@@ -0,0 +1,26 @@
+/**
+ the surface parameters a mesh was loaded with. base colour and emission are already baked into the mesh's textures,
+ these keep the raw values for anything that wants them
+*/
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub name: String,
+    pub base_color: [f32; 4],
+    pub metallic: f32,
+    pub roughness: f32,
+    pub emissive: [f32; 3],
+    pub double_sided: bool
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            base_color: [1.; 4],
+            metallic: 0.,
+            roughness: 1.,
+            emissive: [0.; 3],
+            double_sided: false
+        }
+    }
+}
@@ -1,9 +1,12 @@
-use super::vertex::ModelVertex;
+use super::{material::Material, vertex::ModelVertex};
 
 pub struct Mesh {
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
+    //SkinVertex per vertex, for meshes of models with a skeleton
+    pub skin_buffer: Option<wgpu::Buffer>,
     pub num_elements: u32,
     pub face_indices: Vec<u32>,
-    pub vertices: Vec<ModelVertex>
-}
\ No newline at end of file
+    pub vertices: Vec<ModelVertex>,
+    pub material: Material
+}
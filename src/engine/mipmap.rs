@@ -0,0 +1,107 @@
+use image::{Rgba, RgbaImage};
+
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+fn srgb_to_linear(v: u8) -> f32 {
+    let c = v as f32 / 255.0;
+    if c <= 0.04045 {c / 12.92} else {((c + 0.055) / 1.055).powf(2.4)}
+}
+
+fn linear_to_srgb(c: f32) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    let v = if c <= 0.0031308 {c * 12.92} else {1.055 * c.powf(1.0 / 2.4) - 0.055};
+    (v * 255.0).round() as u8
+}
+
+/**
+ 2x2 box filter. color is weighted by alpha so fully transparent texels (leaf cutouts) don't bleed their
+ rgb into the edges, and srgb textures are averaged in linear space
+*/
+fn downsample(src: &RgbaImage, srgb: bool) -> RgbaImage {
+    let width = (src.width() / 2).max(1);
+    let height = (src.height() / 2).max(1);
+
+    let to_linear = |v: u8| if srgb {srgb_to_linear(v)} else {v as f32 / 255.0};
+    let from_linear = |v: f32| if srgb {linear_to_srgb(v)} else {(v.clamp(0.0, 1.0) * 255.0).round() as u8};
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let mut color = [0.0f32; 3];
+        let mut plain = [0.0f32; 3];
+        let mut alpha = 0.0f32;
+
+        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let sx = (x * 2 + dx).min(src.width() - 1);
+            let sy = (y * 2 + dy).min(src.height() - 1);
+            let p = src.get_pixel(sx, sy);
+            let a = p[3] as f32 / 255.0;
+
+            for c in 0..3 {
+                let l = to_linear(p[c]);
+                color[c] += l * a;
+                plain[c] += l;
+            }
+            alpha += a;
+        }
+
+        let rgb = if alpha > 0.0 {color.map(|c| c / alpha)} else {plain.map(|c| c / 4.0)};
+
+        Rgba([from_linear(rgb[0]), from_linear(rgb[1]), from_linear(rgb[2]), (alpha / 4.0 * 255.0).round() as u8])
+    })
+}
+
+fn alpha_coverage(image: &RgbaImage, cutoff: f32, scale: f32) -> f32 {
+    let passing = image.pixels().filter(|p| (p[3] as f32 / 255.0) * scale >= cutoff).count();
+    passing as f32 / (image.width() * image.height()) as f32
+}
+
+/**
+ raises the alpha of texels below the cutoff until the share of texels passing it matches the base level.
+ without this alpha tested foliage thins out and disappears in the distance. texels already at or above the cutoff
+ keep their alpha, nothing alpha tests so lowering it would make opaque texels see through
+*/
+fn preserve_coverage(image: &mut RgbaImage, cutoff: f32, target: f32) {
+    if alpha_coverage(image, cutoff, 1.0) >= target {return};
+
+    let mut low = 1.0f32;
+    let mut high = 4.0f32;
+
+    for _ in 0..12 {
+        let mid = (low + high) / 2.0;
+        if alpha_coverage(image, cutoff, mid) < target {
+            low = mid;
+        }
+        else {
+            high = mid;
+        }
+    }
+
+    for p in image.pixels_mut() {
+        if (p[3] as f32 / 255.0) < cutoff {
+            p[3] = (p[3] as f32 * high).round().min(255.0) as u8;
+        }
+    }
+}
+
+/**
+ full mip chain down to 1x1, base level included
+*/
+pub fn generate_mip_chain(base: &RgbaImage, srgb: bool, alpha_cutoff: Option<f32>) -> Vec<RgbaImage> {
+    let levels = mip_level_count(base.width(), base.height());
+    let target = alpha_cutoff.map(|cutoff| alpha_coverage(base, cutoff, 1.0));
+
+    let mut chain = vec![base.clone()];
+
+    for _ in 1..levels {
+        let mut next = downsample(chain.last().unwrap(), srgb);
+
+        if let (Some(cutoff), Some(target)) = (alpha_cutoff, target) {
+            preserve_coverage(&mut next, cutoff, target);
+        }
+
+        chain.push(next);
+    }
+
+    chain
+}
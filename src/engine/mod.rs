@@ -4,4 +4,11 @@ pub mod texture_loader;
 pub mod vertex;
 pub mod surfacevertex;
 pub mod model;
-pub mod mesh;
\ No newline at end of file
+pub mod mesh;
+pub mod assetfs;
+pub mod mipmap;
+pub mod model_loader;
+pub mod gltf_loader;
+pub mod material;
+pub mod skeleton;
+pub mod animation;
\ No newline at end of file
@@ -1,7 +1,20 @@
 use std::sync::Arc;
 
-use super::mesh::Mesh;
+use super::{animation::AnimationClip, mesh::Mesh, skeleton::Skeleton};
 
 pub struct Model {
     pub meshes: Vec<Arc<Mesh>>,
-}
\ No newline at end of file
+    //only models with skins or animations have one, their meshes all carry joint weights
+    pub skeleton: Option<Arc<Skeleton>>,
+    pub animations: Vec<Arc<AnimationClip>>
+}
+
+impl Model {
+    pub fn new(meshes: Vec<Arc<Mesh>>) -> Self {
+        Self {
+            meshes,
+            skeleton: None,
+            animations: Vec::new()
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use std::{collections::HashMap, io::{BufReader, Cursor}, path::Path, sync::{Arc, Mutex}};
+
+use cgmath::{InnerSpace, Vector3};
+use image::RgbaImage;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use wgpu::util::DeviceExt;
+
+use super::{animation::AnimationClip, assetfs::read_asset, gltf_loader::load_gltf, material::Material, mesh::Mesh, model::Model, skeleton::Skeleton, texture::SamplingOptions, texture_loader::{get_indices_from_texture, preload_texture, SerFilterMode, TextureType}, vertex::{calculate_tangents_inplace_modelvertex, ModelVertex, SkinVertex}};
+
+#[derive(Deserialize)]
+struct ModelLoadData {
+    #[serde(rename(deserialize = "path"))]
+    model_path: String,
+    alias: String,
+    //for the textures its materials use
+    #[serde(default = "default_filter")]
+    filter: SerFilterMode
+}
+
+fn default_filter() -> SerFilterMode {
+    SerFilterMode::Linear
+}
+
+/**
+ one mesh on the cpu, its textures are registered but don't have indices yet
+*/
+pub struct PreloadedMesh {
+    pub vertices: Vec<ModelVertex>,
+    pub indices: Vec<u32>,
+    //one per vertex, for models with a skeleton
+    pub skin: Option<Vec<SkinVertex>>,
+    pub diffuse_texture: String,
+    pub normal_texture: Option<String>,
+    pub emissive_texture: Option<String>,
+    pub material: Material
+}
+
+pub struct PreloadedModel {
+    pub meshes: Vec<PreloadedMesh>,
+    pub skeleton: Option<Skeleton>,
+    pub animations: Vec<AnimationClip>
+}
+
+static LOADED_MODELS: Lazy<Mutex<HashMap<String, Arc<Model>>>> = Lazy::new(|| {
+    let m = HashMap::new();
+    Mutex::new(m)
+});
+
+/**
+ a model from the manifest by alias, once initialize_load_models has run
+*/
+pub fn get_model(alias: &str) -> Option<Arc<Model>> {
+    LOADED_MODELS.lock().unwrap().get(alias).cloned()
+}
+
+/**
+ parses every model in data/model_manifest.json, obj or gltf by extension. the textures their materials use are
+ registered with the texture loader, so this has to run between preload_textures and initialize_load_textures.
+ models that can't be read are skipped
+*/
+pub fn preload_models(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Vec<(String, PreloadedModel)> {
+    let manifest = read_asset("data/model_manifest.json").expect("Unable to load model_manifest file");
+    let data: Vec<ModelLoadData> = serde_json::from_slice(&manifest).expect("Invalid model_manifest data");
+
+    data.into_iter().filter_map(|definition| {
+        let sampling = SamplingOptions {
+            filter: definition.filter.into(),
+            mipmaps: true,
+            alpha_cutoff: None,
+            anisotropy: 1
+        };
+
+        let model = if definition.model_path.ends_with(".gltf") || definition.model_path.ends_with(".glb") {
+            load_gltf(device, queue, format, &definition.model_path, sampling)
+        } else {
+            load_obj(device, queue, format, &definition.model_path, sampling)
+        };
+
+        match model {
+            Ok(model) => Some((definition.alias, model)),
+            Err(e) => {
+                println!("Unable to load model {}: {}", definition.model_path, e);
+                None
+            }
+        }
+    }).collect()
+}
+
+/**
+ uploads the preloaded models now that their textures have indices, after which get_model finds them
+*/
+pub fn initialize_load_models(device: &wgpu::Device, models: Vec<(String, PreloadedModel)>) {
+    let mut lock = LOADED_MODELS.lock().unwrap();
+
+    for (alias, model) in models {
+        let meshes = model.meshes.into_iter().map(|mesh| {
+            let PreloadedMesh { mut vertices, indices, skin, diffuse_texture, normal_texture, emissive_texture, material } = mesh;
+
+            let diffuse_texture_index = get_indices_from_texture(&diffuse_texture) as u32;
+            let normal_texture_index = normal_texture.map_or(0, |t| get_indices_from_texture(&t) as u32);
+            let emissive_texture_index = emissive_texture.map_or(0, |t| get_indices_from_texture(&t) as u32);
+
+            for vertex in vertices.iter_mut() {
+                vertex.diffuse_texture_index = diffuse_texture_index;
+                vertex.normal_texture_index = normal_texture_index;
+                vertex.emissive_texture_index = emissive_texture_index;
+            }
+
+            let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{} Vertex Buffer", alias)),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+            let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{} Index Buffer", alias)),
+                contents: bytemuck::cast_slice(&indices),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+
+            let skin_buffer = skin.map(|skin| device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{} Skin Buffer", alias)),
+                contents: bytemuck::cast_slice(&skin),
+                usage: wgpu::BufferUsages::VERTEX,
+            }));
+
+            Arc::new(Mesh {
+                vertex_buffer,
+                index_buffer,
+                skin_buffer,
+                num_elements: indices.len() as u32,
+                face_indices: indices,
+                vertices,
+                material
+            })
+        }).collect();
+
+        lock.insert(alias, Arc::new(Model {
+            meshes,
+            skeleton: model.skeleton.map(Arc::new),
+            animations: model.animations.into_iter().map(Arc::new).collect()
+        }));
+    }
+}
+
+fn load_obj(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat, path: &str, sampling: SamplingOptions) -> anyhow::Result<PreloadedModel> {
+    let directory = asset_directory(path);
+    let bytes = read_asset(path)?;
+
+    let (models, materials) = tobj::load_obj_buf(
+        &mut BufReader::new(Cursor::new(bytes)),
+        &tobj::LoadOptions {
+            single_index: true,
+            triangulate: true,
+            ..Default::default()
+        },
+        |mtl_path: &Path| {
+            let path = resolve_path(directory, &mtl_path.to_string_lossy());
+            let bytes = read_asset(&path).map_err(|_| tobj::LoadError::OpenFileFailed)?;
+            tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(bytes)))
+        }
+    )?;
+
+    //a missing mtl file leaves every mesh with the plain grey default
+    let materials = materials.unwrap_or_else(|e| {
+        println!("Unable to load the materials of {}: {}", path, e);
+        Vec::new()
+    });
+
+    let meshes = models.into_iter().map(|model| {
+        let material = model.mesh.material_id.and_then(|id| materials.get(id));
+        let diffuse = material.map_or([0.8; 3], |m| m.diffuse);
+
+        let diffuse_texture = material
+            .and_then(|m| register_material_texture(device, queue, format, directory, &m.diffuse_texture, TextureType::Diffuse, sampling))
+            .unwrap_or_else(|| register_colour_texture(device, queue, format, diffuse, TextureType::Diffuse));
+
+        let normal_texture = material
+            .and_then(|m| register_material_texture(device, queue, format, directory, &m.normal_texture, TextureType::Normal, sampling));
+
+        let (vertices, indices) = build_vertices(&model.mesh);
+
+        PreloadedMesh {
+            vertices,
+            indices,
+            skin: None,
+            diffuse_texture,
+            normal_texture,
+            emissive_texture: None,
+            material: Material {
+                name: material.map_or(String::new(), |m| m.name.clone()),
+                base_color: [diffuse[0], diffuse[1], diffuse[2], 1.],
+                ..Default::default()
+            }
+        }
+    }).collect();
+
+    Ok(PreloadedModel { meshes, skeleton: None, animations: Vec::new() })
+}
+
+fn build_vertices(mesh: &tobj::Mesh) -> (Vec<ModelVertex>, Vec<u32>) {
+    let mut indices = mesh.indices.clone();
+
+    let mut vertices: Vec<ModelVertex> = (0..mesh.positions.len() / 3).map(|i| ModelVertex {
+        position: [mesh.positions[i * 3], mesh.positions[i * 3 + 1], mesh.positions[i * 3 + 2]],
+        //obj v goes up the image, wgpu's goes down
+        tex_coords: if mesh.texcoords.is_empty() {[0.0, 0.0]} else {[mesh.texcoords[i * 2], 1.0 - mesh.texcoords[i * 2 + 1]]},
+        normal: if mesh.normals.is_empty() {[0.0; 3]} else {[mesh.normals[i * 3], mesh.normals[i * 3 + 1], mesh.normals[i * 3 + 2]]},
+        tangent: [0.0; 3],
+        bitangent: [0.0; 3],
+        diffuse_texture_index: 0,
+        normal_texture_index: 0,
+        emissive_texture_index: 0
+    }).collect();
+
+    complete_vertices(&mut vertices, &mut indices, !mesh.normals.is_empty());
+
+    (vertices, indices)
+}
+
+/**
+ fills in what model files tend to leave out: smooth normals from the faces around each vertex when there are none,
+ and tangents for normal mapping
+*/
+pub fn complete_vertices(vertices: &mut Vec<ModelVertex>, indices: &mut Vec<u32>, has_normals: bool) {
+    if !has_normals {
+        for c in indices.chunks(3) {
+            let [a, b, d] = [c[0], c[1], c[2]].map(|i| Vector3::from(vertices[i as usize].position));
+            let face = (b - a).cross(d - a);
+
+            for &i in c {
+                vertices[i as usize].normal = (Vector3::from(vertices[i as usize].normal) + face).into();
+            }
+        }
+
+        for vertex in vertices.iter_mut() {
+            vertex.normal = Vector3::from(vertex.normal).normalize().into();
+        }
+    }
+
+    calculate_tangents_inplace_modelvertex(vertices, indices);
+
+    //triangles with no uv area leave nan tangents, any direction along the surface will do for those
+    for vertex in vertices.iter_mut() {
+        let normal = Vector3::from(vertex.normal);
+        if !Vector3::from(vertex.tangent).magnitude2().is_finite() {
+            let other = if normal.x.abs() < 0.9 {Vector3::unit_x()} else {Vector3::unit_y()};
+            let tangent = normal.cross(other).normalize();
+            vertex.tangent = tangent.into();
+            vertex.bitangent = normal.cross(tangent).into();
+        }
+    }
+}
+
+/**
+ the alias of a texture a material names, loaded now if nothing has used it yet. none if there's no texture or it can't be read
+*/
+fn register_material_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    format: wgpu::TextureFormat,
+    directory: &str,
+    texture: &str,
+    texture_type: TextureType,
+    sampling: SamplingOptions
+) -> Option<String> {
+    if texture.is_empty() {return None};
+
+    let path = resolve_path(directory, texture);
+
+    let pixels = match read_asset(&path).and_then(|bytes| Ok(image::load_from_memory(&bytes)?.to_rgba8())) {
+        Ok(pixels) => pixels,
+        Err(e) => {
+            println!("Unable to load material texture {}: {}", path, e);
+            return None;
+        }
+    };
+
+    preload_texture(device, queue, format, &path, &path, texture_type, pixels, sampling);
+
+    Some(path)
+}
+
+/**
+ materials with only a colour get a texel of it, so they draw like any other textured mesh
+*/
+pub fn register_colour_texture(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat, colour: [f32; 3], texture_type: TextureType) -> String {
+    //diffuse and emissive textures are both srgb
+    let [r, g, b] = colour.map(|c| (c.clamp(0.0, 1.0).powf(1.0 / 2.2) * 255.0).round() as u8);
+    let prefix = if matches!(texture_type, TextureType::Emissive) {"emissive"} else {"colour"};
+    let alias = format!("{}-{:02x}{:02x}{:02x}", prefix, r, g, b);
+
+    preload_texture(device, queue, format, "", &alias, texture_type, RgbaImage::from_pixel(1, 1, image::Rgba([r, g, b, 255])), SamplingOptions::new(wgpu::FilterMode::Nearest));
+
+    alias
+}
+
+pub fn asset_directory(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(directory, _)| directory)
+}
+
+/**
+ paths inside model files are relative to the file, with the odd "./" in front
+*/
+pub fn resolve_path(directory: &str, path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let path = path.trim_start_matches("./");
+
+    if directory.is_empty() {path.to_owned()} else {format!("{}/{}", directory, path)}
+}
@@ -0,0 +1,86 @@
+use cgmath::{Matrix4, One, Quaternion, SquareMatrix, Vector3, Zero};
+
+/**
+ a node's local translation, rotation and scale, the form animation clips are sampled and blended in
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>
+}
+
+impl Transform {
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Vector3::zero(),
+            rotation: Quaternion::one(),
+            scale: Vector3::new(1., 1., 1.)
+        }
+    }
+}
+
+pub struct SkeletonNode {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub rest: Transform,
+    //model space to the node's space in the bind pose. identity for nodes that aren't joints
+    pub inverse_bind: Matrix4<f32>
+}
+
+impl SkeletonNode {
+    pub fn new(name: String, parent: Option<usize>, rest: Transform) -> Self {
+        Self {
+            name,
+            parent,
+            rest,
+            inverse_bind: Matrix4::identity()
+        }
+    }
+}
+
+/**
+ a model's node hierarchy. nodes are stored parents first, so one pass over them resolves every global transform.
+ skinned meshes index joints by node, and meshes on plain nodes are bound to their node with full weight
+*/
+pub struct Skeleton {
+    pub nodes: Vec<SkeletonNode>
+}
+
+impl Skeleton {
+    pub fn rest_pose(&self) -> Vec<Transform> {
+        self.nodes.iter().map(|node| node.rest).collect()
+    }
+
+    /**
+     model space transforms of every node for a pose of local ones
+    */
+    pub fn global_transforms(&self, pose: &[Transform]) -> Vec<Matrix4<f32>> {
+        let mut globals: Vec<Matrix4<f32>> = Vec::with_capacity(self.nodes.len());
+
+        for (node, local) in self.nodes.iter().zip(pose) {
+            let global = match node.parent {
+                Some(parent) => globals[parent] * local.matrix(),
+                None => local.matrix()
+            };
+            globals.push(global);
+        }
+
+        globals
+    }
+
+    /**
+     the matrices the skinned vertex shader reads for a pose, one per node
+    */
+    pub fn palette(&self, pose: &[Transform]) -> Vec<[[f32; 4]; 4]> {
+        self.global_transforms(pose).iter().zip(&self.nodes).map(|(global, node)| (global * node.inverse_bind).into()).collect()
+    }
+}
@@ -1,13 +1,7 @@
-use image::ImageBuffer;
+use image::{ImageBuffer, RgbaImage};
 use wgpu::{TextureFormat, TextureViewDescriptor};
 
-pub fn load_binary_sync(file_name: &str) -> std::io::Result<Vec<u8>> {
-    let path = std::path::Path::new(env!("OUT_DIR"))
-        .join("res")
-        .join(file_name);
-    let data = std::fs::read(path)?;
-    Ok(data)
-}
+use super::mipmap::{generate_mip_chain, mip_level_count};
 
 pub struct Texture {
     pub texture: wgpu::Texture,
@@ -16,53 +10,6 @@ pub struct Texture {
 }
 
 impl Texture {
-    pub fn from_empty_shadows(
-        label: &str,
-        device: &wgpu::Device,
-        format: TextureFormat,
-        width: u32,
-        height: u32,
-        filter: wgpu::FilterMode,
-        max_lights: u32
-    ) -> Self {
-        let size = wgpu::Extent3d {
-            width,
-            height,
-            depth_or_array_layers: max_lights,
-        };
-
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some(label),
-            size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
-            view_formats: &[]
-        });
-
-        let view = texture.create_view(&TextureViewDescriptor::default());
-
-        let sampler = device.create_sampler(
-            &wgpu::SamplerDescriptor {
-                address_mode_u: wgpu::AddressMode::ClampToEdge,
-                address_mode_v: wgpu::AddressMode::ClampToEdge,
-                address_mode_w: wgpu::AddressMode::ClampToEdge,
-                mag_filter: wgpu::FilterMode::Linear,
-                min_filter: wgpu::FilterMode::Linear,
-                mipmap_filter: wgpu::FilterMode::Nearest,
-                compare: None, //TODO: JUST IN CASE, THIS WAS A CHANGE!
-                ..Default::default()
-            }
-        );
-
-        Self {
-            texture,
-            view,
-            sampler
-        }
-    }
     pub fn from_empty(
         label: &str,
         device: &wgpu::Device,
@@ -165,62 +112,100 @@ impl Texture {
         }
     }
 
-    pub fn from_bytes(
+    pub fn from_rgba(
         label: &str,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         format: TextureFormat,
-        bytes: &[u8],
-        filter: wgpu::FilterMode
+        rgba: &RgbaImage,
+        sampling: &SamplingOptions
     ) -> Self {
-        let rgba = image::load_from_memory(bytes).unwrap();
+        Self::from_layers(label, device, queue, format, &[(rgba, sampling.alpha_cutoff)], rgba.width(), rgba.height(), sampling, false)
+    }
+
+    /**
+     packs images into the layers of one texture sharing a single sampler, with a mip chain when sampling asks for one.
+     images that don't match width x height are resized to fit. as_array makes the view a D2Array even for one layer.
+     each image brings its own alpha cutoff, sampling's is ignored
+    */
+    pub fn from_layers(
+        label: &str,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: TextureFormat,
+        layers: &[(&RgbaImage, Option<f32>)],
+        width: u32,
+        height: u32,
+        sampling: &SamplingOptions,
+        as_array: bool
+    ) -> Self {
+        let mip_level_count = if sampling.mipmaps {mip_level_count(width, height)} else {1};
 
         let size = wgpu::Extent3d {
-            width: rgba.width(),
-            height: rgba.height(),
-            depth_or_array_layers: 1,
+            width,
+            height,
+            depth_or_array_layers: layers.len() as u32,
         };
 
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some(label),
             size,
-            mip_level_count: 1,
+            mip_level_count,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[]
         });
 
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                aspect: wgpu::TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            }, 
-            &rgba.to_rgba8(),
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * size.width),
-                rows_per_image: Some(size.height),
-            },
-            size
-        );
+        for (i, (layer, alpha_cutoff)) in layers.iter().enumerate() {
+            let resized;
+            let pixels = if layer.width() == width && layer.height() == height {
+                *layer
+            }
+            else {
+                //nearest keeps pixel art crisp when scaling up, shrinking needs to average
+                let filter = if layer.width() > width || layer.height() > height {image::imageops::FilterType::Triangle} else {image::imageops::FilterType::Nearest};
+                resized = image::imageops::resize(*layer, width, height, filter);
+                &resized
+            };
 
-        let view = texture.create_view(&TextureViewDescriptor::default());
+            let chain = if sampling.mipmaps {
+                generate_mip_chain(pixels, format.is_srgb(), *alpha_cutoff)
+            }
+            else {
+                vec![pixels.clone()]
+            };
 
-        let sampler = device.create_sampler(
-            &wgpu::SamplerDescriptor {
-                address_mode_u: wgpu::AddressMode::Repeat,
-                address_mode_v: wgpu::AddressMode::Repeat,
-                address_mode_w: wgpu::AddressMode::Repeat,
-                mag_filter: filter,
-                min_filter: filter,
-                mipmap_filter: filter,
-                ..Default::default()
+            for (level, mip) in chain.iter().enumerate() {
+                queue.write_texture(
+                    wgpu::ImageCopyTexture {
+                        aspect: wgpu::TextureAspect::All,
+                        texture: &texture,
+                        mip_level: level as u32,
+                        origin: wgpu::Origin3d { x: 0, y: 0, z: i as u32 },
+                    },
+                    mip,
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(4 * mip.width()),
+                        rows_per_image: Some(mip.height()),
+                    },
+                    wgpu::Extent3d {
+                        width: mip.width(),
+                        height: mip.height(),
+                        depth_or_array_layers: 1
+                    }
+                );
             }
-        );
+        }
+
+        let view = texture.create_view(&TextureViewDescriptor {
+            dimension: Some(if as_array {wgpu::TextureViewDimension::D2Array} else {wgpu::TextureViewDimension::D2}),
+            ..Default::default()
+        });
+
+        let sampler = sampling.create_sampler(device);
 
         Self {
             texture,
@@ -228,4 +213,45 @@ impl Texture {
             sampler
         }
     }
+}
+
+/**
+ per texture sampling settings from the texture manifest
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct SamplingOptions {
+    pub filter: wgpu::FilterMode,
+    pub mipmaps: bool,
+    //alpha test threshold to keep coverage for across mips, for cutout textures like leaves
+    pub alpha_cutoff: Option<f32>,
+    pub anisotropy: u16
+}
+
+impl SamplingOptions {
+    pub fn new(filter: wgpu::FilterMode) -> Self {
+        Self {
+            filter,
+            mipmaps: false,
+            alpha_cutoff: None,
+            anisotropy: 1
+        }
+    }
+
+    pub fn create_sampler(&self, device: &wgpu::Device) -> wgpu::Sampler {
+        //wgpu only allows anisotropy when every filter is linear
+        let anisotropic = self.anisotropy > 1 && self.filter == wgpu::FilterMode::Linear;
+
+        device.create_sampler(
+            &wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::Repeat,
+                address_mode_v: wgpu::AddressMode::Repeat,
+                address_mode_w: wgpu::AddressMode::Repeat,
+                mag_filter: self.filter,
+                min_filter: self.filter,
+                mipmap_filter: if self.mipmaps {wgpu::FilterMode::Linear} else {self.filter},
+                anisotropy_clamp: if anisotropic {self.anisotropy.min(16)} else {1},
+                ..Default::default()
+            }
+        )
+    }
 }
\ No newline at end of file
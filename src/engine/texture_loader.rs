@@ -1,9 +1,10 @@
-use std::{collections::HashMap, env, fs::File, io::BufReader, num::NonZeroU32, path::Path, sync::{Arc, Mutex}};
+use std::{collections::HashMap, num::NonZeroU32, sync::{Arc, Mutex}};
+use image::RgbaImage;
 use once_cell::sync::Lazy;
 use serde::Deserialize;
-use wgpu::{FilterMode, Sampler, TextureView};
+use wgpu::{util::DeviceExt, FilterMode, Sampler, TextureView};
 
-use super::texture::{load_binary_sync, Texture};
+use super::{assetfs::read_asset, texture::{SamplingOptions, Texture}};
 
 #[derive(Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -13,6 +14,9 @@ pub enum TextureType {
     Emissive
 }
 
+//surface and lod vertices pack texture indices into 8 bits, so manifest textures (the ones blocks use) can't go past this
+const MAX_BLOCK_TEXTURE_INDEX: u32 = 255;
+
 static TEXTURE_INDICES: Lazy<Mutex<HashMap<String, usize>>> = Lazy::new(|| {
     let m = HashMap::new();
     Mutex::new(m)
@@ -22,15 +26,73 @@ pub fn get_indices_from_texture(texture: &str) -> usize {
     *TEXTURE_INDICES.lock().unwrap().get(&texture.to_owned()).unwrap()
 }
 
-#[derive(Deserialize)]
-struct ModelLoadData {
-    #[serde(rename(deserialize = "path"))]
-    model_path: String,
-    alias: String,
+static TEXTURE_SETS: Lazy<Mutex<HashMap<String, (usize, usize, usize)>>> = Lazy::new(|| {
+    let m = HashMap::new();
+    Mutex::new(m)
+});
+
+/**
+ (diffuse, normal, emissive) indices for a diffuse texture, using the normal_map and emissive_map it names in the manifest.
+ missing maps fall back to the flat default normal and no emission
+*/
+pub fn get_texture_set(diffuse: &str) -> (usize, usize, usize) {
+    *TEXTURE_SETS.lock().unwrap().get(diffuse).unwrap()
 }
-#[derive(Deserialize)]
+
+/**
+ how surface textures are bound. binding arrays need TEXTURE_BINDING_ARRAY and non-uniform indexing,
+ texture arrays work everywhere but every texture of a type gets stretched to the same size
+*/
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
-enum SerFilterMode {
+pub enum TextureBindingMode {
+    BindingArray,
+    TextureArray
+}
+
+impl TextureBindingMode {
+    pub fn detect(adapter: &wgpu::Adapter) -> Self {
+        let needed = Self::BindingArray.required_features();
+
+        if adapter.features().contains(needed) && adapter.limits().max_sampled_textures_per_shader_stage >= MAX_BINDING_ARRAY_TEXTURES {
+            TextureBindingMode::BindingArray
+        }
+        else {
+            TextureBindingMode::TextureArray
+        }
+    }
+
+    pub fn required_features(&self) -> wgpu::Features {
+        match self {
+            TextureBindingMode::BindingArray => wgpu::Features::TEXTURE_BINDING_ARRAY | wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING,
+            TextureBindingMode::TextureArray => wgpu::Features::empty()
+        }
+    }
+
+    /**
+     the shader include that declares the group 0 texture bindings and the sample_* helpers for this mode
+    */
+    pub fn shader_include(&self) -> &'static str {
+        match self {
+            TextureBindingMode::BindingArray => "shaders/include/textures_binding_array.wgsl",
+            TextureBindingMode::TextureArray => "shaders/include/textures_texture_array.wgsl"
+        }
+    }
+}
+
+pub const MAX_BINDING_ARRAY_TEXTURES: u32 = 961;
+
+static TEXTURE_BINDING_MODE: Lazy<Mutex<TextureBindingMode>> = Lazy::new(|| {
+    Mutex::new(TextureBindingMode::BindingArray)
+});
+
+pub fn get_texture_binding_mode() -> TextureBindingMode {
+    *TEXTURE_BINDING_MODE.lock().unwrap()
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SerFilterMode {
     Linear,
     Nearest
 }
@@ -51,14 +113,117 @@ struct TextureLoadData {
     alias: String,
     #[serde(rename(deserialize = "type"))]
     texture_type: TextureType,
-    filter: SerFilterMode
+    filter: SerFilterMode,
+    #[serde(default)]
+    mipmaps: bool,
+    #[serde(default)]
+    alpha_cutoff: Option<f32>,
+    #[serde(default = "default_anisotropy")]
+    anisotropy: u16,
+    #[serde(default)]
+    animation: Option<TextureAnimation>,
+    //companion maps for diffuse textures, by alias
+    #[serde(default)]
+    normal_map: Option<String>,
+    #[serde(default)]
+    emissive_map: Option<String>
+}
+
+fn default_anisotropy() -> u16 {
+    1
+}
+
+/**
+ flip-book animation, the png is a vertical strip of equally sized frames
+*/
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct TextureAnimation {
+    pub frames: u32,
+    //seconds each frame is shown
+    pub frame_time: f32,
+    #[serde(default)]
+    pub interpolate: bool
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct RawTextureAnimation {
+    frames: u32,
+    frame_time: f32,
+    interpolate: u32,
+    _padding: u32
+}
+
+impl RawTextureAnimation {
+    fn still() -> Self {
+        Self {
+            frames: 1,
+            frame_time: 1.0,
+            interpolate: 0,
+            _padding: 0
+        }
+    }
+}
+
+impl From<&TextureAnimation> for RawTextureAnimation {
+    fn from(value: &TextureAnimation) -> Self {
+        Self {
+            frames: value.frames,
+            frame_time: value.frame_time,
+            interpolate: value.interpolate as u32,
+            _padding: 0
+        }
+    }
+}
+
+/**
+ seconds since startup, written every frame so animated textures advance without remeshing
+*/
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TextureAnimationGlobals {
+    pub time: f32,
+    _padding: [f32; 3]
+}
+
+impl TextureAnimationGlobals {
+    pub fn new(time: f32) -> Self {
+        Self {
+            time,
+            _padding: [0.0; 3]
+        }
+    }
+}
+
+fn split_frames(strip: RgbaImage, animation: &Option<TextureAnimation>, alias: &str) -> Vec<RgbaImage> {
+    let frames = match animation {
+        Some(a) if a.frames > 1 => a.frames,
+        _ => return vec![strip]
+    };
+
+    let frame_height = strip.height() / frames;
+
+    if frame_height == 0 || strip.height() % frames != 0 {
+        println!("Texture {} is {} pixels tall which doesn't split into {} frames, using it as a still", alias, strip.height(), frames);
+        return vec![strip];
+    }
+
+    (0..frames).map(|i| image::imageops::crop_imm(&strip, 0, i * frame_height, strip.width(), frame_height).to_image()).collect()
 }
 
 pub struct LoadedTextureData {
     pub path: String,
     pub alias: String,
     pub texture_type: TextureType,
-    pub texture: Arc<Texture>
+    pub sampling: SamplingOptions,
+    pub animation: Option<TextureAnimation>,
+    pub normal_map: Option<String>,
+    pub emissive_map: Option<String>,
+    //from the texture manifest rather than a model, these get the lowest indices so blocks can use them
+    pub from_manifest: bool,
+    //one entry per animation frame, stills have one
+    pub pixels: Vec<RgbaImage>,
+    pub textures: Vec<Arc<Texture>>
 }
 
 pub static LOADED_TEXTURES: Lazy<Mutex<HashMap<String, LoadedTextureData>>> = Lazy::new(|| {
@@ -66,36 +231,102 @@ pub static LOADED_TEXTURES: Lazy<Mutex<HashMap<String, LoadedTextureData>>> = La
     Mutex::new(m)
 });
 
+/**
+ normal maps hold vectors, not colors, so they skip the srgb conversion
+*/
+fn format_for_type(texture_type: &TextureType, format: wgpu::TextureFormat) -> wgpu::TextureFormat {
+    match texture_type {
+        TextureType::Normal => format.remove_srgb_suffix(),
+        _ => format
+    }
+}
+
 pub fn preload_textures(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     format: wgpu::TextureFormat
 ) {
-    let mut tex = env::current_dir().unwrap();
-    tex.push("res/data/texture_manifest.json");
-
-    let file = File::open(tex).expect("Unable to load model_manifest file");
-    let reader = BufReader::new(file);
-    let data: Vec<TextureLoadData> = serde_json::from_reader(reader).expect("Invalid model_manifest data");
+    let manifest = read_asset("data/texture_manifest.json").expect("Unable to load texture_manifest file");
+    let data: Vec<TextureLoadData> = serde_json::from_slice(&manifest).expect("Invalid texture_manifest data");
 
     let mut lock = LOADED_TEXTURES.lock().unwrap();
 
     for definition in data {
-        let mut path = env::current_dir().unwrap();
-        path.push(Path::new(&format!("res/{}", definition.texture_path)));
-        let texture = Arc::new(Texture::from_bytes(&definition.alias, device, queue, format, &load_binary_sync(path.to_str().unwrap()).unwrap(), definition.filter.into()));
+        let bytes = read_asset(&definition.texture_path).expect(&format!("Unable to load texture {}", definition.texture_path));
+        let strip = image::load_from_memory(&bytes).expect(&format!("Unable to decode texture {}", definition.texture_path)).to_rgba8();
+        let pixels = split_frames(strip, &definition.animation, &definition.alias);
+        let sampling = SamplingOptions {
+            filter: definition.filter.into(),
+            mipmaps: definition.mipmaps,
+            alpha_cutoff: definition.alpha_cutoff,
+            anisotropy: definition.anisotropy.max(1)
+        };
+
+        if sampling.anisotropy > 1 && sampling.filter != FilterMode::Linear {
+            println!("Texture {} asks for anisotropy but uses nearest filtering, anisotropy is ignored", definition.alias);
+        }
+
+        let texture_format = format_for_type(&definition.texture_type, format);
+        let textures = pixels.iter().map(|frame| Arc::new(Texture::from_rgba(&definition.alias, device, queue, texture_format, frame, &sampling))).collect();
+
+        let animation = if pixels.len() > 1 {definition.animation} else {None};
 
         lock.insert(definition.alias.clone(), LoadedTextureData {
             path: definition.texture_path,
             alias: definition.alias,
             texture_type: definition.texture_type,
-            texture
+            sampling,
+            animation,
+            normal_map: definition.normal_map,
+            emissive_map: definition.emissive_map,
+            from_manifest: true,
+            pixels,
+            textures
         });
     }
 }
 
-pub fn initialize_load_textures(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> (wgpu::BindGroup, wgpu::BindGroupLayout) {
+/**
+ registers a texture that isn't in the manifest, like the ones model materials point at. has to happen before
+ initialize_load_textures, after which its index can be looked up by alias. an alias that's already loaded is kept
+*/
+pub fn preload_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    format: wgpu::TextureFormat,
+    path: &str,
+    alias: &str,
+    texture_type: TextureType,
+    pixels: RgbaImage,
+    sampling: SamplingOptions
+) {
+    let mut lock = LOADED_TEXTURES.lock().unwrap();
+
+    if lock.contains_key(alias) {return};
+
+    let texture = Arc::new(Texture::from_rgba(alias, device, queue, format_for_type(&texture_type, format), &pixels, &sampling));
+
+    lock.insert(alias.to_owned(), LoadedTextureData {
+        path: path.to_owned(),
+        alias: alias.to_owned(),
+        texture_type,
+        sampling,
+        animation: None,
+        normal_map: None,
+        emissive_map: None,
+        from_manifest: false,
+        pixels: vec![pixels],
+        textures: vec![texture]
+    });
+}
+
+/**
+ builds the group 0 surface texture bind group. the returned buffer holds TextureAnimationGlobals and should be updated every frame
+*/
+pub fn initialize_load_textures(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat, mode: TextureBindingMode) -> (wgpu::BindGroup, wgpu::BindGroupLayout, wgpu::Buffer) {
     
+    *TEXTURE_BINDING_MODE.lock().unwrap() = mode;
+
     let mut texture_indices = TEXTURE_INDICES.lock().unwrap();
 
     let mut diffuse_texture_map: Vec<Arc<Texture>> = Vec::new();
@@ -103,7 +334,7 @@ pub fn initialize_load_textures(device: &wgpu::Device, queue: &wgpu::Queue, form
     let mut emissive_texture_map: Vec<Arc<Texture>> = Vec::new();
 
     let diff = Arc::new(Texture::from_color("default-diffuse", device, queue, format, [0, 255, 255, 0], 1, 1, wgpu::FilterMode::Nearest));
-    let norm = Arc::new(Texture::from_color("default-normal", device, queue, format, [255 / 3, 255 / 3, 255 / 3, 255], 1, 1, wgpu::FilterMode::Nearest));
+    let norm = Arc::new(Texture::from_color("default-normal", device, queue, format_for_type(&TextureType::Normal, format), [128, 128, 255, 255], 1, 1, wgpu::FilterMode::Nearest));
     let emi = Arc::new(Texture::from_color("default-emissive", device, queue, format, [0, 0, 0, 0], 1, 1, wgpu::FilterMode::Nearest));
 
     let mut n_diffuse: u32 = 1;
@@ -118,31 +349,301 @@ pub fn initialize_load_textures(device: &wgpu::Device, queue: &wgpu::Queue, form
     texture_indices.insert("default-normal".to_owned(), 0);
     texture_indices.insert("default-emissive".to_owned(), 0);
 
+    //same defaults as above, as layer 0 of each texture array
+    let default_diffuse = RgbaImage::from_pixel(1, 1, image::Rgba([0, 255, 255, 0]));
+    let default_normal = RgbaImage::from_pixel(1, 1, image::Rgba([128, 128, 255, 255]));
+    let default_emissive = RgbaImage::from_pixel(1, 1, image::Rgba([0, 0, 0, 0]));
+
+    let mut diffuse_layers: Vec<(&RgbaImage, Option<f32>)> = vec![(&default_diffuse, None)];
+    let mut normal_layers: Vec<(&RgbaImage, Option<f32>)> = vec![(&default_normal, None)];
+    let mut emissive_layers: Vec<(&RgbaImage, Option<f32>)> = vec![(&default_emissive, None)];
+
+    let mut diffuse_sampling: Option<SamplingOptions> = None;
+    let mut normal_sampling: Option<SamplingOptions> = None;
+    let mut emissive_sampling: Option<SamplingOptions> = None;
+
+    //indexed by diffuse texture index, only the first frame's entry is read
+    let mut diffuse_animations: Vec<RawTextureAnimation> = vec![RawTextureAnimation::still()];
+
     let loaded_textures = LOADED_TEXTURES.lock().unwrap();
 
-    for name in loaded_textures.keys() {
+    //manifest textures first, in a fixed order so indices don't change between runs
+    let mut names = loaded_textures.keys().collect::<Vec<_>>();
+    names.sort_by_key(|name| (!loaded_textures[*name].from_manifest, *name));
+
+    for name in names {
         let texturedata = &loaded_textures[name];
 
         let alias = texturedata.alias.clone();
     
         match texturedata.texture_type {
             TextureType::Diffuse => {
-                diffuse_texture_map.push(texturedata.texture.clone());
+                diffuse_texture_map.extend(texturedata.textures.iter().cloned());
+                diffuse_layers.extend(texturedata.pixels.iter().map(|v| (v, texturedata.sampling.alpha_cutoff)));
+                merge_sampling(&mut diffuse_sampling, &texturedata.sampling);
                 texture_indices.insert(alias.clone(), n_diffuse as usize);
-                n_diffuse += 1;
+                n_diffuse += texturedata.textures.len() as u32;
+                check_block_texture_index(texturedata, n_diffuse);
+                diffuse_animations.push(texturedata.animation.as_ref().map_or(RawTextureAnimation::still(), |a| a.into()));
+                diffuse_animations.extend((1..texturedata.textures.len()).map(|_| RawTextureAnimation::still()));
             },
             TextureType::Normal => {
-                normal_texture_map.push(texturedata.texture.clone());
+                normal_texture_map.extend(texturedata.textures.iter().cloned());
+                normal_layers.extend(texturedata.pixels.iter().map(|v| (v, texturedata.sampling.alpha_cutoff)));
+                merge_sampling(&mut normal_sampling, &texturedata.sampling);
+                if texturedata.animation.is_some() {
+                    println!("Texture {} is animated but only diffuse textures animate, showing its first frame", alias);
+                }
                 texture_indices.insert(alias.clone(), n_normal as usize);
-                n_normal += 1;
+                n_normal += texturedata.textures.len() as u32;
+                check_block_texture_index(texturedata, n_normal);
             },
             TextureType::Emissive => {
-                emissive_texture_map.push(texturedata.texture.clone());
+                emissive_texture_map.extend(texturedata.textures.iter().cloned());
+                emissive_layers.extend(texturedata.pixels.iter().map(|v| (v, texturedata.sampling.alpha_cutoff)));
+                merge_sampling(&mut emissive_sampling, &texturedata.sampling);
+                if texturedata.animation.is_some() {
+                    println!("Texture {} is animated but only diffuse textures animate, showing its first frame", alias);
+                }
                 texture_indices.insert(alias.clone(), n_emissive as usize);
-                n_emissive += 1;
+                n_emissive += texturedata.textures.len() as u32;
+                check_block_texture_index(texturedata, n_emissive);
+            },
+        }
+    }
+
+    let mut texture_sets = TEXTURE_SETS.lock().unwrap();
+
+    let companion_index = |alias: &Option<String>, owner: &str| alias.as_ref().map_or(0, |a| match texture_indices.get(a) {
+        Some(i) => *i,
+        None => {
+            println!("Texture {} names {} as a companion map, but it isn't loaded", owner, a);
+            0
+        }
+    });
+
+    for texturedata in loaded_textures.values() {
+        if let TextureType::Diffuse = texturedata.texture_type {
+            texture_sets.insert(texturedata.alias.clone(), (
+                texture_indices[&texturedata.alias],
+                companion_index(&texturedata.normal_map, &texturedata.alias),
+                companion_index(&texturedata.emissive_map, &texturedata.alias)
+            ));
+        }
+    }
+
+    texture_sets.insert("default-diffuse".to_owned(), (0, 0, 0));
+
+    let animation_globals_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("texture animation globals buffer"),
+        contents: bytemuck::cast_slice(&[TextureAnimationGlobals::new(0.0)]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST
+    });
+
+    let animation_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("texture animation buffer"),
+        contents: bytemuck::cast_slice(&diffuse_animations),
+        usage: wgpu::BufferUsages::STORAGE
+    });
+
+    let (bindgroup, layout) = match mode {
+        TextureBindingMode::BindingArray => create_binding_array_group(device, &diffuse_texture_map, &normal_texture_map, &emissive_texture_map, &animation_globals_buffer, &animation_buffer),
+        TextureBindingMode::TextureArray => {
+            let diffuse = create_layered_texture("diffuse texture array", device, queue, format, &diffuse_layers, diffuse_sampling);
+            let normal = create_layered_texture("normal texture array", device, queue, format_for_type(&TextureType::Normal, format), &normal_layers, normal_sampling);
+            let emissive = create_layered_texture("emissive texture array", device, queue, format, &emissive_layers, emissive_sampling);
+
+            create_texture_array_group(device, &diffuse, &normal, &emissive, &animation_globals_buffer, &animation_buffer)
+        }
+    };
+
+    (bindgroup, layout, animation_globals_buffer)
+}
+
+/**
+ end is one past the texture's last frame. model textures are only ever drawn with full size indices
+*/
+fn check_block_texture_index(texturedata: &LoadedTextureData, end: u32) {
+    if texturedata.from_manifest && end - 1 > MAX_BLOCK_TEXTURE_INDEX {
+        panic!("Texture {} ends at index {}, block textures of one type can only use indices up to {}", texturedata.alias, end - 1, MAX_BLOCK_TEXTURE_INDEX);
+    }
+}
+
+/**
+ bindings 6 and 7, shared by both binding modes
+*/
+fn animation_layout_entries() -> [wgpu::BindGroupLayoutEntry; 2] {
+    [
+        wgpu::BindGroupLayoutEntry {
+            binding: 6,
+            visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
             },
+            count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+            binding: 7,
+            visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
         }
+    ]
+}
+
+/**
+ texture arrays share one sampler, so the first texture's filter wins and mips/anisotropy are on if any texture wants them.
+ alpha cutoffs stay with their own layers
+*/
+fn merge_sampling(merged: &mut Option<SamplingOptions>, sampling: &SamplingOptions) {
+    match merged {
+        Some(m) => {
+            m.mipmaps |= sampling.mipmaps;
+            m.anisotropy = m.anisotropy.max(sampling.anisotropy);
+        },
+        None => *merged = Some(*sampling)
     }
+}
+
+/**
+ layers are sized to the size most textures of the type share, usually the block size, so one big model texture
+ doesn't blow every block layer up. textures of other sizes are resized to fit
+*/
+fn create_layered_texture(
+    label: &str,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    format: wgpu::TextureFormat,
+    layers: &[(&RgbaImage, Option<f32>)],
+    sampling: Option<SamplingOptions>
+) -> Texture {
+    let limits = device.limits();
+    if layers.len() as u32 > limits.max_texture_array_layers {
+        panic!("{} needs {} layers but this device only supports {}", label, layers.len(), limits.max_texture_array_layers);
+    }
+
+    let mut sizes: HashMap<(u32, u32), usize> = HashMap::new();
+    //layer 0 is the single colour default, it looks the same at any size
+    for (layer, _) in layers.iter().skip(1) {
+        *sizes.entry(layer.dimensions()).or_default() += 1;
+    }
+    //ties go to the bigger size so it's the small textures that get scaled up
+    let (width, height) = sizes.into_iter().max_by_key(|(size, count)| (*count, size.0 * size.1)).map_or((1, 1), |(size, _)| size);
+    let (width, height) = (width.min(limits.max_texture_dimension_2d), height.min(limits.max_texture_dimension_2d));
+
+    let resized = layers.iter().skip(1).filter(|(v, _)| v.dimensions() != (width, height)).count();
+    if resized > 0 {
+        println!("{} layers of {} don't match its {}x{} layers and are resized to fit", resized, label, width, height);
+    }
+
+    Texture::from_layers(label, device, queue, format, layers, width, height, &sampling.unwrap_or(SamplingOptions::new(FilterMode::Nearest)), true)
+}
+
+fn create_texture_array_group(
+    device: &wgpu::Device,
+    diffuse: &Texture,
+    normal: &Texture,
+    emissive: &Texture,
+    animation_globals: &wgpu::Buffer,
+    animations: &wgpu::Buffer
+) -> (wgpu::BindGroup, wgpu::BindGroupLayout) {
+    let [globals_entry, animation_entry] = animation_layout_entries();
+
+    let texture_entry = |binding: u32| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2Array,
+            multisampled: false,
+        },
+        count: None,
+    };
+
+    let sampler_entry = |binding: u32| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    };
+
+    let surface_texture_bind_group_layout = device.create_bind_group_layout(
+        &wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                texture_entry(0),
+                sampler_entry(1),
+                texture_entry(2),
+                sampler_entry(3),
+                texture_entry(4),
+                sampler_entry(5),
+                globals_entry,
+                animation_entry
+            ],
+            label: Some("Surface texture array bind group layout")
+        }
+    );
+
+    let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Surface texture array bind group"),
+        layout: &surface_texture_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&diffuse.view)
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&diffuse.sampler)
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(&normal.view)
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::Sampler(&normal.sampler)
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::TextureView(&emissive.view)
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: wgpu::BindingResource::Sampler(&emissive.sampler)
+            },
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: animation_globals.as_entire_binding()
+            },
+            wgpu::BindGroupEntry {
+                binding: 7,
+                resource: animations.as_entire_binding()
+            }
+        ]
+    });
+
+    (texture_bind_group, surface_texture_bind_group_layout)
+}
+
+fn create_binding_array_group(
+    device: &wgpu::Device,
+    diffuse_texture_map: &[Arc<Texture>],
+    normal_texture_map: &[Arc<Texture>],
+    emissive_texture_map: &[Arc<Texture>],
+    animation_globals: &wgpu::Buffer,
+    animations: &wgpu::Buffer
+) -> (wgpu::BindGroup, wgpu::BindGroupLayout) {
+    let [globals_entry, animation_entry] = animation_layout_entries();
+
+    let n_diffuse = diffuse_texture_map.len() as u32;
+    let n_normal = normal_texture_map.len() as u32;
+    let n_emissive = emissive_texture_map.len() as u32;
 
     let surface_texture_bind_group_layout = device.create_bind_group_layout(
         &wgpu::BindGroupLayoutDescriptor {
@@ -195,6 +696,8 @@ pub fn initialize_load_textures(device: &wgpu::Device, queue: &wgpu::Queue, form
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                     count: NonZeroU32::new(n_emissive),
                 },
+                globals_entry,
+                animation_entry
             ],
             label: Some("Surface texture bind group layout")
         }
@@ -239,6 +742,14 @@ pub fn initialize_load_textures(device: &wgpu::Device, queue: &wgpu::Queue, form
                 resource: wgpu::BindingResource::SamplerArray(
                     &emissive_texture_map.iter().map(|v| &v.sampler).collect::<Vec<&Sampler>>()
                 )
+            },
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: animation_globals.as_entire_binding()
+            },
+            wgpu::BindGroupEntry {
+                binding: 7,
+                resource: animations.as_entire_binding()
             }
         ]
     });
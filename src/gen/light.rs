@@ -0,0 +1,120 @@
+use cgmath::{perspective, Deg, EuclideanSpace, InnerSpace, Matrix4, Point3, Vector3};
+
+use crate::{engine::color4::Color4, internal::camera::OPENGL_TO_WGPU_MATRIX};
+
+//shadow maps start this far from the light, closer casters are clipped
+const SHADOW_NEAR: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    //shines every way, its shadows take a layer per cube face
+    Point,
+    //angles are from the centre of the cone to its edges in degrees. full brightness inside inner_angle,
+    //fading out towards outer_angle
+    Spot {
+        direction: Vector3<f32>,
+        inner_angle: f32,
+        outer_angle: f32
+    }
+}
+
+/**
+ a local light. change it through the handle the renderer gave out, the light manager picks it up on the next frame
+*/
+pub struct Light {
+    pub kind: LightKind,
+    pub position: Point3<f32>,
+    pub color: Color4,
+    pub intensity: f32,
+    //nothing past this distance is lit
+    pub range: f32,
+    pub casts_shadows: bool
+}
+
+impl Light {
+    pub fn point(position: Point3<f32>) -> Self {
+        Self {
+            kind: LightKind::Point,
+            position,
+            color: Color4::new(1., 1., 1., 1.),
+            intensity: 8.,
+            range: 16.,
+            casts_shadows: true
+        }
+    }
+
+    pub fn spot(position: Point3<f32>, target: Point3<f32>) -> Self {
+        Self {
+            kind: LightKind::Spot {
+                direction: (target - position).normalize(),
+                inner_angle: 25.,
+                outer_angle: 35.
+            },
+            position,
+            color: Color4::new(1., 1., 1., 1.),
+            intensity: 16.,
+            range: 48.,
+            casts_shadows: true
+        }
+    }
+
+    pub fn point_at(&mut self, target: Point3<f32>) {
+        if let LightKind::Spot { direction, .. } = &mut self.kind {
+            *direction = (target - self.position).normalize();
+        }
+    }
+
+    /**
+     shadow map layers this light needs, one per cube face for point lights
+    */
+    pub fn shadow_layer_count(&self) -> usize {
+        match self.kind {
+            _ if !self.casts_shadows => 0,
+            LightKind::Point => 6,
+            LightKind::Spot { .. } => 1
+        }
+    }
+
+    /**
+     view projections of the light's shadow layers. point light faces go +x, -x, +y, -y, +z, -z,
+     the order shaders/include/lights.wgsl picks them in
+    */
+    pub fn shadow_view_projs(&self) -> Vec<Matrix4<f32>> {
+        let faces = match self.kind {
+            LightKind::Point => vec![
+                (Vector3::unit_x(), -Vector3::unit_y()),
+                (-Vector3::unit_x(), -Vector3::unit_y()),
+                (Vector3::unit_y(), Vector3::unit_z()),
+                (-Vector3::unit_y(), -Vector3::unit_z()),
+                (Vector3::unit_z(), -Vector3::unit_y()),
+                (-Vector3::unit_z(), -Vector3::unit_y())
+            ],
+            LightKind::Spot { direction, .. } => {
+                let up = if direction.y.abs() > 0.99 {Vector3::unit_z()} else {Vector3::unit_y()};
+                vec![(direction, up)]
+            }
+        };
+
+        let projection = OPENGL_TO_WGPU_MATRIX * perspective(Deg(self.shadow_fov()), 1., SHADOW_NEAR, self.range);
+
+        faces.into_iter().map(|(direction, up)| projection * Matrix4::look_to_rh(self.position, direction, up)).collect()
+    }
+
+    /**
+     full vertical angle of each shadow layer's projection
+    */
+    pub fn shadow_fov(&self) -> f32 {
+        match self.kind {
+            LightKind::Point => 90.,
+            LightKind::Spot { outer_angle, .. } => (outer_angle * 2.).min(170.)
+        }
+    }
+
+    /**
+     the box around everything the light can reach, for culling against the view
+    */
+    pub fn bounds(&self) -> (Vector3<f32>, Vector3<f32>) {
+        let reach = Vector3::new(self.range, self.range, self.range);
+        (self.position.to_vec() - reach, self.position.to_vec() + reach)
+    }
+}
@@ -1,3 +1,3 @@
 pub mod primitive;
 pub mod object;
-pub mod spotlight;
\ No newline at end of file
+pub mod light;
\ No newline at end of file
@@ -2,7 +2,9 @@ use std::{mem, sync::Arc};
 
 use cgmath::{Quaternion, Vector3};
 
-use crate::engine::model::Model;
+use parking_lot::Mutex;
+
+use crate::engine::{animation::AnimationPlayer, model::Model};
 
 pub struct Object {
     name: String,
@@ -10,14 +12,17 @@ pub struct Object {
     scale: Vector3<f32>,
     orientation: Quaternion<f32>,
     model: Arc<Model>,
-    raw_object: RawObject
+    raw_object: RawObject,
+    //for models with a skeleton. shared so whoever spawned the object can keep steering it
+    animation: Option<Arc<Mutex<AnimationPlayer>>>
 }
 
 impl Object {
     pub fn new(name: String, model: Arc<Model>) -> Self {
         let scale = Vector3::new(1., 1., 1.);
         let position = Vector3::new(0., 0., 0.);
-        let orientation = Quaternion::from_sv(1., Vector3::unit_z());
+        let orientation = Quaternion::from_sv(1., Vector3::new(0., 0., 0.));
+        let animation = AnimationPlayer::new(&model).map(|player| Arc::new(Mutex::new(player)));
 
         let model_matrix = cgmath::Matrix4::from_translation(position)
             * cgmath::Matrix4::from(orientation) 
@@ -31,8 +36,10 @@ impl Object {
             orientation,
             raw_object: RawObject {
                 model: model_matrix.into(),
-                normal: cgmath::Matrix3::from(orientation).into()
-            }
+                normal: cgmath::Matrix3::from(orientation).into(),
+                joint_offset: 0
+            },
+            animation
         }
     }
 
@@ -47,7 +54,8 @@ impl Object {
 
         self.raw_object = RawObject {
             model: model_matrix.into(),
-            normal: cgmath::Matrix3::from(self.orientation).into()
+            normal: cgmath::Matrix3::from(self.orientation).into(),
+            joint_offset: 0
         }
     }
 
@@ -68,16 +76,20 @@ impl Object {
     pub fn get_orientation(&self) -> Quaternion<f32> {self.orientation}
 
     pub fn set_model(&mut self, model: Arc<Model>) {
+        self.animation = AnimationPlayer::new(&model).map(|player| Arc::new(Mutex::new(player)));
         self.model = model;
     }
     pub fn get_model(&self) -> &Arc<Model> {&self.model}
+    pub fn get_animation(&self) -> Option<&Arc<Mutex<AnimationPlayer>>> {self.animation.as_ref()}
 }
 
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct RawObject {
     pub model: [[f32; 4]; 4],
-    pub normal: [[f32; 3]; 3]
+    pub normal: [[f32; 3]; 3],
+    //where this object's pose starts in the frame's joint palette, filled in by the renderer
+    pub joint_offset: u32
 }
 
 impl RawObject {
@@ -120,6 +132,11 @@ impl RawObject {
                     offset: mem::size_of::<[f32; 22]>() as wgpu::BufferAddress,
                     shader_location: 12,
                     format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 25]>() as wgpu::BufferAddress,
+                    shader_location: 15,
+                    format: wgpu::VertexFormat::Uint32,
                 }
             ]
         }
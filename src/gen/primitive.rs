@@ -1,10 +1,10 @@
 use std::{fmt::Display, sync::Arc};
 
-use cached::{cached_key, proc_macro::cached, SizedCache};
-use cgmath::{Quaternion, Vector3};
+use cached::{cached_key, SizedCache};
+use cgmath::{ElementWise, InnerSpace, Quaternion, Vector2, Vector3};
 use wgpu::util::DeviceExt;
 
-use crate::engine::{mesh::Mesh, model::Model, texture_loader::get_indices_from_texture, vertex::{calculate_tangents_inplace_modelvertex, ModelVertex}};
+use crate::engine::{material::Material, mesh::Mesh, model::Model, texture_loader::{get_indices_from_texture, get_texture_set}, vertex::ModelVertex};
 
 use super::object::Object;
 
@@ -18,7 +18,32 @@ pub enum Primitive {
         min: Vector3<f32>,
         max: Vector3<f32>
     },
-    Cube
+    Cube,
+    //centred on the origin along y, like every other primitive
+    Cylinder {
+        radius: f32,
+        height: f32,
+        segments: u32
+    },
+    Cone {
+        radius: f32,
+        height: f32,
+        segments: u32
+    },
+    //height is end to end, the straight part is what the two hemispheres leave of it
+    Capsule {
+        radius: f32,
+        height: f32,
+        segments: u32
+    },
+    //facing up, split into subdivisions by subdivisions quads
+    Plane {
+        width: f32,
+        depth: f32,
+        subdivisions: u32
+    },
+    //a unit cube cut diagonally, rising from the front (+z) to the back
+    Wedge
 }
 
 impl Display for Primitive {
@@ -29,141 +54,306 @@ impl Display for Primitive {
             Primitive::Rect { min, max } => 
                 write!(f, "Primitive(Rect)(Min: {}, {}, {}, Max: {}, {}, {})", min.x, min.y, min.z, max.x, max.y, max.z),
             Primitive::Cube => write!(f, "Primitive(Cube)"),
+            Primitive::Cylinder { radius, height, segments } =>
+                write!(f, "Primitive(Cylinder)(Radius: {}, Height: {}, Segments: {})", radius, height, segments),
+            Primitive::Cone { radius, height, segments } =>
+                write!(f, "Primitive(Cone)(Radius: {}, Height: {}, Segments: {})", radius, height, segments),
+            Primitive::Capsule { radius, height, segments } =>
+                write!(f, "Primitive(Capsule)(Radius: {}, Height: {}, Segments: {})", radius, height, segments),
+            Primitive::Plane { width, depth, subdivisions } =>
+                write!(f, "Primitive(Plane)(Width: {}, Depth: {}, Subdivisions: {})", width, depth, subdivisions),
+            Primitive::Wedge => write!(f, "Primitive(Wedge)"),
+        }
+    }
+}
+
+/**
+ a vertex with its texture indices left at 0, create_primitive fills those in.
+ uvs run down the texture, so the bitangent (the texture's up) is normal x tangent
+*/
+fn primitive_vertex(position: Vector3<f32>, tex_coords: [f32; 2], normal: Vector3<f32>, tangent: Vector3<f32>) -> ModelVertex {
+    ModelVertex {
+        position: position.into(),
+        tex_coords,
+        normal: normal.into(),
+        tangent: tangent.into(),
+        bitangent: normal.cross(tangent).into(),
+        diffuse_texture_index: 0,
+        normal_texture_index: 0,
+        emissive_texture_index: 0
+    }
+}
+
+/**
+ a flat convex face, corners counter clockwise seen from the front. uvs are projected along the tangent and bitangent,
+ either one texture per unit or one stretched over the whole face
+*/
+fn push_polygon(vertices: &mut Vec<ModelVertex>, indices: &mut Vec<u32>, corners: &[Vector3<f32>], normal: Vector3<f32>, tangent: Vector3<f32>, tile: bool) {
+    let bitangent = normal.cross(tangent);
+    let projected: Vec<Vector2<f32>> = corners.iter().map(|p| Vector2::new(p.dot(tangent), -p.dot(bitangent))).collect();
+
+    let min = projected.iter().fold(Vector2::new(f32::MAX, f32::MAX), |a, p| Vector2::new(a.x.min(p.x), a.y.min(p.y)));
+    let max = projected.iter().fold(Vector2::new(f32::MIN, f32::MIN), |a, p| Vector2::new(a.x.max(p.x), a.y.max(p.y)));
+    let size = if tile {Vector2::new(1., 1.)} else {Vector2::new((max.x - min.x).max(f32::EPSILON), (max.y - min.y).max(f32::EPSILON))};
+
+    let first = vertices.len() as u32;
+    for (corner, uv) in corners.iter().zip(projected) {
+        vertices.push(primitive_vertex(*corner, [(uv.x - min.x) / size.x, (uv.y - min.y) / size.y], normal, tangent));
+    }
+
+    for i in 1..corners.len() as u32 - 1 {
+        indices.extend_from_slice(&[first, first + i, first + i + 1]);
+    }
+}
+
+fn push_box(vertices: &mut Vec<ModelVertex>, indices: &mut Vec<u32>, min: Vector3<f32>, max: Vector3<f32>, tile: bool) {
+    let center = (min + max) * 0.5;
+    let half = (max - min) * 0.5;
+
+    //(normal, tangent) per face, with the texture upright on the sides
+    let faces = [
+        (Vector3::unit_x(), -Vector3::unit_z()),
+        (-Vector3::unit_x(), Vector3::unit_z()),
+        (Vector3::unit_y(), Vector3::unit_x()),
+        (-Vector3::unit_y(), Vector3::unit_x()),
+        (Vector3::unit_z(), Vector3::unit_x()),
+        (-Vector3::unit_z(), -Vector3::unit_x())
+    ];
+
+    for (normal, tangent) in faces {
+        let bitangent = normal.cross(tangent);
+        let c = center + normal.mul_element_wise(half);
+        let t = tangent.mul_element_wise(half);
+        let b = bitangent.mul_element_wise(half);
+
+        push_polygon(vertices, indices, &[c - t + b, c - t - b, c + t - b, c + t + b], normal, tangent, tile);
+    }
+}
+
+/**
+ spins a profile of (radius, height) points with their (radial, vertical) normals around the y axis.
+ the profile runs top to bottom, u goes once around and v follows the profile's length
+*/
+fn push_revolution(vertices: &mut Vec<ModelVertex>, indices: &mut Vec<u32>, profile: &[(Vector2<f32>, Vector2<f32>)], segments: u32) {
+    let mut lengths = vec![0.];
+    for pair in profile.windows(2) {
+        lengths.push(lengths.last().unwrap() + (pair[1].0 - pair[0].0).magnitude());
+    }
+    let total = lengths.last().unwrap().max(f32::EPSILON);
+
+    let first = vertices.len() as u32;
+    for ((point, normal), length) in profile.iter().zip(&lengths) {
+        for s in 0..=segments {
+            let u = s as f32 / segments as f32;
+            let (sin, cos) = (u * std::f32::consts::TAU).sin_cos();
+
+            vertices.push(primitive_vertex(
+                Vector3::new(point.x * sin, point.y, point.x * cos),
+                [u, length / total],
+                Vector3::new(normal.x * sin, normal.y, normal.x * cos).normalize(),
+                Vector3::new(cos, 0., -sin)
+            ));
+        }
+    }
+
+    let columns = segments + 1;
+    for row in 0..profile.len() as u32 - 1 {
+        for s in 0..segments {
+            let a = first + row * columns + s;
+            let (b, c, d) = (a + 1, a + columns, a + columns + 1);
+
+            indices.extend_from_slice(&[a, c, d, a, d, b]);
         }
     }
 }
 
+/**
+ a flat cap for a cylinder or cone, facing up or down
+*/
+fn push_disc(vertices: &mut Vec<ModelVertex>, indices: &mut Vec<u32>, radius: f32, y: f32, segments: u32, up: bool) {
+    let mut corners: Vec<Vector3<f32>> = (0..segments).map(|s| {
+        let (sin, cos) = (s as f32 / segments as f32 * std::f32::consts::TAU).sin_cos();
+        Vector3::new(radius * sin, y, radius * cos)
+    }).collect();
+
+    if !up {corners.reverse()};
+
+    let normal = if up {Vector3::unit_y()} else {-Vector3::unit_y()};
+    push_polygon(vertices, indices, &corners, normal, Vector3::unit_x(), false);
+}
 
-#[cached]
-fn create_cube(diffuse_texture_index: u32, normal_texture_index: u32, emissive_texture_index: u32) -> (Vec<ModelVertex>, Vec<u32>) {
+/**
+ points around a quarter circle for a sphere or capsule profile, from straight up or the equator depending on the range
+*/
+fn push_arc(profile: &mut Vec<(Vector2<f32>, Vector2<f32>)>, radius: f32, center: f32, from: f32, to: f32, rings: u32) {
+    for r in 0..=rings {
+        let angle = from + (to - from) * r as f32 / rings as f32;
+        let normal = Vector2::new(angle.sin(), angle.cos());
+
+        profile.push((Vector2::new(normal.x * radius, center + normal.y * radius), normal));
+    }
+}
+
+fn create_geometry(p: &Primitive) -> (Vec<ModelVertex>, Vec<u32>) {
     let mut vertices: Vec<ModelVertex> = Vec::new();
-    let mut indices: Vec<u32> = Vec::from([
-        2, 1, 0, 3, 2, 0,
-        4, 5, 6, 4, 6, 7,
-        8, 9, 10, 8, 10, 11,
-        14, 13, 12, 15, 14, 12,
-        16, 17, 18, 16, 18, 19,
-        22, 21, 20, 23, 22, 20
-    ]);
-
-    let half_side_length = 0.5;
-    
-    //back
-    vertices.push(ModelVertex {position: [-half_side_length, -half_side_length, -half_side_length], tex_coords: [0., 0.],
-        normal: [0., 0., -1.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [half_side_length, -half_side_length, -half_side_length], tex_coords: [1., 0.],
-        normal: [0., 0., -1.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [half_side_length, half_side_length, -half_side_length], tex_coords: [1., 1.],
-        normal: [0., 0., -1.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [-half_side_length, half_side_length, -half_side_length], tex_coords: [0., 1.],
-        normal: [0., 0., -1.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    //front
-    vertices.push(ModelVertex {position: [-half_side_length, -half_side_length, half_side_length], tex_coords: [0., 0.],
-        normal: [0., 0., 1.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [half_side_length, -half_side_length, half_side_length], tex_coords: [1., 0.],
-        normal: [0., 0., 1.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [half_side_length, half_side_length, half_side_length], tex_coords: [1., 1.],
-        normal: [0., 0., 1.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [-half_side_length, half_side_length, half_side_length], tex_coords: [0., 1.],
-        normal: [0., 0., 1.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    //left
-    vertices.push(ModelVertex {position: [-half_side_length, -half_side_length, -half_side_length], tex_coords: [0., 0.],
-        normal: [-1., 0., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [-half_side_length, -half_side_length, half_side_length], tex_coords: [1., 0.],
-        normal: [-1., 0., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [-half_side_length, half_side_length, half_side_length], tex_coords: [1., 1.],
-        normal: [-1., 0., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [-half_side_length, half_side_length, -half_side_length], tex_coords: [0., 1.],
-        normal: [-1., 0., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    //right
-    vertices.push(ModelVertex {position: [half_side_length, -half_side_length, -half_side_length], tex_coords: [0., 0.],
-        normal: [1., 0., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [half_side_length, -half_side_length, half_side_length], tex_coords: [1., 0.],
-        normal: [1., 0., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [half_side_length, half_side_length, half_side_length], tex_coords: [1., 1.],
-        normal: [1., 0., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [half_side_length, half_side_length, -half_side_length], tex_coords: [0., 1.],
-        normal: [1., 0., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    //bottom
-    vertices.push(ModelVertex {position: [-half_side_length, -half_side_length, -half_side_length], tex_coords: [0., 0.],
-        normal: [0., -1., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [half_side_length, -half_side_length, -half_side_length], tex_coords: [1., 0.],
-        normal: [0., -1., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [half_side_length, -half_side_length, half_side_length], tex_coords: [1., 1.],
-        normal: [0., -1., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [-half_side_length, -half_side_length, half_side_length], tex_coords: [0., 1.],
-        normal: [0., -1., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    //top
-    vertices.push(ModelVertex {position: [-half_side_length, half_side_length, -half_side_length], tex_coords: [0., 0.],
-        normal: [0., 1., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [half_side_length, half_side_length, -half_side_length], tex_coords: [1., 0.],
-        normal: [0., 1., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [half_side_length, half_side_length, half_side_length], tex_coords: [0., 1.],
-        normal: [0., 1., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-    vertices.push(ModelVertex {position: [-half_side_length, half_side_length, half_side_length], tex_coords: [0., 0.],
-        normal: [0., 1., 0.], bitangent: [0., 0., 0.], tangent: [0., 0., 0.], diffuse_texture_index, normal_texture_index, emissive_texture_index});
-
-    calculate_tangents_inplace_modelvertex(&mut vertices, &mut indices);
+    let mut indices: Vec<u32> = Vec::new();
+
+    match *p {
+        Primitive::Cube => push_box(&mut vertices, &mut indices, Vector3::new(-0.5, -0.5, -0.5), Vector3::new(0.5, 0.5, 0.5), false),
+        Primitive::Rect { min, max } => push_box(&mut vertices, &mut indices, min, max, true),
+        Primitive::Ball { radius, subdivisions } => {
+            let rings = subdivisions.max(2);
+            let mut profile = Vec::new();
+            push_arc(&mut profile, radius, 0., 0., std::f32::consts::PI, rings);
+
+            push_revolution(&mut vertices, &mut indices, &profile, rings * 2);
+        },
+        Primitive::Cylinder { radius, height, segments } => {
+            let segments = segments.max(3);
+            let half = height * 0.5;
+
+            push_revolution(&mut vertices, &mut indices, &[
+                (Vector2::new(radius, half), Vector2::new(1., 0.)),
+                (Vector2::new(radius, -half), Vector2::new(1., 0.))
+            ], segments);
+            push_disc(&mut vertices, &mut indices, radius, half, segments, true);
+            push_disc(&mut vertices, &mut indices, radius, -half, segments, false);
+        },
+        Primitive::Cone { radius, height, segments } => {
+            let segments = segments.max(3);
+            let half = height * 0.5;
+            let slope = Vector2::new(height, radius).normalize();
+
+            push_revolution(&mut vertices, &mut indices, &[
+                (Vector2::new(0., half), slope),
+                (Vector2::new(radius, -half), slope)
+            ], segments);
+            push_disc(&mut vertices, &mut indices, radius, -half, segments, false);
+        },
+        Primitive::Capsule { radius, height, segments } => {
+            let segments = segments.max(4);
+            let rings = (segments / 4).max(2);
+            let straight = (height * 0.5 - radius).max(0.);
+            let half_pi = std::f32::consts::FRAC_PI_2;
+
+            let mut profile = Vec::new();
+            push_arc(&mut profile, radius, straight, 0., half_pi, rings);
+            push_arc(&mut profile, radius, -straight, half_pi, std::f32::consts::PI, rings);
+
+            push_revolution(&mut vertices, &mut indices, &profile, segments);
+        },
+        Primitive::Plane { width, depth, subdivisions } => {
+            let subdivisions = subdivisions.max(1);
+            let columns = subdivisions + 1;
+
+            for row in 0..=subdivisions {
+                for column in 0..=subdivisions {
+                    let (x, z) = (column as f32 / subdivisions as f32 - 0.5, row as f32 / subdivisions as f32 - 0.5);
+
+                    vertices.push(primitive_vertex(
+                        Vector3::new(x * width, 0., z * depth),
+                        //one texture per unit, like a rect
+                        [(x + 0.5) * width, (z + 0.5) * depth],
+                        Vector3::unit_y(),
+                        Vector3::unit_x()
+                    ));
+                }
+            }
+
+            for row in 0..subdivisions {
+                for column in 0..subdivisions {
+                    let a = row * columns + column;
+                    let (b, c, d) = (a + 1, a + columns, a + columns + 1);
+
+                    indices.extend_from_slice(&[a, c, d, a, d, b]);
+                }
+            }
+        },
+        Primitive::Wedge => {
+            let slope = Vector3::new(0., 1., 1.).normalize();
+
+            //bottom, back and the slope between their far edges
+            push_polygon(&mut vertices, &mut indices, &[
+                Vector3::new(-0.5, -0.5, -0.5), Vector3::new(0.5, -0.5, -0.5), Vector3::new(0.5, -0.5, 0.5), Vector3::new(-0.5, -0.5, 0.5)
+            ], -Vector3::unit_y(), Vector3::unit_x(), false);
+            push_polygon(&mut vertices, &mut indices, &[
+                Vector3::new(0.5, 0.5, -0.5), Vector3::new(0.5, -0.5, -0.5), Vector3::new(-0.5, -0.5, -0.5), Vector3::new(-0.5, 0.5, -0.5)
+            ], -Vector3::unit_z(), -Vector3::unit_x(), false);
+            push_polygon(&mut vertices, &mut indices, &[
+                Vector3::new(-0.5, 0.5, -0.5), Vector3::new(-0.5, -0.5, 0.5), Vector3::new(0.5, -0.5, 0.5), Vector3::new(0.5, 0.5, -0.5)
+            ], slope, Vector3::unit_x(), false);
+
+            //the two triangular sides
+            push_polygon(&mut vertices, &mut indices, &[
+                Vector3::new(0.5, -0.5, -0.5), Vector3::new(0.5, 0.5, -0.5), Vector3::new(0.5, -0.5, 0.5)
+            ], Vector3::unit_x(), -Vector3::unit_z(), false);
+            push_polygon(&mut vertices, &mut indices, &[
+                Vector3::new(-0.5, -0.5, -0.5), Vector3::new(-0.5, -0.5, 0.5), Vector3::new(-0.5, 0.5, -0.5)
+            ], -Vector3::unit_x(), Vector3::unit_z(), false);
+        }
+    }
 
     (vertices, indices)
-    
 }
 
 cached_key! {
-    LENGTH: SizedCache<String, Arc<Mesh>> = SizedCache::with_size(100);
+    //cached as a whole model, so every object built from the same primitive and textures shares it and is drawn instanced
+    LENGTH: SizedCache<String, Arc<Model>> = SizedCache::with_size(100);
     Key = { format!("{}:{}:{}:{}", p, diffuse_texture_index, normal_texture_index, emissive_texture_index) };
-    fn create_primitive(p: Primitive, device: &wgpu::Device, diffuse_texture_index: u32, normal_texture_index: u32, emissive_texture_index: u32) -> Arc<Mesh> = {
-        println!("{}", diffuse_texture_index);
-        match p {
-            Primitive::Cube => {
-                let (mut vertices, mut indices) = create_cube(diffuse_texture_index, normal_texture_index, emissive_texture_index);
-                calculate_tangents_inplace_modelvertex(&mut vertices, &mut indices);
-
-                for i in 0..3 {
-                    println!("{:?}", vertices[i]);
-                }
-    
-                let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some(&format!("Chunk Vertex Buffer")),
-                    contents: bytemuck::cast_slice(&vertices),
-                    usage: wgpu::BufferUsages::VERTEX,
-                });
-    
-                let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some(&format!("Index Buffer")),
-                    contents: bytemuck::cast_slice(&indices),
-                    usage: wgpu::BufferUsages::INDEX,
-                });
-    
-                Arc::new(Mesh {
-                    vertex_buffer: vertex_buffer,
-                    index_buffer: index_buffer,
-                    num_elements: indices.len() as u32,
-                    vertices: vertices,
-                    face_indices: indices
-                })
-            },
-            _ => {todo!()}
+    fn create_primitive(p: Primitive, device: &wgpu::Device, diffuse_texture_index: u32, normal_texture_index: u32, emissive_texture_index: u32) -> Arc<Model> = {
+        let (mut vertices, indices) = create_geometry(&p);
+
+        for vertex in vertices.iter_mut() {
+            vertex.diffuse_texture_index = diffuse_texture_index;
+            vertex.normal_texture_index = normal_texture_index;
+            vertex.emissive_texture_index = emissive_texture_index;
         }
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Vertex Buffer", p)),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Index Buffer", p)),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Arc::new(Model::new(vec![Arc::new(Mesh {
+            vertex_buffer,
+            index_buffer,
+            skin_buffer: None,
+            num_elements: indices.len() as u32,
+            vertices,
+            face_indices: indices,
+            material: Material::default()
+        })]))
     }
 }
 
 pub struct PrimitiveBuilder {
-    mesh: Option<Arc<Mesh>>,
+    model: Option<Arc<Model>>,
     position: Vector3<f32>,
     size: Vector3<f32>,
     rotation: Quaternion<f32>,
-    diffuse_texture_index: Option<u32>
+    diffuse_texture_index: Option<u32>,
+    //0 is the flat default normal and no emission
+    normal_texture_index: u32,
+    emissive_texture_index: u32
 }
 
 impl PrimitiveBuilder {
     pub fn new() -> Self {
         Self {
-            mesh: None,
+            model: None,
             rotation: Quaternion::new(1., 0., 0., 0.),
             size: Vector3::new(1., 1., 1.),
             position: Vector3::new(0., 0., 0.),
-            diffuse_texture_index: None
+            diffuse_texture_index: None,
+            normal_texture_index: 0,
+            emissive_texture_index: 0
         }
     }
 
@@ -190,11 +380,32 @@ impl PrimitiveBuilder {
 
         self
     }
+    pub fn set_normal_texture_by_name(mut self, name: &str) -> Self {
+        self.normal_texture_index = get_indices_from_texture(name) as u32;
+
+        self
+    }
+    pub fn set_emissive_texture_by_name(mut self, name: &str) -> Self {
+        self.emissive_texture_index = get_indices_from_texture(name) as u32;
+
+        self
+    }
+
+    /**
+     a diffuse texture along with the normal and emissive maps the texture manifest gives it
+    */
+    pub fn set_texture_set_by_name(mut self, diffuse: &str) -> Self {
+        let (diffuse_texture_index, normal_texture_index, emissive_texture_index) = get_texture_set(diffuse);
+
+        self.diffuse_texture_index = Some(diffuse_texture_index as u32);
+        self.normal_texture_index = normal_texture_index as u32;
+        self.emissive_texture_index = emissive_texture_index as u32;
+
+        self
+    }
 
     pub fn finalize(&self) -> Object {
-        let mut object = Object::new("some object".to_owned(), Arc::new(Model {
-            meshes: [self.mesh.clone().expect("set_primitive must be called before finalize")].to_vec()
-        }));
+        let mut object = Object::new("some object".to_owned(), self.model.clone().expect("set_primitive must be called before finalize"));
 
         object.set_orientation(self.rotation);
         object.set_position(self.position);
@@ -205,9 +416,9 @@ impl PrimitiveBuilder {
 
     pub fn set_primitive(mut self, device: &wgpu::Device, p: Primitive) -> Self {
         
-        let mesh = create_primitive(p, device, self.diffuse_texture_index.expect("all textures must be set before set_primitive is called"), 0, 0);
+        let model = create_primitive(p, device, self.diffuse_texture_index.expect("all textures must be set before set_primitive is called"), self.normal_texture_index, self.emissive_texture_index);
 
-        self.mesh = Some(mesh);
+        self.model = Some(model);
 
         self
     }
@@ -0,0 +1,79 @@
+use serde::Deserialize;
+
+use super::postprocess::HDR_FORMAT;
+
+/**
+ the antialiasing section of data/graphics.json. both can be changed at runtime through the workspace
+*/
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct AntialiasingConfig {
+    //1 turns msaa off. counts the adapter can't do fall back to the nearest one below
+    pub msaa_samples: u32,
+    pub fxaa: bool
+}
+
+impl Default for AntialiasingConfig {
+    fn default() -> Self {
+        Self {
+            msaa_samples: 4,
+            fxaa: false
+        }
+    }
+}
+
+/**
+ the antialiasing the renderer should use. the renderer compares this against what it's using every frame
+ and rebuilds its scene targets and pipelines when the sample count changes
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Antialiasing {
+    msaa_samples: u32,
+    pub fxaa: bool,
+    //sample counts the scene colour and depth targets can both use, ascending and always starting at 1
+    supported_samples: Vec<u32>
+}
+
+impl Antialiasing {
+    pub fn new(config: AntialiasingConfig, supported_samples: Vec<u32>) -> Self {
+        let msaa_samples = supported_samples.iter().copied().filter(|&n| n <= config.msaa_samples).max().unwrap_or(1);
+
+        if msaa_samples != config.msaa_samples {
+            println!("{}x msaa isn't supported, using {}x", config.msaa_samples, msaa_samples);
+        }
+
+        Self {
+            msaa_samples,
+            fxaa: config.fxaa,
+            supported_samples
+        }
+    }
+
+    pub fn msaa_samples(&self) -> u32 {
+        self.msaa_samples
+    }
+
+    /**
+     steps to the next supported sample count, wrapping back round to off
+    */
+    pub fn next_msaa(&mut self) {
+        self.msaa_samples = self.supported_samples.iter().copied().find(|&n| n > self.msaa_samples).unwrap_or(1);
+    }
+}
+
+/**
+ 1 and 4 are always allowed. anything else needs the adapter's own format features, which request_gpu asks for
+ when they're available
+*/
+pub fn supported_sample_counts(adapter: &wgpu::Adapter) -> Vec<u32> {
+    if !adapter.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+        return vec![1, 4];
+    }
+
+    let colour = adapter.get_texture_format_features(HDR_FORMAT).flags;
+    let depth = adapter.get_texture_format_features(wgpu::TextureFormat::Depth32Float).flags;
+
+    [1, 2, 4, 8, 16].into_iter()
+        .filter(|&n| n == 1 || (colour.sample_count_supported(n) && depth.sample_count_supported(n) && colour.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE)))
+        .collect()
+}
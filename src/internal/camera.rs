@@ -5,6 +5,8 @@ use instant::Duration;
 use wgpu::util::DeviceExt;
 use winit::{event::ElementState, keyboard::KeyCode};
 
+use super::{fog::FogUniform, frustum::Frustum};
+
 pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
     1.0, 0.0, 0.0, 0.0,
     0.0, 1.0, 0.0, 0.0,
@@ -19,6 +21,16 @@ pub const OPENGL_TO_WGPU_MATRIX_GLAM: glam::Mat4 = glam::Mat4::from_cols_array(&
     0.0, 0.0, 0.5, 1.0,
 ]);
 
+/**
+ maps wgpu's 0..1 depth to 1..0, the near plane ends up at 1 so float depth keeps its precision far away
+*/
+pub const REVERSE_Z_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, -1.0, 0.0,
+    0.0, 0.0, 1.0, 1.0,
+);
+
 const SAFE_FRAC_PI_2: f32 = FRAC_PI_2 - 0.0001;
 
 pub struct Camera {
@@ -39,6 +51,7 @@ pub struct Camera {
     zfar: f32,
 
     pub controller: CameraController,
+    fog: FogUniform,
     buffer: wgpu::Buffer,
     pub bindgroup: wgpu::BindGroup
 }
@@ -49,7 +62,8 @@ pub struct CameraUniform {
     view_position: [f32; 4],
     view_proj: [[f32; 4]; 4],
     inv_view_proj: [[f32; 4]; 4],
-    screendims: [u32; 4]
+    screendims: [u32; 4],
+    fog: FogUniform
 }
 
 impl Into<CameraUniform> for Camera {
@@ -58,7 +72,8 @@ impl Into<CameraUniform> for Camera {
             view_position: [self.position.x, self.position.y, self.position.z, 1.0],
             view_proj: self.view_proj_matrix.into(),
             inv_view_proj: self.inv_view_proj_matrix.into(),
-            screendims: [0, 0, 0, 0]
+            screendims: [0, 0, 0, 0],
+            fog: self.fog
         }
     }
 }
@@ -80,7 +95,8 @@ impl Camera {
                 view_position: [0., 0., 0., 1.],
                 view_proj: Matrix4::from_nonuniform_scale(0., 0., 0.).into(),
                 inv_view_proj: Matrix4::from_nonuniform_scale(0., 0., 0.).into(),
-                screendims: [dims.0, dims.1, 0, 0]
+                screendims: [dims.0, dims.1, 0, 0],
+                fog: FogUniform::disabled()
             }]),
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST
         });
@@ -112,6 +128,7 @@ impl Camera {
             view_proj_matrix: Matrix4::identity(),
             inv_view_proj_matrix: Matrix4::identity(),
             controller: CameraController::new(),
+            fog: FogUniform::disabled(),
 
             buffer,
             bindgroup,
@@ -125,6 +142,28 @@ impl Camera {
     pub fn set_aspect_ratio(&mut self, aspect: f32) {
         self.aspect_ratio = aspect;
     }
+    pub fn set_view_distance(&mut self, zfar: f32) {
+        self.zfar = zfar;
+    }
+    pub fn view_distance(&self) -> f32 {
+        self.zfar
+    }
+
+    /**
+     written straight away, update_matrices keeps it
+    */
+    pub fn set_fog(&mut self, queue: &wgpu::Queue, fog: FogUniform) {
+        self.fog = fog;
+        queue.write_buffer(&self.buffer, std::mem::offset_of!(CameraUniform, fog) as u64, bytemuck::cast_slice(&[fog]));
+    }
+
+    /**
+     new uniform values are written by the next update_matrices
+    */
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.screendims = (width, height);
+        self.aspect_ratio = width as f32 / height as f32;
+    }
 
     pub fn update_matrices(&mut self, queue: &wgpu::Queue) {
         let (pitch_sin, pitch_cos) = self.pitch.0.sin_cos();
@@ -140,7 +179,7 @@ impl Camera {
             Vector3::unit_y()
         );
 
-        self.projection_matrix = OPENGL_TO_WGPU_MATRIX * perspective(self.fov, self.aspect_ratio, self.znear, self.zfar);
+        self.projection_matrix = REVERSE_Z_MATRIX * OPENGL_TO_WGPU_MATRIX * perspective(self.fov, self.aspect_ratio, self.znear, self.zfar);
     
         self.view_proj_matrix = self.projection_matrix * self.view_matrix;
 
@@ -150,10 +189,18 @@ impl Camera {
             view_position: self.position.to_homogeneous().into(),
             view_proj: self.view_proj_matrix.into(),
             inv_view_proj: self.inv_view_proj_matrix.into(),
-            screendims: [self.screendims.0, self.screendims.1, 0, 0]
+            screendims: [self.screendims.0, self.screendims.1, 0, 0],
+            fog: self.fog
         }]));
     }
 
+    /**
+     uses the matrices from the last update_matrices call
+    */
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&self.view_proj_matrix)
+    }
+
     pub fn look_vector(&self) -> Vector3<f32> {
         let (sin_yaw, cos_yaw) = self.yaw.0.sin_cos();
         let (sin_pitch, cos_pitch) = self.pitch.0.sin_cos();
@@ -257,4 +304,22 @@ impl CameraController {
     pub fn process_mouse_input(&mut self, dx: f64, dy: f64) {
         self.mouse_delta = Vector2::new(dx as f32, dy as f32);
     }
-}
\ No newline at end of file
+}
+
+pub fn create_camera_bindgroup_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }
+        ],
+        label: Some("camera bind group layout :)"),
+    })
+}
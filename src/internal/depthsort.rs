@@ -1,12 +1,11 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::{mpsc::{self, Receiver, Sender}, Arc}, thread};
 
-use cgmath::{MetricSpace, Vector3};
-use parking_lot::RwLock;
-use wgpu::util::DeviceExt;
+use cgmath::{EuclideanSpace, MetricSpace, Point3, Vector3};
 
-use crate::{engine::surfacevertex::SurfaceVertex, vox::{binarymesher::generate_indices, chunk::{Chunk, ChunkState}}};
+use crate::{engine::surfacevertex::SurfaceVertex, vox::{chunk::{xz_to_index, ChunkState}, chunk_manager::ChunkManager}};
 
-use super::camera::Camera;
+//how far the camera has to move before every transparent slice is sorted again
+const RESORT_DISTANCE: f32 = 1.0;
 
 #[derive(Clone, Copy)]
 pub struct Quad {
@@ -14,38 +13,96 @@ pub struct Quad {
     pub vertices: [SurfaceVertex; 4]
 }
 
-pub fn sort_chunk_transparent_quads(device: &wgpu::Device, camera: &Camera, chunka: &mut Arc<Chunk>, i: usize) -> Option<(wgpu::Buffer, wgpu::Buffer, usize)> {
-    let chunk = Arc::make_mut(chunka);
-    let camera_pos = Vector3::new(camera.position.x, camera.position.y, camera.position.z);
-    
-    if chunk.states[i] != ChunkState::Ready { return None };
-        
-    chunk.transparent_quads[i].sort_by(|a, b| {
-        let dista = a.center.distance(camera_pos);
-        let distb = b.center.distance(camera_pos);
-
-        dista.partial_cmp(&distb).unwrap()
-    });
-
-    let vertices = chunk.transparent_quads[i].clone().iter().flat_map(|v| {
-        v.vertices
-    }).collect::<Vec<_>>();
-
-    let indices = generate_indices(vertices.len());
-
-    let ilen = indices.len();
-
-    let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some(&format!("Transparent Quad Vertex Buffer")),
-        contents: bytemuck::cast_slice(&vertices),
-        usage: wgpu::BufferUsages::VERTEX,
-    });
-
-    let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some(&format!("Transparent Quad Index Buffer")),
-        contents: bytemuck::cast_slice(&indices),
-        usage: wgpu::BufferUsages::INDEX,
-    });
-
-    Some((vertex_buffer, index_buffer, ilen))
-}
\ No newline at end of file
+/**
+ furthest quad first, flattened into the vertex order the mesh pool draws
+*/
+pub fn sort_quads_back_to_front(quads: &[Quad], camera_pos: Vector3<f32>) -> Vec<SurfaceVertex> {
+    let mut sorted = quads.iter().map(|q| (q.center.distance2(camera_pos), q)).collect::<Vec<_>>();
+    sorted.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    sorted.into_iter().flat_map(|(_, q)| q.vertices).collect()
+}
+
+/**
+ sorts transparent quads on its own thread and writes the results back over the slices' transparent meshes.
+ only one batch is in flight at a time, a new one is sent when the camera has moved or a slice was remeshed.
+ the batch holds just the slices' quads, not their chunks, so editing a chunk mid sort doesn't copy it
+*/
+pub struct TransparencySorter {
+    send: Sender<(Vector3<f32>, Vec<(Vector3<i32>, u32, Arc<Vec<Quad>>)>)>,
+    recv: Receiver<Vec<(Vector3<i32>, u32, Vec<SurfaceVertex>)>>,
+    in_flight: bool,
+    sorted_from: Option<Point3<f32>>,
+    sorted_versions: HashMap<Vector3<i32>, u32>
+}
+
+impl TransparencySorter {
+    pub fn new() -> Self {
+        let (send, worker_recv) = mpsc::channel::<(Vector3<f32>, Vec<(Vector3<i32>, u32, Arc<Vec<Quad>>)>)>();
+        let (worker_send, recv) = mpsc::channel();
+
+        thread::spawn(move || {
+            while let Ok((camera_pos, slices)) = worker_recv.recv() {
+                let sorted = slices.into_iter().map(|(slice, version, quads)| {
+                    (slice, version, sort_quads_back_to_front(&quads, camera_pos))
+                }).collect::<Vec<_>>();
+
+                if worker_send.send(sorted).is_err() {break}
+            }
+        });
+
+        Self {
+            send,
+            recv,
+            in_flight: false,
+            sorted_from: None,
+            sorted_versions: HashMap::new()
+        }
+    }
+
+    /**
+     uploads a finished batch, then queues the next one if anything is out of date. called once per frame
+    */
+    pub fn update(&mut self, queue: &wgpu::Queue, camera_position: Point3<f32>, chunk_manager: &ChunkManager) {
+        if let Ok(sorted) = self.recv.try_recv() {
+            self.in_flight = false;
+
+            for (slice, version, vertices) in sorted {
+                let Some(buffers) = chunk_manager.chunk_buffers.get(&xz_to_index(slice.x, slice.z)) else {continue};
+                if buffers.transparent_versions[slice.y as usize] != version {continue}
+                let Some(allocation) = buffers.transparent[slice.y as usize] else {continue};
+
+                chunk_manager.mesh_pool.write(queue, allocation, &vertices);
+                self.sorted_versions.insert(slice, version);
+            }
+        }
+
+        if self.in_flight {return}
+
+        let moved = self.sorted_from.map_or(true, |p| p.distance(camera_position) > RESORT_DISTANCE);
+
+        let mut slices = Vec::new();
+        for (index, buffers) in chunk_manager.chunk_buffers.iter() {
+            let Some(chunk) = chunk_manager.chunks.get(index) else {continue};
+
+            for (y, allocation) in buffers.transparent.iter().enumerate() {
+                if allocation.is_none() || chunk.states[y] != ChunkState::Ready {continue}
+
+                let slice = Vector3::new(buffers.position.x, y as i32, buffers.position.y);
+                let version = buffers.transparent_versions[y];
+                if moved || self.sorted_versions.get(&slice) != Some(&version) {
+                    slices.push((slice, version, chunk.transparent_quads[y].clone()));
+                }
+            }
+        }
+
+        if moved {
+            self.sorted_from = Some(camera_position);
+        }
+        if slices.is_empty() {return}
+
+        if self.send.send((camera_position.to_vec(), slices)).is_ok() {
+            self.in_flight = true;
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use cgmath::Point3;
+
+use crate::{state::worldclock::WorldLighting, vox::chunk_manager::ChunkManager};
+
+//how far you can see inside a denser medium, and the colour it fades to
+const WATER_FOG_DISTANCE: f32 = 24.0;
+const WATER_COLOUR: [f32; 3] = [0.04, 0.16, 0.22];
+const CAVE_FOG_DISTANCE: f32 = 48.0;
+const CAVE_COLOUR: [f32; 3] = [0.01, 0.01, 0.012];
+//sunlight at or below this around the camera counts as being underground
+const CAVE_SUNLIGHT: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FogMode {
+    Linear,
+    Exponential,
+    //exponential, and thinner the higher the air is
+    Height
+}
+
+impl FogMode {
+    pub fn next(self) -> Self {
+        match self {
+            FogMode::Linear => FogMode::Exponential,
+            FogMode::Exponential => FogMode::Height,
+            FogMode::Height => FogMode::Linear
+        }
+    }
+}
+
+/**
+ what the camera is in, water and caves swap the sky coloured fog for a much denser one
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FogMedium {
+    Air,
+    Water,
+    Cave
+}
+
+impl FogMedium {
+    pub fn at(chunk_manager: &ChunkManager, position: Point3<f32>) -> Self {
+        let block = chunk_manager.get_block_at_absolute(position.x.floor() as i32, position.y.floor() as i32, position.z.floor() as i32);
+
+        match block {
+            Some(block) if block.is_fluid() => FogMedium::Water,
+            Some(block) if block.get_sunlight_intensity() <= CAVE_SUNLIGHT => FogMedium::Cave,
+            _ => FogMedium::Air
+        }
+    }
+}
+
+/**
+ fog settings in shaders/include/fog.wgsl, part of the camera uniform
+*/
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct FogUniform {
+    //w is the mode
+    sun_direction: [f32; 4],
+    //start, end, density, height falloff
+    params: [f32; 4],
+    //x base height
+    height: [f32; 4],
+    //rgb replaces the sky colour by a
+    medium: [f32; 4]
+}
+
+impl FogUniform {
+    /**
+     starts past anything the camera can see
+    */
+    pub fn disabled() -> Self {
+        Self {
+            sun_direction: [0.0, 1.0, 0.0, 0.0],
+            params: [1e9, 1e9, 0.0, 0.0],
+            height: [0.0; 4],
+            medium: [0.0; 4]
+        }
+    }
+}
+
+/**
+ distance fog that blends terrain into the sky at the horizon, its range follows the camera's view distance
+*/
+pub struct Fog {
+    pub mode: FogMode,
+    //fraction of the view distance where the fog begins, it's opaque at the view distance
+    pub start: f32,
+    //height mode: how fast it thins out going up, and the height it's as dense as the exponential mode at
+    pub height_falloff: f32,
+    pub base_height: f32
+}
+
+impl Fog {
+    pub fn new() -> Self {
+        Self {
+            mode: FogMode::Linear,
+            start: 0.6,
+            height_falloff: 0.04,
+            base_height: 90.0
+        }
+    }
+
+    pub fn uniform(&self, view_distance: f32, lighting: &WorldLighting, medium: FogMedium) -> FogUniform {
+        let (mode, start, end, colour, blend) = match medium {
+            FogMedium::Air => (self.mode, view_distance * self.start, view_distance, [0.0; 3], 0.0),
+            //lit by whatever gets through the surface
+            FogMedium::Water => (FogMode::Exponential, 0.0, WATER_FOG_DISTANCE, WATER_COLOUR.map(|c| c * lighting.sky_light), 1.0),
+            FogMedium::Cave => (FogMode::Exponential, 0.0, CAVE_FOG_DISTANCE, CAVE_COLOUR, 1.0)
+        };
+
+        //exponential fog is 99% opaque by the end
+        let density = 4.6 / (end - start).max(1.0);
+
+        FogUniform {
+            sun_direction: lighting.sun_direction.extend(mode as u32 as f32).into(),
+            params: [start, end, density, self.height_falloff],
+            height: [self.base_height, 0.0, 0.0, 0.0],
+            medium: [colour[0], colour[1], colour[2], blend]
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use cgmath::{InnerSpace, Matrix, Matrix4, Vector3, Vector4};
+
+/**
+ six planes (xyz normal pointing inwards, w distance) pulled out of a view projection matrix.
+ expects wgpu's 0..1 clip depth, so one of the depth planes is the z row on its own. which one doesn't matter,
+ reversed depth just swaps near and far
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    planes: [Vector4<f32>; 6]
+}
+
+impl Frustum {
+    pub fn from_matrix(view_proj: &Matrix4<f32>) -> Self {
+        let (r0, r1, r2, r3) = (view_proj.row(0), view_proj.row(1), view_proj.row(2), view_proj.row(3));
+
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r2, r3 - r2].map(|p| {
+            let len = p.truncate().magnitude();
+            if len > 0.0 {p / len} else {p}
+        });
+
+        Self { planes }
+    }
+
+    /**
+     conservative, boxes near a corner of the frustum can pass without being visible
+    */
+    pub fn intersects_aabb(&self, min: Vector3<f32>, max: Vector3<f32>) -> bool {
+        self.planes.iter().all(|plane| {
+            //corner furthest along the plane normal
+            let corner = Vector3::new(
+                if plane.x >= 0.0 {max.x} else {min.x},
+                if plane.y >= 0.0 {max.y} else {min.y},
+                if plane.z >= 0.0 {max.z} else {min.z}
+            );
+
+            plane.truncate().dot(corner) + plane.w >= 0.0
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CullStats {
+    pub drawn_slices: u32,
+    pub culled_slices: u32,
+    pub occluded_slices: u32,
+    pub drawn_transparent_slices: u32,
+    pub culled_transparent_slices: u32,
+    pub visible_lights: u32,
+    pub culled_lights: u32
+}
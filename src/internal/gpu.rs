@@ -0,0 +1,153 @@
+use std::env;
+
+use serde::Deserialize;
+
+use crate::{engine::{assetfs::read_asset, texture_loader::TextureBindingMode}, vox::meshpool::SliceDrawMode};
+
+use super::{antialiasing::{supported_sample_counts, AntialiasingConfig}, postprocess::PostProcessConfig};
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendChoice {
+    #[default]
+    Auto,
+    Vulkan,
+    Gl,
+    Dx12,
+    Metal
+}
+
+impl BackendChoice {
+    pub fn to_backends(&self) -> wgpu::Backends {
+        match self {
+            BackendChoice::Auto => wgpu::Backends::all(),
+            BackendChoice::Vulkan => wgpu::Backends::VULKAN,
+            BackendChoice::Gl => wgpu::Backends::GL,
+            BackendChoice::Dx12 => wgpu::Backends::DX12,
+            BackendChoice::Metal => wgpu::Backends::METAL
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(value.to_lowercase())).ok()
+    }
+}
+
+/**
+ graphics settings from data/graphics.json, each of which can be overridden with an environment variable:
+ SOULSPARK_BACKEND (auto, vulkan, gl, dx12, metal), SOULSPARK_TEXTURE_MODE (binding_array, texture_array)
+ and SOULSPARK_FALLBACK_ADAPTER (1 to ask for a software adapter). post_processing and antialiasing have no overrides
+*/
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct GraphicsConfig {
+    #[serde(default)]
+    pub backend: BackendChoice,
+    #[serde(default)]
+    pub texture_binding_mode: Option<TextureBindingMode>,
+    #[serde(default)]
+    pub force_fallback_adapter: bool,
+    #[serde(default)]
+    pub post_processing: PostProcessConfig,
+    #[serde(default)]
+    pub antialiasing: AntialiasingConfig
+}
+
+impl GraphicsConfig {
+    pub fn load() -> Self {
+        let mut config: GraphicsConfig = match read_asset("data/graphics.json") {
+            Ok(bytes) => serde_json::from_slice(&bytes).expect("Invalid graphics.json data"),
+            Err(_) => GraphicsConfig::default()
+        };
+
+        if let Ok(v) = env::var("SOULSPARK_BACKEND") {
+            match BackendChoice::parse(&v) {
+                Some(b) => config.backend = b,
+                None => println!("Unknown backend {} in SOULSPARK_BACKEND, using {:?}", v, config.backend)
+            }
+        }
+
+        if let Ok(v) = env::var("SOULSPARK_TEXTURE_MODE") {
+            match serde_json::from_value(serde_json::Value::String(v.to_lowercase())) {
+                Ok(m) => config.texture_binding_mode = Some(m),
+                Err(_) => println!("Unknown texture mode {} in SOULSPARK_TEXTURE_MODE", v)
+            }
+        }
+
+        if let Ok(v) = env::var("SOULSPARK_FALLBACK_ADAPTER") {
+            config.force_fallback_adapter = v == "1" || v.eq_ignore_ascii_case("true");
+        }
+
+        config
+    }
+
+    pub fn create_instance(&self) -> wgpu::Instance {
+        wgpu::Instance::new(wgpu::InstanceDescriptor {
+            flags: wgpu::InstanceFlags::empty(),
+            backends: self.backend.to_backends(),
+            dx12_shader_compiler: Default::default(),
+            gles_minor_version: wgpu::Gles3MinorVersion::Automatic
+        })
+    }
+}
+
+pub struct GpuContext {
+    pub adapter: wgpu::Adapter,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub texture_binding_mode: TextureBindingMode,
+    pub slice_draw_mode: SliceDrawMode,
+    pub msaa_sample_counts: Vec<u32>
+}
+
+/**
+ picks an adapter and only asks for what it supports. binding arrays, indirect slice draws, unclipped depth, bgra storage and adapter specific msaa counts are used when available
+*/
+pub async fn request_gpu(instance: &wgpu::Instance, config: &GraphicsConfig, compatible_surface: Option<&wgpu::Surface<'_>>) -> anyhow::Result<GpuContext> {
+    let adapter = instance.request_adapter(
+        &wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface,
+            force_fallback_adapter: config.force_fallback_adapter
+        }
+    ).await.ok_or_else(|| anyhow::anyhow!("No adapter found for backend {:?}", config.backend))?;
+
+    println!("ADAPTER: {:?}", adapter.get_info());
+
+    let detected = TextureBindingMode::detect(&adapter);
+
+    let texture_binding_mode = match config.texture_binding_mode {
+        Some(TextureBindingMode::BindingArray) if detected == TextureBindingMode::TextureArray => {
+            println!("Binding arrays were requested but the adapter doesn't support them");
+            TextureBindingMode::TextureArray
+        },
+        Some(mode) => mode,
+        None => detected
+    };
+
+    println!("Texture binding mode: {:?}", texture_binding_mode);
+
+    let slice_draw_mode = SliceDrawMode::detect(&adapter);
+
+    println!("Slice draw mode: {:?}", slice_draw_mode);
+
+    let msaa_sample_counts = supported_sample_counts(&adapter);
+
+    println!("MSAA sample counts: {:?}", msaa_sample_counts);
+
+    let optional_features = wgpu::Features::BGRA8UNORM_STORAGE | wgpu::Features::DEPTH_CLIP_CONTROL | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+
+    let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
+        required_features: texture_binding_mode.required_features() | slice_draw_mode.required_features() | (adapter.features() & optional_features),
+        required_limits: adapter.limits(),
+        label: None,
+    }, None).await?;
+
+    Ok(GpuContext {
+        adapter,
+        device,
+        queue,
+        texture_binding_mode,
+        slice_draw_mode,
+        msaa_sample_counts
+    })
+}
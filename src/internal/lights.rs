@@ -0,0 +1,320 @@
+use std::{mem::size_of, sync::Arc};
+
+use bytemuck::Zeroable;
+use cgmath::{InnerSpace, Matrix4, MetricSpace, Point3};
+use parking_lot::RwLock;
+
+use crate::{engine::{surfacevertex::SurfaceVertex, vertex::Vertex}, gen::light::{Light, LightKind}, vox::{chunk_manager::ChunkManager, meshpool::create_mesh_pool_bindgroup_layout}};
+
+use super::{frustum::Frustum, renderpipeline::create_render_pipeline, sunshadows::{draw_object_shadow_casters, draw_shadow_casters, ObjectShadowPipelines, ShadowObjects}};
+
+//the nearest lights in view that shaders get to see, the rest are left out for the frame
+pub const MAX_VISIBLE_LIGHTS: usize = 32;
+//layers in the shadow atlas, a spot light takes one and a point light six
+pub const SHADOW_LAYERS: usize = 24;
+const SHADOW_RESOLUTION: u32 = 512;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct RawLight {
+    position_range: [f32; 4],
+    color_intensity: [f32; 4],
+    //xyz spot direction, w cosine of the outer cone. unused for point lights
+    direction_cone: [f32; 4],
+    //x cosine of the inner cone, y first shadow layer or -1, z 1 for spot lights, w world size of a shadow texel per unit of distance
+    params: [f32; 4]
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightsUniform {
+    lights: [RawLight; MAX_VISIBLE_LIGHTS],
+    shadow_matrices: [[[f32; 4]; 4]; SHADOW_LAYERS],
+    //x lights in use
+    count: [u32; 4]
+}
+
+struct ShadowLayer {
+    view_proj: Matrix4<f32>,
+    buffer: wgpu::Buffer,
+    bindgroup: wgpu::BindGroup,
+    view: wgpu::TextureView
+}
+
+/**
+ every spot and point light in the world. each frame the ones whose reach is in view are sorted nearest first,
+ the nearest get shadow atlas layers while there are some left, and up to MAX_VISIBLE_LIGHTS go to the shaders
+ (shaders/include/lights.wgsl). like the sun, terrain and render storage objects cast their shadows
+*/
+pub struct LightManager {
+    lights: Vec<Arc<RwLock<Light>>>,
+    pipeline: wgpu::RenderPipeline,
+    object_pipelines: ObjectShadowPipelines,
+    layers: Vec<ShadowLayer>,
+    //layers handed out this frame, the rest aren't rendered
+    used_layers: usize,
+    uniform_buffer: wgpu::Buffer,
+    pub shadow_view: wgpu::TextureView,
+    pub shadow_sampler: wgpu::Sampler
+}
+
+impl LightManager {
+    /**
+     joint_bindgroup_layout is the renderer's joint palette, for shadows of skinned objects
+    */
+    pub fn new(device: &wgpu::Device, joint_bindgroup_layout: &wgpu::BindGroupLayout) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("light shadow atlas"),
+            size: wgpu::Extent3d {
+                width: SHADOW_RESOLUTION,
+                height: SHADOW_RESOLUTION,
+                //one spare layer. the gl backend makes square textures with a multiple of 6 layers cube map arrays,
+                //which then can't be sampled as a 2d array
+                depth_or_array_layers: SHADOW_LAYERS as u32 + 1
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[]
+        });
+
+        let shadow_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let shadow_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("light shadow sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        //same shape as a sun shadow cascade's, so the terrain depth shader works for both
+        let layer_bindgroup_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("light shadow layer bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None
+                }
+            ]
+        });
+
+        let layers = (0..SHADOW_LAYERS).map(|i| {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Light Shadow Layer Buffer"),
+                size: size_of::<[[f32; 4]; 4]>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false
+            });
+
+            let bindgroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("light shadow layer bind group"),
+                layout: &layer_bindgroup_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: buffer.as_entire_binding()
+                    }
+                ]
+            });
+
+            let view = texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("light shadow layer"),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_array_layer: i as u32,
+                array_layer_count: Some(1),
+                ..Default::default()
+            });
+
+            ShadowLayer { view_proj: Matrix4::from_scale(1.0), buffer, bindgroup, view }
+        }).collect::<Vec<_>>();
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("light shadow pipeline layout"),
+            bind_group_layouts: &[&layer_bindgroup_layout, &create_mesh_pool_bindgroup_layout(device)],
+            push_constant_ranges: &[]
+        });
+
+        let pipeline = create_render_pipeline(
+            "light shadow pipeline",
+            device,
+            &pipeline_layout,
+            wgpu::TextureFormat::Rgba8Unorm,
+            Some(wgpu::TextureFormat::Depth32Float),
+            &[SurfaceVertex::desc()],
+            "shaders/shadowshader.wgsl",
+            false,
+            true,
+            Some(wgpu::DepthBiasState {
+                constant: 2,
+                slope_scale: 2.0,
+                clamp: 0.0
+            }),
+            true,
+            true,
+            true,
+            false,
+            1
+        );
+
+        let object_pipelines = ObjectShadowPipelines::new(device, &layer_bindgroup_layout, joint_bindgroup_layout);
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Lights Buffer"),
+            size: size_of::<LightsUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        Self {
+            lights: Vec::new(),
+            pipeline,
+            object_pipelines,
+            layers,
+            used_layers: 0,
+            uniform_buffer,
+            shadow_view,
+            shadow_sampler
+        }
+    }
+
+    pub fn add(&mut self, light: Light) -> Arc<RwLock<Light>> {
+        let light = Arc::new(RwLock::new(light));
+        self.lights.push(light.clone());
+
+        light
+    }
+
+    pub fn remove(&mut self, light: &Arc<RwLock<Light>>) {
+        self.lights.retain(|other| !Arc::ptr_eq(other, light));
+    }
+
+    pub fn uniform_buffer(&self) -> &wgpu::Buffer {
+        &self.uniform_buffer
+    }
+
+    /**
+     picks this frame's lights and hands out shadow layers, returns how many lights were visible and how many culled
+    */
+    pub fn update(&mut self, queue: &wgpu::Queue, camera_position: Point3<f32>, frustum: &Frustum) -> (u32, u32) {
+        let mut visible = self.lights.iter()
+            .map(|light| light.read())
+            .filter(|light| light.intensity > 0. && light.range > 0.)
+            .filter(|light| {
+                let (min, max) = light.bounds();
+                frustum.intersects_aabb(min, max)
+            })
+            .collect::<Vec<_>>();
+
+        let culled = (self.lights.len() - visible.len()) as u32;
+
+        //by how close the camera is to the edge of each light's reach, so the camera standing inside a big light keeps it first
+        visible.sort_by(|a, b| {
+            let a = a.position.distance(camera_position) - a.range;
+            let b = b.position.distance(camera_position) - b.range;
+            a.total_cmp(&b)
+        });
+        visible.truncate(MAX_VISIBLE_LIGHTS);
+
+        let mut uniform = LightsUniform {
+            lights: [RawLight::zeroed(); MAX_VISIBLE_LIGHTS],
+            shadow_matrices: [[[0.0; 4]; 4]; SHADOW_LAYERS],
+            count: [visible.len() as u32, 0, 0, 0]
+        };
+
+        self.used_layers = 0;
+        for (raw, light) in uniform.lights.iter_mut().zip(&visible) {
+            let needed = light.shadow_layer_count();
+
+            let first_layer = if needed > 0 && self.used_layers + needed <= SHADOW_LAYERS {
+                for (i, view_proj) in light.shadow_view_projs().into_iter().enumerate() {
+                    let layer = &mut self.layers[self.used_layers + i];
+                    layer.view_proj = view_proj;
+
+                    uniform.shadow_matrices[self.used_layers + i] = view_proj.into();
+                    queue.write_buffer(&layer.buffer, 0, bytemuck::cast_slice(&[uniform.shadow_matrices[self.used_layers + i]]));
+                }
+
+                self.used_layers += needed;
+                (self.used_layers - needed) as f32
+            } else {
+                -1.
+            };
+
+            *raw = raw_light(light, first_layer);
+        }
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+
+        (visible.len() as u32, culled)
+    }
+
+    /**
+     terrain and object depth into every shadow layer handed out by the last update
+    */
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, chunk_manager: &ChunkManager, objects: &ShadowObjects) {
+        for layer in self.layers.iter().take(self.used_layers) {
+            let frustum = Frustum::from_matrix(&layer.view_proj);
+
+            let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("light shadow pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(
+                    wgpu::RenderPassDepthStencilAttachment {
+                        view: &layer.view,
+                        depth_ops: Some(
+                            wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(1.0),
+                                store: wgpu::StoreOp::Store
+                            }
+                        ),
+                        stencil_ops: None,
+                    }
+                ),
+                timestamp_writes: None,
+                occlusion_query_set: None
+            });
+
+            shadow_pass.set_pipeline(&self.pipeline);
+            shadow_pass.set_bind_group(0, &layer.bindgroup, &[]);
+            draw_shadow_casters(&mut shadow_pass, chunk_manager, &frustum);
+            draw_object_shadow_casters(&mut shadow_pass, &self.object_pipelines, objects);
+        }
+    }
+}
+
+fn raw_light(light: &Light, first_layer: f32) -> RawLight {
+    let (direction, cone, inner_cone, spot) = match light.kind {
+        LightKind::Point => ([0.0; 3], -1.0, -1.0, 0.0),
+        LightKind::Spot { direction, inner_angle, outer_angle } => (
+            direction.normalize().into(),
+            outer_angle.to_radians().cos(),
+            inner_angle.min(outer_angle).to_radians().cos(),
+            1.0
+        )
+    };
+
+    let texel_size = (light.shadow_fov() * 0.5).to_radians().tan() * 2.0 / SHADOW_RESOLUTION as f32;
+
+    RawLight {
+        position_range: [light.position.x, light.position.y, light.position.z, light.range],
+        color_intensity: [light.color.r, light.color.g, light.color.b, light.intensity],
+        direction_cone: [direction[0], direction[1], direction[2], cone],
+        params: [inner_cone, first_layer, spot, texel_size]
+    }
+}
@@ -4,4 +4,13 @@ pub mod renderer;
 pub mod renderpipeline;
 pub mod renderstorage;
 pub mod raycaster;
-pub mod depthsort;
\ No newline at end of file
+pub mod depthsort;
+pub mod gpu;
+pub mod offscreen;
+pub mod frustum;
+pub mod sunshadows;
+pub mod lights;
+pub mod sky;
+pub mod fog;
+pub mod postprocess;
+pub mod antialiasing;
\ No newline at end of file
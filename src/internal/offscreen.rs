@@ -0,0 +1,175 @@
+use std::{path::Path, sync::mpsc, time::Duration};
+
+use cgmath::Vector2;
+use image::RgbaImage;
+
+use crate::{state::workspace::Workspace, vox::worker_threads::{receive_lod_results, receive_worker_results, spawn_chunk_creation_loop, spawn_chunk_meshing_loop, spawn_lod_loop}};
+
+use super::{antialiasing::Antialiasing, camera::create_camera_bindgroup_layout, gpu::{request_gpu, GpuContext, GraphicsConfig}, renderer::MainRenderer};
+
+pub const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/**
+ renders MainRenderer output into a texture instead of a window surface and reads it back.
+ works on software adapters, so frames can be compared in image tests
+*/
+pub struct OffscreenRenderer {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub renderer: MainRenderer,
+    pub camera_bindgroup_layout: wgpu::BindGroupLayout,
+    target: wgpu::Texture,
+    width: u32,
+    height: u32
+}
+
+impl OffscreenRenderer {
+    pub async fn new(width: u32, height: u32) -> anyhow::Result<Self> {
+        let config = GraphicsConfig::load();
+
+        let instance = config.create_instance();
+
+        let GpuContext { device, queue, texture_binding_mode, slice_draw_mode, msaa_sample_counts, .. } = request_gpu(&instance, &config, None).await?;
+
+        let camera_bindgroup_layout = create_camera_bindgroup_layout(&device);
+
+        let renderer = MainRenderer::new(
+            &device, &queue, OFFSCREEN_FORMAT,
+            &camera_bindgroup_layout, (width, height),
+            texture_binding_mode, slice_draw_mode, config.post_processing.clone(),
+            Antialiasing::new(config.antialiasing, msaa_sample_counts)
+        );
+
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: OFFSCREEN_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[]
+        });
+
+        Ok(Self {
+            device,
+            queue,
+            renderer,
+            camera_bindgroup_layout,
+            target,
+            width,
+            height
+        })
+    }
+
+    pub fn render(&mut self, workspace: &mut Workspace) -> anyhow::Result<RgbaImage> {
+        workspace.current_camera.update_matrices(&self.queue);
+
+        let view = self.target.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Offscreen Encoder")
+        });
+
+        self.renderer.render_surface(&self.device, &self.queue, &view, &mut encoder, workspace);
+
+        //rows of a texture copy have to be 256 byte aligned
+        let unpadded_row = 4 * self.width;
+        let padded_row = unpadded_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("offscreen readback buffer"),
+            size: (padded_row * self.height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.target,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(self.height),
+                }
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1
+            }
+        );
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = readback.slice(..);
+        let (send, recv) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |res| send.send(res).unwrap());
+        self.device.poll(wgpu::Maintain::Wait);
+        recv.recv()??;
+
+        let mut pixels = Vec::with_capacity((unpadded_row * self.height) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(padded_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_row as usize]);
+            }
+        }
+        readback.unmap();
+
+        RgbaImage::from_raw(self.width, self.height, pixels).ok_or_else(|| anyhow::anyhow!("Readback size mismatch"))
+    }
+}
+
+/**
+ generates the world around the origin without a window, waits for every chunk in render distance to be meshed
+ and every lod tile to be built, then writes one frame to a png
+*/
+pub fn render_world_to_file(path: &Path, width: u32, height: u32, timeout: Duration) -> anyhow::Result<()> {
+    let mut offscreen = pollster::block_on(OffscreenRenderer::new(width, height))?;
+
+    let mut workspace = Workspace::new(&offscreen.device, &offscreen.camera_bindgroup_layout, width, height, None, offscreen.renderer.antialiasing.clone());
+
+    let (sendmesh, getmesh) = spawn_chunk_meshing_loop(3);
+    let (sendchunk, getchunk) = spawn_chunk_creation_loop(4, workspace.chunk_manager.seed);
+    workspace.chunk_manager.generate_chunks(&offscreen.device, &sendchunk, Vector2::new(0., 0.));
+    let (sendlod, getlod) = spawn_lod_loop(2, workspace.chunk_manager.seed);
+    workspace.chunk_manager.lod.request_tiles(&sendlod, Vector2::new(0, 0));
+
+    let started = instant::Instant::now();
+    let chunk_count = (workspace.chunk_manager.render_distance * 2 + 1).pow(2);
+
+    loop {
+        receive_worker_results(&mut workspace.chunk_manager, &offscreen.device, &offscreen.queue, &sendmesh, &getmesh, &getchunk);
+        workspace.chunk_manager.on_frame_action(&offscreen.device, &sendmesh);
+        receive_lod_results(&mut workspace.chunk_manager, &offscreen.device, &getlod);
+
+        let generated = workspace.chunk_manager.chunks.len() as u32 == chunk_count;
+        if generated && workspace.chunk_manager.unresolved_meshes.is_empty() && workspace.chunk_manager.all_slices_meshed() && workspace.chunk_manager.lod.all_tiles_ready() {
+            break;
+        }
+
+        if started.elapsed() > timeout {
+            anyhow::bail!("World wasn't ready after {:?}", timeout);
+        }
+
+        std::thread::sleep(Duration::from_millis(5));
+    }
+
+    let image = offscreen.render(&mut workspace)?;
+    image.save(path)?;
+
+    println!("Rendered offscreen frame to {}", path.display());
+
+    Ok(())
+}
@@ -0,0 +1,534 @@
+use std::mem::size_of;
+
+use serde::Deserialize;
+
+use crate::engine::assetfs::read_asset;
+
+use super::renderpipeline::create_fullscreen_pipeline;
+
+/**
+ everything in the scene renders into this, the post process chain brings it down to the output format
+*/
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+const MAX_BLOOM_LEVELS: usize = 6;
+const IDENTITY_LUT_SIZE: u32 = 16;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TonemapOperator {
+    //clamps, anything over 1 clips
+    None,
+    Reinhard,
+    #[default]
+    Aces,
+    Filmic
+}
+
+/**
+ the post_processing section of data/graphics.json, every effect can be turned off on its own
+*/
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PostProcessConfig {
+    pub bloom: bool,
+    //scene brightness where bloom starts, 1 is the brightest a lit block gets
+    pub bloom_threshold: f32,
+    pub bloom_intensity: f32,
+    pub exposure: f32,
+    pub tonemap: TonemapOperator,
+    pub colour_grading: bool,
+    //strip of size*size by size texels: red across each square, green down, blue from square to square.
+    //a neutral lut is used when there's none
+    pub colour_grading_lut: Option<String>,
+    pub gamma_correction: bool,
+    //display gamma, 2.2 leaves an srgb output as it is
+    pub gamma: f32
+}
+
+impl Default for PostProcessConfig {
+    fn default() -> Self {
+        Self {
+            bloom: true,
+            bloom_threshold: 1.0,
+            bloom_intensity: 0.4,
+            exposure: 1.0,
+            tonemap: TonemapOperator::Aces,
+            colour_grading: false,
+            colour_grading_lut: None,
+            gamma_correction: true,
+            gamma: 2.2
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct BloomUniform {
+    //threshold, knee, upsample radius
+    params: [f32; 4]
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct PostUniform {
+    //exposure, bloom intensity, gamma exponent
+    params: [f32; 4],
+    //tonemap operator, bloom, colour grading, luma in alpha for fxaa
+    modes: [u32; 4]
+}
+
+struct BloomLevel {
+    view: wgpu::TextureView,
+    //this level as the source of the next pass
+    bindgroup: wgpu::BindGroup
+}
+
+/**
+ everything sized to the screen
+*/
+struct PostTargets {
+    scene_view: wgpu::TextureView,
+    prefilter_bindgroup: wgpu::BindGroup,
+    bloom_levels: Vec<BloomLevel>,
+    composite_bindgroup: wgpu::BindGroup,
+    //the composited image in the output format, fxaa reads it back from here
+    ldr_view: wgpu::TextureView,
+    fxaa_bindgroup: wgpu::BindGroup
+}
+
+/**
+ the hdr scene target and the chain that takes it to the screen: bloom, exposure, tonemapping, colour grading
+ and gamma, then a blit into the output, or fxaa on the way there
+*/
+pub struct PostProcess {
+    pub config: PostProcessConfig,
+    output_format: wgpu::TextureFormat,
+    prefilter_pipeline: wgpu::RenderPipeline,
+    downsample_pipeline: wgpu::RenderPipeline,
+    upsample_pipeline: wgpu::RenderPipeline,
+    composite_pipeline: wgpu::RenderPipeline,
+    fxaa_pipeline: wgpu::RenderPipeline,
+    bloom_bindgroup_layout: wgpu::BindGroupLayout,
+    composite_bindgroup_layout: wgpu::BindGroupLayout,
+    fxaa_bindgroup_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    lut_view: wgpu::TextureView,
+    bloom_buffer: wgpu::Buffer,
+    post_buffer: wgpu::Buffer,
+    targets: PostTargets
+}
+
+impl PostProcess {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, output_format: wgpu::TextureFormat, config: PostProcessConfig, width: u32, height: u32) -> Self {
+        let bloom_bindgroup_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("bloom bind group layout"),
+            entries: &[
+                texture_entry(0, wgpu::TextureViewDimension::D2),
+                sampler_entry(1),
+                uniform_entry(2)
+            ]
+        });
+
+        let composite_bindgroup_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("post composite bind group layout"),
+            entries: &[
+                texture_entry(0, wgpu::TextureViewDimension::D2),
+                texture_entry(1, wgpu::TextureViewDimension::D2),
+                sampler_entry(2),
+                texture_entry(3, wgpu::TextureViewDimension::D3),
+                uniform_entry(4)
+            ]
+        });
+
+        let fxaa_bindgroup_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("fxaa bind group layout"),
+            entries: &[
+                texture_entry(0, wgpu::TextureViewDimension::D2),
+                sampler_entry(1)
+            ]
+        });
+
+        let bloom_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("bloom pipeline layout"),
+            bind_group_layouts: &[&bloom_bindgroup_layout],
+            push_constant_ranges: &[]
+        });
+
+        let composite_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post composite pipeline layout"),
+            bind_group_layouts: &[&composite_bindgroup_layout],
+            push_constant_ranges: &[]
+        });
+
+        let fxaa_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("fxaa pipeline layout"),
+            bind_group_layouts: &[&fxaa_bindgroup_layout],
+            push_constant_ranges: &[]
+        });
+
+        let additive = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add
+            },
+            alpha: wgpu::BlendComponent::REPLACE
+        };
+
+        let prefilter_pipeline = create_fullscreen_pipeline("bloom prefilter pipeline", device, &bloom_pipeline_layout, HDR_FORMAT, "shaders/bloomshader.wgsl", "fs_prefilter", None);
+        let downsample_pipeline = create_fullscreen_pipeline("bloom downsample pipeline", device, &bloom_pipeline_layout, HDR_FORMAT, "shaders/bloomshader.wgsl", "fs_downsample", None);
+        let upsample_pipeline = create_fullscreen_pipeline("bloom upsample pipeline", device, &bloom_pipeline_layout, HDR_FORMAT, "shaders/bloomshader.wgsl", "fs_upsample", Some(additive));
+        let composite_pipeline = create_fullscreen_pipeline("post composite pipeline", device, &composite_pipeline_layout, output_format, "shaders/postshader.wgsl", "fs_main", None);
+        let fxaa_pipeline = create_fullscreen_pipeline("fxaa pipeline", device, &fxaa_pipeline_layout, output_format, "shaders/fxaashader.wgsl", "fs_main", None);
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("post process sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let lut_view = create_grading_lut(device, queue, config.colour_grading_lut.as_deref());
+
+        let bloom_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Bloom Buffer"),
+            size: size_of::<BloomUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let post_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Post Process Buffer"),
+            size: size_of::<PostUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let targets = create_targets(device, &bloom_bindgroup_layout, &composite_bindgroup_layout, &fxaa_bindgroup_layout, &sampler, &lut_view, &bloom_buffer, &post_buffer, output_format, width, height);
+
+        Self {
+            config,
+            output_format,
+            prefilter_pipeline,
+            downsample_pipeline,
+            upsample_pipeline,
+            composite_pipeline,
+            fxaa_pipeline,
+            bloom_bindgroup_layout,
+            composite_bindgroup_layout,
+            fxaa_bindgroup_layout,
+            sampler,
+            lut_view,
+            bloom_buffer,
+            post_buffer,
+            targets
+        }
+    }
+
+    pub fn scene_view(&self) -> &wgpu::TextureView {
+        &self.targets.scene_view
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.targets = create_targets(device, &self.bloom_bindgroup_layout, &self.composite_bindgroup_layout, &self.fxaa_bindgroup_layout, &self.sampler, &self.lut_view, &self.bloom_buffer, &self.post_buffer, self.output_format, width, height);
+    }
+
+    /**
+     runs the chain on whatever has been drawn into scene_view and writes the result to output_view
+    */
+    pub fn render(&self, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder, output_view: &wgpu::TextureView, fxaa: bool) {
+        let config = &self.config;
+
+        let gamma_exponent = match (config.gamma_correction, self.output_format.is_srgb()) {
+            (false, _) => 1.0,
+            //the output encodes 2.2 itself, only the difference is left
+            (true, true) => 2.2 / config.gamma,
+            (true, false) => 1.0 / config.gamma
+        };
+
+        queue.write_buffer(&self.bloom_buffer, 0, bytemuck::cast_slice(&[BloomUniform {
+            params: [config.bloom_threshold, config.bloom_threshold * 0.5, 1.0, 0.0]
+        }]));
+
+        queue.write_buffer(&self.post_buffer, 0, bytemuck::cast_slice(&[PostUniform {
+            params: [config.exposure, config.bloom_intensity, gamma_exponent, 0.0],
+            modes: [config.tonemap as u32, config.bloom as u32, config.colour_grading as u32, fxaa as u32]
+        }]));
+
+        if config.bloom {
+            let levels = &self.targets.bloom_levels;
+
+            fullscreen_pass(encoder, "bloom prefilter pass", &levels[0].view, &self.prefilter_pipeline, &self.targets.prefilter_bindgroup, true);
+
+            for i in 1..levels.len() {
+                fullscreen_pass(encoder, "bloom downsample pass", &levels[i].view, &self.downsample_pipeline, &levels[i - 1].bindgroup, true);
+            }
+
+            for i in (0..levels.len() - 1).rev() {
+                fullscreen_pass(encoder, "bloom upsample pass", &levels[i].view, &self.upsample_pipeline, &levels[i + 1].bindgroup, false);
+            }
+        }
+
+        if fxaa {
+            fullscreen_pass(encoder, "post composite pass", &self.targets.ldr_view, &self.composite_pipeline, &self.targets.composite_bindgroup, true);
+            fullscreen_pass(encoder, "fxaa pass", output_view, &self.fxaa_pipeline, &self.targets.fxaa_bindgroup, true);
+        } else {
+            fullscreen_pass(encoder, "post composite pass", output_view, &self.composite_pipeline, &self.targets.composite_bindgroup, true);
+        }
+    }
+}
+
+fn fullscreen_pass(encoder: &mut wgpu::CommandEncoder, label: &str, target: &wgpu::TextureView, pipeline: &wgpu::RenderPipeline, bindgroup: &wgpu::BindGroup, clear: bool) {
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: target,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: if clear {wgpu::LoadOp::Clear(wgpu::Color::BLACK)} else {wgpu::LoadOp::Load},
+                store: wgpu::StoreOp::Store
+            }
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None
+    });
+
+    pass.set_pipeline(pipeline);
+    pass.set_bind_group(0, bindgroup, &[]);
+    pass.draw(0..3, 0..1);
+}
+
+fn create_targets(
+    device: &wgpu::Device,
+    bloom_bindgroup_layout: &wgpu::BindGroupLayout,
+    composite_bindgroup_layout: &wgpu::BindGroupLayout,
+    fxaa_bindgroup_layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    lut_view: &wgpu::TextureView,
+    bloom_buffer: &wgpu::Buffer,
+    post_buffer: &wgpu::Buffer,
+    output_format: wgpu::TextureFormat,
+    width: u32,
+    height: u32
+) -> PostTargets {
+    let scene_view = create_colour_texture(device, "hdr scene texture", HDR_FORMAT, width, height);
+
+    let bloom_bindgroup = |view: &wgpu::TextureView| device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("bloom bind group"),
+        layout: bloom_bindgroup_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view)
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler)
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: bloom_buffer.as_entire_binding()
+            }
+        ]
+    });
+
+    //each level its own texture, half the one before, down to a couple of texels. there's always a first one
+    let mut bloom_levels = Vec::new();
+    let (mut level_width, mut level_height) = (width, height);
+    while bloom_levels.is_empty() || (bloom_levels.len() < MAX_BLOOM_LEVELS && level_width >= 4 && level_height >= 4) {
+        level_width = (level_width / 2).max(1);
+        level_height = (level_height / 2).max(1);
+
+        let view = create_colour_texture(device, "bloom texture", HDR_FORMAT, level_width, level_height);
+        let bindgroup = bloom_bindgroup(&view);
+        bloom_levels.push(BloomLevel { view, bindgroup });
+    }
+
+    let prefilter_bindgroup = bloom_bindgroup(&scene_view);
+
+    let composite_bindgroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("post composite bind group"),
+        layout: composite_bindgroup_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&scene_view)
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&bloom_levels[0].view)
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(sampler)
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(lut_view)
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: post_buffer.as_entire_binding()
+            }
+        ]
+    });
+
+    let ldr_view = create_colour_texture(device, "ldr post texture", output_format, width, height);
+
+    let fxaa_bindgroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("fxaa bind group"),
+        layout: fxaa_bindgroup_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&ldr_view)
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler)
+            }
+        ]
+    });
+
+    PostTargets { scene_view, prefilter_bindgroup, bloom_levels, composite_bindgroup, ldr_view, fxaa_bindgroup }
+}
+
+fn create_colour_texture(device: &wgpu::Device, label: &str, format: wgpu::TextureFormat, width: u32, height: u32) -> wgpu::TextureView {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[]
+    }).create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/**
+ the lut from the given strip image, or a neutral one when there's none or it can't be read
+*/
+fn create_grading_lut(device: &wgpu::Device, queue: &wgpu::Queue, path: Option<&str>) -> wgpu::TextureView {
+    let loaded = path.and_then(|path| match load_lut_strip(path) {
+        Ok(lut) => Some(lut),
+        Err(e) => {
+            println!("Unable to load colour grading lut {}: {}", path, e);
+            None
+        }
+    });
+
+    let (size, texels) = loaded.unwrap_or_else(|| {
+        let n = IDENTITY_LUT_SIZE;
+        let texels = (0..n * n * n).flat_map(|i| {
+            let (r, g, b) = (i % n, (i / n) % n, i / (n * n));
+            [r, g, b].map(|c| (c * 255 / (n - 1)) as u8).into_iter().chain([255])
+        }).collect();
+        (n, texels)
+    });
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("colour grading lut"),
+        size: wgpu::Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: size
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D3,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[]
+    });
+
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All
+        },
+        &texels,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * size),
+            rows_per_image: Some(size)
+        },
+        wgpu::Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: size
+        }
+    );
+
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/**
+ rgba texels in x red, y green, z blue order
+*/
+fn load_lut_strip(path: &str) -> anyhow::Result<(u32, Vec<u8>)> {
+    let strip = image::load_from_memory(&read_asset(path)?)?.to_rgba8();
+    let size = strip.height();
+
+    if strip.width() != size * size {
+        anyhow::bail!("expected a {}x{} strip, got {}x{}", size * size, size, strip.width(), strip.height());
+    }
+
+    let mut texels = Vec::with_capacity((size * size * size * 4) as usize);
+    for b in 0..size {
+        for g in 0..size {
+            for r in 0..size {
+                texels.extend_from_slice(&strip.get_pixel(r + b * size, g).0);
+            }
+        }
+    }
+
+    Ok((size, texels))
+}
+
+fn texture_entry(binding: u32, view_dimension: wgpu::TextureViewDimension) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension,
+            multisampled: false
+        },
+        count: None
+    }
+}
+
+fn sampler_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None
+    }
+}
+
+fn uniform_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None
+    }
+}
@@ -1,51 +1,224 @@
-use std::{mem::{self, size_of}, num::NonZeroU32, sync::{Arc, RwLock, RwLockReadGuard}};
+use std::{mem::{self, size_of}, num::NonZeroU32, sync::{Arc, RwLockReadGuard}};
 
-use cgmath::{Matrix3, Matrix4, MetricSpace, Point3, SquareMatrix, Vector3};
+use cgmath::{Matrix3, MetricSpace, Point3, Vector3};
+use parking_lot::RwLock;
 use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use stopwatch::Stopwatch;
-use wgpu::{util::DeviceExt, BindGroupLayout, RenderPipeline, TextureFormat};
+use wgpu::{util::{DeviceExt, DrawIndexedIndirectArgs}, BindGroupLayout, RenderPipeline, TextureFormat};
 
-use crate::{engine::{surfacevertex::SurfaceVertex, texture::Texture, texture_loader::{initialize_load_textures, preload_textures}, vertex::{ModelVertex, Vertex}}, gen::{object::RawObject, spotlight::{RawSpotLight, Spotlight}}, state::workspace::Workspace, vox::chunk::{local_xz_to_index_dynamic, xz_to_index, Chunk, ChunkDataVertex, ChunkState}};
+use crate::{engine::{model_loader::{initialize_load_models, preload_models}, surfacevertex::SurfaceVertex, texture::Texture, texture_loader::{initialize_load_textures, preload_textures, TextureAnimationGlobals, TextureBindingMode}, vertex::{ModelVertex, SkinVertex, Vertex}}, gen::{light::Light, object::RawObject}, state::workspace::Workspace, vox::{chunk::{local_xz_to_index_dynamic, xz_to_index, Chunk, ChunkState}, lod::{create_lod_bindgroup_layout, LodVertex}, meshpool::{create_mesh_pool_bindgroup_layout, SliceDrawMode}, structure_exporter::OutlineVertex, visibility::find_visible_slices}};
 
-use super::{camera::Camera, depthsort::sort_chunk_transparent_quads, renderpipeline::create_render_pipeline, renderstorage::RenderStorage};
+use super::{camera::Camera, frustum::{CullStats, Frustum}, depthsort::TransparencySorter, fog::FogMedium, antialiasing::Antialiasing, postprocess::{PostProcess, PostProcessConfig, HDR_FORMAT}, renderpipeline::create_render_pipeline, lights::LightManager, renderstorage::RenderStorage, sky::Sky, sunshadows::{ShadowObjects, SunShadows}};
 
-#[repr(C)]
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-struct RendererGlobals {
-    current_light_model: [[f32; 4]; 4]
+/**
+ the layouts the scene pipelines are built from, kept so they can be rebuilt for another sample count
+*/
+struct ScenePipelineLayouts {
+    surface: wgpu::PipelineLayout,
+    lod: wgpu::PipelineLayout,
+    outline: wgpu::PipelineLayout,
+    object: wgpu::PipelineLayout
+}
+
+/**
+ everything that draws into the scene's colour and depth targets, so everything that has to match their sample count
+*/
+struct ScenePipelines {
+    surface: RenderPipeline,
+    surface_wireframe: RenderPipeline,
+    transparent_surface: RenderPipeline,
+    lod: RenderPipeline,
+    outline: RenderPipeline,
+    object: RenderPipeline,
+    skinned_object: RenderPipeline
+}
+
+impl ScenePipelines {
+    fn new(device: &wgpu::Device, layouts: &ScenePipelineLayouts, sample_count: u32) -> Self {
+        Self {
+            surface: create_render_pipeline(
+                "surface_pipeline",
+                device,
+                &layouts.surface,
+                HDR_FORMAT,
+                Some(TextureFormat::Depth32Float),
+                &[SurfaceVertex::desc()],
+                "shaders/surfaceshader.wgsl",
+                true,
+                true,
+                None,
+                false,
+                false,
+                false,
+                false,
+                sample_count
+            ),
+            surface_wireframe: create_render_pipeline(
+                "surface_pipeline wireframe",
+                device,
+                &layouts.surface,
+                HDR_FORMAT,
+                Some(TextureFormat::Depth32Float),
+                &[SurfaceVertex::desc()],
+                "shaders/surfaceshader.wgsl",
+                true,
+                true,
+                None,
+                false,
+                false,
+                false,
+                true,
+                sample_count
+            ),
+            transparent_surface: create_render_pipeline(
+                "surface_pipeline",
+                device,
+                &layouts.surface,
+                HDR_FORMAT,
+                Some(TextureFormat::Depth32Float),
+                &[SurfaceVertex::desc()],
+                "shaders/surfaceshader.wgsl",
+                true,
+                false,
+                None,
+                false,
+                false,
+                false,
+                false,
+                sample_count
+            ),
+            lod: create_render_pipeline(
+                "lod_pipeline",
+                device,
+                &layouts.lod,
+                HDR_FORMAT,
+                Some(TextureFormat::Depth32Float),
+                &[LodVertex::desc()],
+                "shaders/lodshader.wgsl",
+                false,
+                true,
+                None,
+                false,
+                false,
+                false,
+                false,
+                sample_count
+            ),
+            outline: create_render_pipeline(
+                "outline_pipeline",
+                device,
+                &layouts.outline,
+                HDR_FORMAT,
+                Some(TextureFormat::Depth32Float),
+                &[OutlineVertex::desc()],
+                "shaders/outlineshader.wgsl",
+                false,
+                false,
+                None,
+                false,
+                false,
+                false,
+                true,
+                sample_count
+            ),
+            object: create_render_pipeline(
+                "object_pipeline",
+                device,
+                &layouts.object,
+                HDR_FORMAT,
+                Some(TextureFormat::Depth32Float),
+                &[ModelVertex::desc(), RawObject::desc()],
+                "shaders/objectshader.wgsl",
+                true,
+                true,
+                None,
+                false,
+                false,
+                false,
+                false,
+                sample_count
+            ),
+            skinned_object: create_render_pipeline(
+                "skinned_object_pipeline",
+                device,
+                &layouts.object,
+                HDR_FORMAT,
+                Some(TextureFormat::Depth32Float),
+                &[ModelVertex::desc(), RawObject::desc(), SkinVertex::desc()],
+                "shaders/skinnedobjectshader.wgsl",
+                true,
+                true,
+                None,
+                false,
+                false,
+                false,
+                false,
+                sample_count
+            )
+        }
+    }
+}
+
+/**
+ the multisampled colour target (none without msaa, the scene then goes straight into the post process's hdr target)
+ and the depth target, which has to share its sample count
+*/
+struct SceneTargets {
+    colour_view: Option<wgpu::TextureView>,
+    depth_view: wgpu::TextureView
+}
+
+impl SceneTargets {
+    fn new(device: &wgpu::Device, width: u32, height: u32, sample_count: u32) -> Self {
+        let colour_view = (sample_count > 1).then(|| create_scene_texture(device, "msaa scene texture", HDR_FORMAT, width, height, sample_count));
+        let depth_view = create_scene_texture(device, "depth texture", TextureFormat::Depth32Float, width, height, sample_count);
+
+        Self { colour_view, depth_view }
+    }
 }
 
 pub struct MainRenderer {
-    surface_pipeline: RenderPipeline,
-    surface_pipeline_wireframe: RenderPipeline,
-    transparent_surface_pipeline: RenderPipeline,
-    object_pipeline: RenderPipeline,
+    pipelines: ScenePipelines,
+    pipeline_layouts: ScenePipelineLayouts,
     material_bind_group_layout: BindGroupLayout,
     texture_format: wgpu::TextureFormat,
-    depth_texture: Texture,
+    scene_targets: SceneTargets,
     texture_bindgroup: wgpu::BindGroup,
     texture_bindgroup_layout: wgpu::BindGroupLayout,
+    texture_animation_buffer: wgpu::Buffer,
+    start_time: instant::Instant,
     surface_texture_format: wgpu::TextureFormat,
     pub render_storage: RenderStorage,
-    shadow_texture: Texture,
-    spotlights: Vec<Arc<RwLock<Spotlight>>>,
-    shadow_pipeline: wgpu::RenderPipeline,
-    shadow_bindgroup_layout: wgpu::BindGroupLayout,
-    globals: RendererGlobals,
+    pub lights: LightManager,
     global_bindgroup_layout: wgpu::BindGroupLayout,
-    shadow_prebindgroup_layout: wgpu::BindGroupLayout,
+    //group 2 of the object pipelines, the joint palette skinned objects read
+    global_bindgroup: wgpu::BindGroup,
+    joint_buffer: wgpu::Buffer,
+    last_object_update: instant::Instant,
     width: u32,
     height: u32,
-    wireframe_mode: bool
+    wireframe_mode: bool,
+    slice_draw_mode: SliceDrawMode,
+    transparency_sorter: TransparencySorter,
+    sun_shadows: SunShadows,
+    sky: Sky,
+    pub post_process: PostProcess,
+    pub antialiasing: Antialiasing,
+    slice_indirect_buffer: wgpu::Buffer,
+    object_instance_buffer: wgpu::Buffer,
+    pub cull_stats: CullStats
 }
 
 impl MainRenderer {
-    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, surface_texture_format: wgpu::TextureFormat, camera_bindgroup_layout: &BindGroupLayout, screendims: (u32, u32)) -> Self {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, surface_texture_format: wgpu::TextureFormat, camera_bindgroup_layout: &BindGroupLayout, screendims: (u32, u32), texture_binding_mode: TextureBindingMode, slice_draw_mode: SliceDrawMode, post_processing: PostProcessConfig, antialiasing: Antialiasing) -> Self {
         let texture_format = wgpu::TextureFormat::Rgba8UnormSrgb;
         
         preload_textures(device, queue, texture_format);
+        //models register their material textures, so they're read before the texture bind group is built
+        let models = preload_models(device, queue, texture_format);
 
-        let (texture_bindgroup, texture_bindgroup_layout) = initialize_load_textures(device, queue, texture_format);
+        let (texture_bindgroup, texture_bindgroup_layout, texture_animation_buffer) = initialize_load_textures(device, queue, texture_format, texture_binding_mode);
+        initialize_load_models(device, models);
 
         let material_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Main renderer material bind group layout"),
@@ -101,46 +274,15 @@ impl MainRenderer {
             ]
         });
 
-        let shadow_bindgroup_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("shadows bindgroup"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        sample_type: wgpu::TextureSampleType::Depth,
-                        view_dimension: wgpu::TextureViewDimension::D2Array,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ]
-        });
-
         let global_bindgroup_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("global bindgroup layout"),
             entries: &[
+                //joint palette for skinned objects
                 wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    visibility: wgpu::ShaderStages::VERTEX,
                     ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
                         has_dynamic_offset: false,
                         min_binding_size: None,
                     },
@@ -149,435 +291,318 @@ impl MainRenderer {
             ]
         });
 
+        let lights = LightManager::new(device, &global_bindgroup_layout);
+        let sun_shadows = SunShadows::new(device, &lights, &global_bindgroup_layout);
+
         let surface_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("surface pipeline layout"),
-            bind_group_layouts: &[&texture_bindgroup_layout, &camera_bindgroup_layout],
+            bind_group_layouts: &[&texture_bindgroup_layout, &camera_bindgroup_layout, &create_mesh_pool_bindgroup_layout(device), &sun_shadows.bindgroup_layout],
             push_constant_ranges: &[]
         });
 
-        let shadow_prebindgroup_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("empty shadow bindgroup layout"),
-            entries: &[]
+        let lod_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("lod pipeline layout"),
+            bind_group_layouts: &[&texture_bindgroup_layout, &camera_bindgroup_layout, &create_lod_bindgroup_layout(device), &sun_shadows.bindgroup_layout],
+            push_constant_ranges: &[]
         });
 
-        let surface_pipeline = create_render_pipeline(
-            "surface_pipeline",
-            device,
-            &surface_pipeline_layout,
-            surface_texture_format,
-            Some(TextureFormat::Depth32Float),
-            &[SurfaceVertex::desc(), ChunkDataVertex::desc()],
-            "res/shaders/surfaceshader.wgsl",
-            true,
-            true,
-            None,
-            false,
-            false,
-            false,
-            false
-        );
-
-        let surface_pipeline_wireframe = create_render_pipeline(
-            "surface_pipeline wireframe",
-            device,
-            &surface_pipeline_layout,
-            surface_texture_format,
-            Some(TextureFormat::Depth32Float),
-            &[SurfaceVertex::desc(), ChunkDataVertex::desc()],
-            "res/shaders/surfaceshader.wgsl",
-            true,
-            true,
-            None,
-            false,
-            false,
-            false,
-            true
-        );
-
-        let transparent_surface_pipeline = create_render_pipeline(
-            "surface_pipeline",
-            device,
-            &surface_pipeline_layout,
-            surface_texture_format,
-            Some(TextureFormat::Depth32Float),
-            &[SurfaceVertex::desc(), ChunkDataVertex::desc()],
-            "res/shaders/surfaceshader.wgsl",
-            true,
-            false,
-            None,
-            false,
-            false,
-            false,
-            false
-        );
-
-        let object_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("object pipeline layout"),
-            bind_group_layouts: &[&texture_bindgroup_layout, &camera_bindgroup_layout, &global_bindgroup_layout, &shadow_bindgroup_layout],
+        let outline_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("outline pipeline layout"),
+            bind_group_layouts: &[&camera_bindgroup_layout],
             push_constant_ranges: &[]
         });
 
-        let shadow_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        let object_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("object pipeline layout"),
-            bind_group_layouts: &[&texture_bindgroup_layout, &camera_bindgroup_layout, &global_bindgroup_layout, &shadow_prebindgroup_layout],
+            bind_group_layouts: &[&texture_bindgroup_layout, &camera_bindgroup_layout, &global_bindgroup_layout, &sun_shadows.bindgroup_layout],
             push_constant_ranges: &[]
         });
 
-        let object_pipeline = create_render_pipeline(
-            "object_pipeline",
-            device,
-            &object_pipeline_layout,
-            surface_texture_format,
-            Some(TextureFormat::Depth32Float),
-            &[ModelVertex::desc(), RawObject::desc()],
-            "res/shaders/objectshader.wgsl",
-            true,
-            true,
-            None,
-            false,
-            false,
-            false,
-            false
-        );
-
-        let shadow_pipeline = create_render_pipeline(
-            "shadow pipeline",
-            device,
-            &shadow_pipeline_layout,
-            surface_texture_format,
-            Some(TextureFormat::Depth32Float),
-            &[ModelVertex::desc(), RawObject::desc()],
-            "res/shaders/objectshader.wgsl",
-            true,
-            true,
-            Some(wgpu::DepthBiasState {
-                constant: 2, // corresponds to bilinear filtering
-                slope_scale: 2.0,
-                clamp: 0.0,
-            }),
-            true,
-            true,
-            true,
-            false
-        );
-
-        let depth_texture = Texture::from_empty("depth texture", &device, wgpu::TextureFormat::Depth32Float, screendims.0, screendims.1, wgpu::FilterMode::Linear);
+        let pipeline_layouts = ScenePipelineLayouts {
+            surface: surface_pipeline_layout,
+            lod: lod_pipeline_layout,
+            outline: outline_pipeline_layout,
+            object: object_pipeline_layout
+        };
 
-        let shadow_texture = Texture::from_empty_shadows("shadow texture", &device, wgpu::TextureFormat::Depth32Float, 512, 512, wgpu::FilterMode::Linear, 100);
+        let sample_count = antialiasing.msaa_samples();
+        let pipelines = ScenePipelines::new(device, &pipeline_layouts, sample_count);
+        let scene_targets = SceneTargets::new(device, screendims.0, screendims.1, sample_count);
 
-        let globals = RendererGlobals {
-            current_light_model: Matrix4::identity().into()
-        };
+        let joint_buffer = create_joint_buffer(device, 64 * size_of::<[[f32; 4]; 4]>() as u64);
+        let global_bindgroup = create_global_bindgroup(device, &global_bindgroup_layout, &joint_buffer);
 
         Self {
             material_bind_group_layout,
-            surface_pipeline,
-            transparent_surface_pipeline,
+            pipelines,
+            pipeline_layouts,
             texture_format,
             texture_bindgroup,
             texture_bindgroup_layout,
-            depth_texture,
-            object_pipeline,
+            texture_animation_buffer,
+            start_time: instant::Instant::now(),
+            scene_targets,
             surface_texture_format,
             render_storage: RenderStorage::new(),
-            shadow_texture,
-            spotlights: Vec::new(),
-            shadow_pipeline,
-            shadow_bindgroup_layout,
-            globals,
+            lights,
             global_bindgroup_layout,
-            shadow_prebindgroup_layout,
+            global_bindgroup,
+            joint_buffer,
+            last_object_update: instant::Instant::now(),
             width: screendims.0,
             height: screendims.1,
             wireframe_mode: false,
-            surface_pipeline_wireframe
+            slice_draw_mode,
+            transparency_sorter: TransparencySorter::new(),
+            sun_shadows,
+            sky: Sky::new(device, HDR_FORMAT, camera_bindgroup_layout, sample_count),
+            post_process: PostProcess::new(device, queue, surface_texture_format, post_processing, screendims.0, screendims.1),
+            antialiasing,
+            slice_indirect_buffer: create_slice_indirect_buffer(device, 4096 * size_of::<DrawIndexedIndirectArgs>() as u64),
+            object_instance_buffer: create_object_instance_buffer(device, 64 * size_of::<RawObject>() as u64),
+            cull_stats: CullStats::default()
         }
     }
 
-    pub fn create_spotlight(&mut self, position: Point3<f32>, target: Point3<f32>) -> Arc<RwLock<Spotlight>> {
-        let view = self.shadow_texture.texture.create_view(&wgpu::TextureViewDescriptor {
-            label: Some("shadow"),
-            format: None,
-            dimension: Some(wgpu::TextureViewDimension::D2),
-            aspect: wgpu::TextureAspect::All,
-            base_mip_level: 0,
-            mip_level_count: None,
-            base_array_layer: 0, //change this for each one
-            array_layer_count: Some(1)
-        });
+    /**
+     indirect args for this frame's slices, the buffer grows to fit
+    */
+    fn write_slice_draws(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, draws: &[DrawIndexedIndirectArgs]) {
+        let size = (draws.len() * size_of::<DrawIndexedIndirectArgs>()) as u64;
 
-        let spotlight = Spotlight::new(position, target, 70., 0.1, 400., self.width as f32 / self.height as f32, view);
+        if size > self.slice_indirect_buffer.size() {
+            self.slice_indirect_buffer = create_slice_indirect_buffer(device, size.next_power_of_two());
+        }
 
-        let lock = Arc::new(RwLock::new(spotlight));
+        let bytes = draws.iter().flat_map(|d| d.as_bytes().iter().copied()).collect::<Vec<u8>>();
+        queue.write_buffer(&self.slice_indirect_buffer, 0, &bytes);
+    }
 
-        self.spotlights.push(lock.clone());
+    /**
+     recreates everything sized to the screen. zero sized targets aren't allowed, so callers skip this while minimised
+    */
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.scene_targets = SceneTargets::new(device, width, height, self.antialiasing.msaa_samples());
+        self.post_process.resize(device, width, height);
+    }
+
+    /**
+     switches to the workspace's antialiasing. a new sample count needs new scene targets and every pipeline
+     that draws into them
+    */
+    fn set_antialiasing(&mut self, device: &wgpu::Device, antialiasing: Antialiasing) {
+        let sample_count = antialiasing.msaa_samples();
+
+        if sample_count != self.antialiasing.msaa_samples() {
+            self.pipelines = ScenePipelines::new(device, &self.pipeline_layouts, sample_count);
+            self.sky.set_sample_count(device, sample_count);
+            self.scene_targets = SceneTargets::new(device, self.width, self.height, sample_count);
+        }
 
-        lock//view, forwarddepth
+        self.antialiasing = antialiasing;
     }
 
-    pub fn render_objects(&mut self, 
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        output_texture: &mut wgpu::SurfaceTexture, 
-        output_view: &wgpu::TextureView, 
-        encoder: &mut wgpu::CommandEncoder,
-        camera_bindgroup: &wgpu::BindGroup,
-        workspace: &Workspace
-    ) {
-        // let frame_start = Stopwatch::start_new();
-        // let spotlight_raws: Vec<RawSpotLight> = self.spotlights.par_iter().map(|v| v.read().unwrap().get_raw().clone()).collect();
-
-        // let spotlight_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        //     label: Some("object buffer"),
-        //     contents: bytemuck::cast_slice(&spotlight_raws),
-        //     usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC
-        // });
-
-        // let empty_shadows_bindgroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        //     label: Some("empty shadow bindgroup"),
-        //     layout: &self.shadow_prebindgroup_layout,
-        //     entries: &[]
-        // });
-
-        // let shadows_bindgroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        //     label: Some("shadow bindgroup"),
-        //     layout: &self.shadow_bindgroup_layout,
-        //     entries: &[
-        //         wgpu::BindGroupEntry {
-        //             binding: 0,
-        //             resource: spotlight_buffer.as_entire_binding()
-        //         },
-        //         wgpu::BindGroupEntry {
-        //             binding: 1,
-        //             resource: wgpu::BindingResource::TextureView(&self.shadow_texture.view),
-        //         },
-        //         wgpu::BindGroupEntry {
-        //             binding: 2,
-        //             resource: wgpu::BindingResource::Sampler(&self.shadow_texture.sampler),
-        //         },
-        //     ]
-        // });
-
-        // let mut buffers: Vec<wgpu::Buffer> = Vec::new();
-
-        // for obj in self.render_storage.get_objects() {
-        //     let raw = vec![obj.get_raw().clone()];
-            
-        //     let obj_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        //         label: Some("some object buffer"),
-        //         contents: bytemuck::cast_slice(&raw),
-        //         usage: wgpu::BufferUsages::VERTEX
-        //     });
-
-        //     buffers.push(obj_buffer); //can't have both a mutable and immutable reference at the same time :(
-        // }
-        // let mut index = 0;
-
-        // let default_object_buffer = {
-        //     let raw = vec![RawObject {
-        //         model: Matrix4::identity().into(),
-        //         normal: Matrix3::identity().into()
-        //     }];
-
-        //     device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        //         label: Some("default object buffer"),
-        //         contents: bytemuck::cast_slice(&raw),
-        //         usage: wgpu::BufferUsages::VERTEX
-        //     })
-        // };
-
-        // for light in &self.spotlights {
-
-        //     let read = light.read().unwrap();
-
-        //     self.globals.current_light_model = read.get_raw().model;
-
-        //     let global_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        //         label: Some("Camera Buffer"),
-        //         contents: bytemuck::cast_slice(&[self.globals]),
-        //         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST
-        //     });
-    
-        //     let global_bindgroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        //         layout: &self.global_bindgroup_layout,
-        //         entries: &[
-        //             wgpu::BindGroupEntry {
-        //                 binding: 0,
-        //                 resource: global_buffer.as_entire_binding()
-        //             },
-        //         ],
-        //         label: Some("Camera bind group :)")
-        //     });
-
-        //     let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-        //         label: Some("shadow render pass"),
-        //         color_attachments: &[],
-        //         depth_stencil_attachment: Some(
-        //             wgpu::RenderPassDepthStencilAttachment {
-        //                 view: &read.texture_view,
-        //                 depth_ops: Some(
-        //                     wgpu::Operations {
-        //                         load: wgpu::LoadOp::Clear(1.0),
-        //                         store: wgpu::StoreOp::Store
-        //                     }
-        //                 ),
-        //                 stencil_ops: None,
-        //             }
-        //         ),
-        //         timestamp_writes: None,
-        //         occlusion_query_set: None
-        //     });
-    
-        //     shadow_pass.set_pipeline(&self.shadow_pipeline);
-        //     shadow_pass.set_bind_group(0, &self.texture_bindgroup, &[]);
-        //     shadow_pass.set_bind_group(1, camera_bindgroup, &[]);
-        //     shadow_pass.set_bind_group(2, &global_bindgroup, &[]);
-        //     shadow_pass.set_bind_group(3, &empty_shadows_bindgroup, &[]);
-
-        //     let mut i = 0;
-
-        //     for obj in self.render_storage.get_objects() {
-        //         let model = obj.get_model();
-    
-        //         for mesh in &model.meshes {
-        //             shadow_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-        //             shadow_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-        //             shadow_pass.set_vertex_buffer(1, buffers[i].slice(..));
-        //             shadow_pass.draw_indexed(0..mesh.num_elements, 0, 0..1);
-        //         }
-        //         i += 1;
-        //     }
-
-        //     for (index, chunk) in workspace.chunk_manager.chunks.iter() {
-        //         let out = chunk.get_solid_buffers();
-
-        //         for (vertex_buffer, index_buffer, ilen) in out {
-        //             if *ilen == 0 {continue};
-        //             shadow_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-        //             shadow_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-        //             shadow_pass.set_vertex_buffer(1, default_object_buffer.slice(..));
-        //             shadow_pass.draw_indexed(0..*ilen, 0, 0..1);
-        //         }
-        //     }
-    
-        //     drop(shadow_pass);
-        //     index += 1;
-        // }
-
-        // let global_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        //     label: Some("Camera Buffer"),
-        //     contents: bytemuck::cast_slice(&[self.globals]),
-        //     usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST
-        // });
-
-        // let global_bindgroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        //     layout: &self.global_bindgroup_layout,
-        //     entries: &[
-        //         wgpu::BindGroupEntry {
-        //             binding: 0,
-        //             resource: global_buffer.as_entire_binding()
-        //         },
-        //     ],
-        //     label: Some("Camera bind group :)")
-        // });
-
-        // let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-        //     label: Some("object render pass"),
-        //     color_attachments: &[Some(wgpu::RenderPassColorAttachment { 
-        //         view: &output_view, 
-        //         resolve_target: None, 
-        //         ops: wgpu::Operations { 
-        //             load: wgpu::LoadOp::Clear(wgpu::Color {
-        //                 r: 0.1,
-        //                 g: 0.2,
-        //                 b: 0.3,
-        //                 a: 1.0
-        //             }),
-        //             store: wgpu::StoreOp::Store
-        //         }
-        //     })],
-        //     depth_stencil_attachment: Some(
-        //         wgpu::RenderPassDepthStencilAttachment {
-        //             view: &self.depth_texture.view,
-        //             depth_ops: Some(
-        //                 wgpu::Operations {
-        //                     load: wgpu::LoadOp::Clear(1.0),
-        //                     store: wgpu::StoreOp::Discard
-        //                 }
-        //             ),
-        //             stencil_ops: None,
-        //         }
-        //     ),
-        //     timestamp_writes: None,
-        //     occlusion_query_set: None,
-        // });
-        // render_pass.set_pipeline(&self.object_pipeline);
-        // render_pass.set_bind_group(0, &self.texture_bindgroup, &[]);
-        // render_pass.set_bind_group(1, camera_bindgroup, &[]);
-        // render_pass.set_bind_group(2, &global_bindgroup, &[]);
-        // render_pass.set_bind_group(3, &shadows_bindgroup, &[]);
-
-        // let mut i = 0;
-
-        // for obj in self.render_storage.get_objects() {
-        //     let model = obj.get_model();
-
-        //     for mesh in &model.meshes {
-        //         render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-        //         render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-        //         render_pass.set_vertex_buffer(1, buffers[i].slice(..));
-        //         render_pass.draw_indexed(0..mesh.num_elements, 0, 0..1);
-        //     }
-        //     i += 1;
-        // }
-
-        // for (_index, chunk) in workspace.chunk_manager.chunks.iter() {
-        //     let out = chunk.get_solid_buffers();
-
-        //     for (vertex_buffer, index_buffer, ilen) in out {
-        //         if *ilen == 0 {continue};
-        //         render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-        //         render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-        //         render_pass.set_vertex_buffer(1, default_object_buffer.slice(..));
-        //         render_pass.draw_indexed(0..*ilen, 0, 0..1);
-        //     }
-        // }
-        // println!("Frame took {}ms", frame_start.elapsed_ms());
+    /**
+     a spot light with the default cone, colour and range. the handle changes it or hands it back to remove_light
+    */
+    pub fn create_spotlight(&mut self, position: Point3<f32>, target: Point3<f32>) -> Arc<RwLock<Light>> {
+        self.lights.add(Light::spot(position, target))
+    }
+
+    pub fn create_point_light(&mut self, position: Point3<f32>) -> Arc<RwLock<Light>> {
+        self.lights.add(Light::point(position))
+    }
+
+    pub fn remove_light(&mut self, light: &Arc<RwLock<Light>>) {
+        self.lights.remove(light);
+    }
+
+    /**
+     keeps the instance buffer in step with the render storage, only batches whose objects changed are uploaded again.
+     the joint palette is rewritten every frame since animated objects move every frame
+    */
+    fn write_object_instances(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let dt = self.last_object_update.elapsed().as_secs_f32();
+        self.last_object_update = instant::Instant::now();
+
+        let mut relaid = self.render_storage.layout_instances();
+        let (instance_count, joint_count) = self.render_storage.reserved();
+
+        let size = instance_count as u64 * size_of::<RawObject>() as u64;
+        if size > self.object_instance_buffer.size() {
+            self.object_instance_buffer = create_object_instance_buffer(device, size.next_power_of_two());
+            //a new buffer starts out empty
+            relaid = true;
+        }
+
+        let size = joint_count as u64 * size_of::<[[f32; 4]; 4]>() as u64;
+        if size > self.joint_buffer.size() {
+            self.joint_buffer = create_joint_buffer(device, size.next_power_of_two());
+            self.global_bindgroup = create_global_bindgroup(device, &self.global_bindgroup_layout, &self.joint_buffer);
+        }
+
+        let mut joints: Vec<[[f32; 4]; 4]> = vec![[[0.; 4]; 4]; joint_count as usize];
+
+        for batch in self.render_storage.get_batches_mut() {
+            if relaid || batch.dirty {
+                let raws = batch.objects.iter().enumerate().map(|(slot, object)| RawObject {
+                    joint_offset: batch.joint_offset(slot),
+                    ..*object.get_raw()
+                }).collect::<Vec<RawObject>>();
+
+                let offset = batch.first_instance as u64 * size_of::<RawObject>() as u64;
+                queue.write_buffer(&self.object_instance_buffer, offset, bytemuck::cast_slice(&raws));
+                batch.dirty = false;
+            }
+
+            //animated objects step their players and put this frame's pose in their part of the palette
+            for (slot, object) in batch.objects.iter().enumerate() {
+                let Some(player) = object.get_animation() else {continue};
+                let mut player = player.lock();
+                player.advance(dt);
+
+                let offset = batch.joint_offset(slot) as usize;
+                let palette = player.palette();
+                joints[offset..offset + palette.len()].copy_from_slice(&palette);
+            }
+        }
+
+        if !joints.is_empty() {
+            queue.write_buffer(&self.joint_buffer, 0, bytemuck::cast_slice(&joints));
+        }
+    }
+
+    /**
+     draws the render storage objects into the solid pass, after the terrain so most of what's hidden behind it
+     fails the depth test early. one instanced draw per mesh of every model. expects the texture, camera and
+     sun shadow groups to be bound already
+    */
+    fn render_objects<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        let batches = self.render_storage.get_batches();
+        if batches.is_empty() {return};
+
+        render_pass.set_bind_group(2, &self.global_bindgroup, &[]);
+        render_pass.set_vertex_buffer(1, self.object_instance_buffer.slice(..));
+
+        for batch in batches {
+            let instances = batch.first_instance..batch.first_instance + batch.objects.len() as u32;
+
+            for mesh in batch.model.meshes.iter() {
+                match &mesh.skin_buffer {
+                    Some(skin_buffer) => {
+                        render_pass.set_pipeline(&self.pipelines.skinned_object);
+                        render_pass.set_vertex_buffer(2, skin_buffer.slice(..));
+                    },
+                    None => render_pass.set_pipeline(&self.pipelines.object)
+                }
+
+                render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..mesh.num_elements, 0, instances.clone());
+            }
+        }
     }
 
     pub fn render_surface(&mut self, 
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        output_texture: &mut wgpu::SurfaceTexture, 
         output_view: &wgpu::TextureView, 
         encoder: &mut wgpu::CommandEncoder,
         workspace: &mut Workspace
     ) {
         if (workspace.chunk_manager.chunks.len() as u32) < (workspace.chunk_manager.render_distance * 2 + 1).pow(2) {return}
         let t = Stopwatch::start_new();
+
+        if workspace.antialiasing != self.antialiasing {
+            self.set_antialiasing(device, workspace.antialiasing.clone());
+        }
+
+        let lighting = workspace.world_clock.lighting();
+        let medium = FogMedium::at(&workspace.chunk_manager, workspace.current_camera.position);
+        let fog = workspace.fog.uniform(workspace.current_camera.view_distance(), &lighting, medium);
+        workspace.current_camera.set_fog(queue, fog);
+
         let camera_bindgroup = &workspace.current_camera.bindgroup;
+        let frustum = workspace.current_camera.frustum();
+        let mut cull_stats = CullStats::default();
+        let visible_slices = find_visible_slices(&workspace.chunk_manager.chunks, workspace.current_camera.position, &frustum);
+
+        queue.write_buffer(&self.texture_animation_buffer, 0, bytemuck::cast_slice(&[TextureAnimationGlobals::new(self.start_time.elapsed().as_secs_f32())]));
+        self.transparency_sorter.update(queue, workspace.current_camera.position, &workspace.chunk_manager);
+        self.sun_shadows.update(queue, &workspace.current_camera, &lighting);
+        (cull_stats.visible_lights, cull_stats.culled_lights) = self.lights.update(queue, workspace.current_camera.position, &frustum);
+        self.sky.update(queue, &lighting, self.start_time.elapsed().as_secs_f32());
+
+        let mut slice_draws = Vec::new();
+
+        for (index, chunk) in workspace.chunk_manager.chunk_buffers.iter() {
+            let chunkref = workspace.chunk_manager.chunks.get(index).unwrap();
+
+            for (i, allocation) in chunk.solid.iter().enumerate() {
+                if chunkref.states[i] != ChunkState::Ready {continue};
+                let Some(allocation) = allocation else {continue};
+
+                if !slice_visible(&frustum, chunk.position.x, i as i32, chunk.position.y) {
+                    cull_stats.culled_slices += 1;
+                    continue;
+                }
+                if visible_slices.as_ref().is_some_and(|v| !v.contains(&Vector3::new(chunk.position.x, i as i32, chunk.position.y))) {
+                    cull_stats.occluded_slices += 1;
+                    continue;
+                }
+                cull_stats.drawn_slices += 1;
+
+                slice_draws.push(DrawIndexedIndirectArgs {
+                    index_count: allocation.index_count(),
+                    instance_count: 1,
+                    first_index: 0,
+                    base_vertex: allocation.offset as i32,
+                    first_instance: chunk.slots[i]
+                });
+            }
+        }
+
+        if self.slice_draw_mode != SliceDrawMode::Direct {
+            self.write_slice_draws(device, queue, &slice_draws);
+        }
+        self.write_object_instances(device, queue);
+
+        workspace.chunk_manager.lod.update_ready_mask(queue, &workspace.chunk_manager.chunks);
+
+        let shadow_objects = ShadowObjects {
+            render_storage: &self.render_storage,
+            instance_buffer: &self.object_instance_buffer,
+            joint_bindgroup: &self.global_bindgroup
+        };
+        self.sun_shadows.render(encoder, &workspace.chunk_manager, &shadow_objects);
+        self.lights.render(encoder, &workspace.chunk_manager, &shadow_objects);
+        //the scene goes into the hdr target, post processing brings it to output_view at the end. with msaa it's drawn
+        //into the multisampled target and resolved into the hdr one by the last pass
+        let scene_view = self.scene_targets.colour_view.as_ref().unwrap_or(self.post_process.scene_view());
+        let resolve_target = self.scene_targets.colour_view.as_ref().map(|_| self.post_process.scene_view());
+
+        self.sky.render(encoder, scene_view, camera_bindgroup);
 
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("object render pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment { 
-                view: &output_view, 
+                view: scene_view, 
                 resolve_target: None, 
                 ops: wgpu::Operations { 
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0
-                    }),
+                    //on top of the sky pass
+                    load: wgpu::LoadOp::Load,
                     store: wgpu::StoreOp::Store
                 }
             })],
             depth_stencil_attachment: Some(
                 wgpu::RenderPassDepthStencilAttachment {
-                    view: &self.depth_texture.view,
+                    view: &self.scene_targets.depth_view,
                     depth_ops: Some(
                         wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(1.0),
+                            //reversed depth, 0 is the far plane
+                            load: wgpu::LoadOp::Clear(0.0),
                             store: wgpu::StoreOp::Store
                         }
                     ),
@@ -587,42 +612,51 @@ impl MainRenderer {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
-        render_pass.set_pipeline(if self.wireframe_mode {&self.surface_pipeline_wireframe} else {&self.surface_pipeline});
+        render_pass.set_pipeline(if self.wireframe_mode {&self.pipelines.surface_wireframe} else {&self.pipelines.surface});
         render_pass.set_bind_group(0, &self.texture_bindgroup, &[]);
         render_pass.set_bind_group(1, camera_bindgroup, &[]);
 
 
-        let mut outeri = 0;
-        for (index, chunk) in workspace.chunk_manager.chunk_buffers.iter() {
-            let chunkref = workspace.chunk_manager.chunks.get(index).unwrap();
-            let out = chunk.get_solid_buffers();
+        let mesh_pool = &workspace.chunk_manager.mesh_pool;
 
-            let mut i = 0;
+        render_pass.set_bind_group(2, &mesh_pool.bindgroup, &[]);
+        render_pass.set_bind_group(3, &self.sun_shadows.bindgroup, &[]);
+        render_pass.set_vertex_buffer(0, mesh_pool.vertex_buffer().slice(..));
+        render_pass.set_index_buffer(mesh_pool.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
 
-            for t in out {
-                if chunkref.states[i] != ChunkState::Ready {i += 1; continue};
-                let (vertex_buffer, index_buffer, ilen) = t.as_ref().unwrap();
-                if *ilen == 0 {
-                    i += 1;
-                    continue;
-                };
-                
-                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-                render_pass.set_vertex_buffer(1, chunk.slice_vertex_buffers[i].slice(..));
-                render_pass.draw_indexed(0..*ilen, 0, 0..1);
-
-                i += 1;
+        match self.slice_draw_mode {
+            SliceDrawMode::MultiDrawIndirect => {
+                render_pass.multi_draw_indexed_indirect(&self.slice_indirect_buffer, 0, slice_draws.len() as u32);
+            },
+            SliceDrawMode::Indirect => {
+                for i in 0..slice_draws.len() {
+                    render_pass.draw_indexed_indirect(&self.slice_indirect_buffer, (i * size_of::<DrawIndexedIndirectArgs>()) as u64);
+                }
+            },
+            SliceDrawMode::Direct => {
+                for draw in slice_draws.iter() {
+                    render_pass.draw_indexed(0..draw.index_count, draw.base_vertex, draw.first_instance..draw.first_instance + 1);
+                }
             }
-            outeri += 1;
         }
+
+        //after the chunks so the depth test throws away tiles hidden behind them early
+        render_pass.set_pipeline(&self.pipelines.lod);
+        render_pass.set_bind_group(2, &workspace.chunk_manager.lod.bindgroup, &[]);
+
+        for (vertex_buffer, vertex_count) in workspace.chunk_manager.lod.visible_tiles(&frustum) {
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.draw(0..vertex_count, 0..1);
+        }
+
+        self.render_objects(&mut render_pass);
         drop(render_pass);
 
         let mut transparency_render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("transparent render pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment { 
-                view: &output_view, 
-                resolve_target: None, 
+                view: scene_view, 
+                resolve_target, 
                 ops: wgpu::Operations { 
                     load: wgpu::LoadOp::Load,
                     store: wgpu::StoreOp::Store
@@ -630,7 +664,7 @@ impl MainRenderer {
             })],
             depth_stencil_attachment: Some(
                 wgpu::RenderPassDepthStencilAttachment {
-                    view: &self.depth_texture.view,
+                    view: &self.scene_targets.depth_view,
                     depth_ops: None,
                     stencil_ops: None,
                 }
@@ -639,14 +673,13 @@ impl MainRenderer {
             occlusion_query_set: None,
         });
 
-        transparency_render_pass.set_pipeline(&self.transparent_surface_pipeline);
+        transparency_render_pass.set_pipeline(&self.pipelines.transparent_surface);
         transparency_render_pass.set_bind_group(0, &self.texture_bindgroup, &[]);
         transparency_render_pass.set_bind_group(1, camera_bindgroup, &[]);
-
-        let mut chunks_sorted = workspace.chunk_manager.chunks.clone().iter().flat_map(|v| (0..16).map(|slice| {
-            let pos = v.1.position;
-            Vector3::new(pos.x as f32, slice as f32, pos.y as f32)
-        })).collect::<Vec<_>>();
+        transparency_render_pass.set_bind_group(2, &mesh_pool.bindgroup, &[]);
+        transparency_render_pass.set_bind_group(3, &self.sun_shadows.bindgroup, &[]);
+        transparency_render_pass.set_vertex_buffer(0, mesh_pool.vertex_buffer().slice(..));
+        transparency_render_pass.set_index_buffer(mesh_pool.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
 
         let camera_pos = Vector3::new(
             workspace.current_camera.position.x,
@@ -654,61 +687,137 @@ impl MainRenderer {
             workspace.current_camera.position.z
         );
 
-        chunks_sorted.sort_by(|a, b| {
-            let dista = a.distance(camera_pos);
-            let distb = b.distance(camera_pos);
-    
-            dista.partial_cmp(&distb).unwrap()
-        });
+        let mut transparent_slices = Vec::new();
 
-        // for chunkp in chunks_sorted {
-        //     let chunk = workspace.chunk_manager.chunks.get_mut(&xz_to_index(chunkp.x as i32, chunkp.z as i32)).unwrap();
-        //     let chunkbuff = workspace.chunk_manager.chunk_buffers.get(&xz_to_index(chunkp.x as i32, chunkp.z as i32)).unwrap();
-
-        //     let buffs = sort_chunk_transparent_quads(device, &workspace.current_camera, chunk, chunkp.y as usize);
-
-        //     match buffs {
-        //         None => {},
-        //         Some(transparent_tris) => {
-        //             transparency_render_pass.set_index_buffer(transparent_tris.1.slice(..), wgpu::IndexFormat::Uint32);
-        //             transparency_render_pass.set_vertex_buffer(0, transparent_tris.0.slice(..));
-        //             transparency_render_pass.set_vertex_buffer(1, chunkbuff.slice_vertex_buffers[chunkp.y as usize].slice(..));
-        //             transparency_render_pass.draw_indexed(0..transparent_tris.2 as u32, 0, 0..1);
-        //         }
-        //     }
-        // }
-
-        // for (_index, chunk) in workspace.chunk_manager.chunks.iter() {
-        //     let read = chunk.read();
-        //     locks.push(read);
-        // }
-        
-        // let mut outeri = 0;
-        // for (_index, chunk) in workspace.chunk_manager.chunks.iter() {
-        //     let read = &locks[outeri];
-
-        //     let out = read.get_transparent_buffers();
-
-        //     let mut i = 0;
-
-        //     for t in out {
-        //         if read.states[i] != ChunkState::Ready {i += 1; continue};
-        //         let (vertex_buffer, index_buffer, ilen) = t.as_ref().unwrap();
-        //         if *ilen == 0 {
-        //             i += 1;
-        //             continue;
-        //         };
-                
-        //         transparency_render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-        //         transparency_render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-        //         transparency_render_pass.set_vertex_buffer(1, read.slice_vertex_buffers[i].slice(..));
-        //         transparency_render_pass.draw_indexed(0..*ilen, 0, 0..1);
-
-        //         i += 1;
-        //     }
-        //     outeri += 1;
-        // }
+        for (index, chunk) in workspace.chunk_manager.chunk_buffers.iter() {
+            let chunkref = workspace.chunk_manager.chunks.get(index).unwrap();
+
+            for (i, allocation) in chunk.transparent.iter().enumerate() {
+                if chunkref.states[i] != ChunkState::Ready {continue};
+                let Some(allocation) = allocation else {continue};
+
+                let visible = slice_visible(&frustum, chunk.position.x, i as i32, chunk.position.y) &&
+                    visible_slices.as_ref().map_or(true, |v| v.contains(&Vector3::new(chunk.position.x, i as i32, chunk.position.y)));
+                if visible {cull_stats.drawn_transparent_slices += 1} else {cull_stats.culled_transparent_slices += 1; continue}
+
+                let centre = Vector3::new(chunk.position.x as f32, i as f32, chunk.position.y as f32) * 16.0 + Vector3::new(8.0, 8.0, 8.0);
+                transparent_slices.push((centre.distance2(camera_pos), *allocation, chunk.slots[i]));
+            }
+        }
+
+        //back to front, the quads inside each slice are already sorted the same way
+        transparent_slices.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        for (_, allocation, slot) in transparent_slices {
+            transparency_render_pass.draw_indexed(0..allocation.index_count(), allocation.offset as i32, slot..slot + 1);
+        }
         drop(transparency_render_pass);
+
+        self.cull_stats = cull_stats;
+
+        let outline_vertices = workspace.structure_selection.get_outline_vertices();
+
+        if outline_vertices.len() > 0 {
+            let outline_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Selection Outline Buffer"),
+                contents: bytemuck::cast_slice(&outline_vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+            let mut outline_render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("outline render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment { 
+                    view: scene_view, 
+                    //resolves again over the transparent pass's
+                    resolve_target, 
+                    ops: wgpu::Operations { 
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store
+                    }
+                })],
+                depth_stencil_attachment: Some(
+                    wgpu::RenderPassDepthStencilAttachment {
+                        view: &self.scene_targets.depth_view,
+                        depth_ops: None,
+                        stencil_ops: None,
+                    }
+                ),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            outline_render_pass.set_pipeline(&self.pipelines.outline);
+            outline_render_pass.set_bind_group(0, &workspace.current_camera.bindgroup, &[]);
+            outline_render_pass.set_vertex_buffer(0, outline_buffer.slice(..));
+            outline_render_pass.draw(0..outline_vertices.len() as u32, 0..1);
+        }
+
+        self.post_process.render(queue, encoder, output_view, self.antialiasing.fxaa);
         //println!("frame render: {}ms", t.elapsed_ms());
     }
-}
\ No newline at end of file
+}
+
+/**
+ slices are 16x16x16 blocks, positions are in chunk/slice units like the mesh pool's slice data
+*/
+fn slice_visible(frustum: &Frustum, x: i32, slice: i32, z: i32) -> bool {
+    let min = Vector3::new(x as f32, slice as f32, z as f32) * 16.0;
+    frustum.intersects_aabb(min, min + Vector3::new(16.0, 16.0, 16.0))
+}
+
+fn create_slice_indirect_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Slice Indirect Buffer"),
+        size,
+        usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false
+    })
+}
+
+fn create_object_instance_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Object Instance Buffer"),
+        size,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false
+    })
+}
+
+fn create_joint_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Joint Palette Buffer"),
+        size,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false
+    })
+}
+
+fn create_global_bindgroup(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, joint_buffer: &wgpu::Buffer) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("global bindgroup"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: joint_buffer.as_entire_binding()
+            }
+        ]
+    })
+}
+
+fn create_scene_texture(device: &wgpu::Device, label: &str, format: wgpu::TextureFormat, width: u32, height: u32, sample_count: u32) -> wgpu::TextureView {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[]
+    }).create_view(&wgpu::TextureViewDescriptor::default())
+}
@@ -1,4 +1,29 @@
-use std::{fs, path::Path};
+use crate::engine::{assetfs::read_asset_to_string, texture_loader::get_texture_binding_mode};
+
+/**
+ reads a shader and expands its "#import <name>" lines. "textures" resolves to the group 0 texture bindings
+ for the current texture binding mode, anything else to shaders/include/<name>.wgsl
+*/
+pub fn load_shader_source(shader_path: &str) -> anyhow::Result<String> {
+    let source = read_asset_to_string(shader_path)?;
+    let mut out = String::with_capacity(source.len());
+
+    for line in source.lines() {
+        match line.trim().strip_prefix("#import ") {
+            Some(name) => {
+                let include = match name.trim() {
+                    "textures" => get_texture_binding_mode().shader_include().to_owned(),
+                    other => format!("shaders/include/{}.wgsl", other)
+                };
+                out.push_str(&load_shader_source(&include)?);
+            },
+            None => out.push_str(line)
+        }
+        out.push('\n');
+    }
+
+    Ok(out)
+}
 
 pub fn create_render_pipeline(
     name: &str,
@@ -14,11 +39,12 @@ pub fn create_render_pipeline(
     nocolor: bool,
     nofrag: bool,
     bake: bool,
-    iswireframe: bool
+    iswireframe: bool,
+    sample_count: u32
 ) -> wgpu::RenderPipeline {
     let shader_descriptor = wgpu::ShaderModuleDescriptor {
         label: Some(shader_path),
-        source: wgpu::ShaderSource::Wgsl(fs::read_to_string(Path::new(shader_path)).unwrap().into())
+        source: wgpu::ShaderSource::Wgsl(load_shader_source(shader_path).expect(&format!("Unable to load shader {}", shader_path)).into())
     };
 
     let shader = device.create_shader_module(shader_descriptor);
@@ -55,19 +81,64 @@ pub fn create_render_pipeline(
             front_face: wgpu::FrontFace::Ccw,
             cull_mode: if backface_culling {Some(wgpu::Face::Back)} else {None},
             polygon_mode: wgpu::PolygonMode::Fill,
-            unclipped_depth: true,
+            unclipped_depth: device.features().contains(wgpu::Features::DEPTH_CLIP_CONTROL),
             conservative: false,
         },
         depth_stencil: depth_format.map(|format| wgpu::DepthStencilState {
             format,
             depth_write_enabled,
-            depth_compare: wgpu::CompareFunction::Less,
+            //the camera uses reversed depth, light views still write regular depth
+            depth_compare: if bake {wgpu::CompareFunction::Less} else {wgpu::CompareFunction::Greater},
             stencil: wgpu::StencilState::default(),
             bias: if bias.is_some() {bias.unwrap()} else {wgpu::DepthBiasState::default()},
         }),
-        multisample: wgpu::MultisampleState::default(),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
         multiview: None,
     };
 
     device.create_render_pipeline(&desc)
-}
\ No newline at end of file
+}
+/**
+ a pass that draws one triangle over the whole target (shaders/include/fullscreen.wgsl), no vertex buffers or depth.
+ blend None replaces what's there
+*/
+pub fn create_fullscreen_pipeline(
+    name: &str,
+    device: &wgpu::Device,
+    pipeline_layout: &wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+    shader_path: &str,
+    entry_point: &str,
+    blend: Option<wgpu::BlendState>
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(shader_path),
+        source: wgpu::ShaderSource::Wgsl(load_shader_source(shader_path).expect(&format!("Unable to load shader {}", shader_path)).into())
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(name),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point,
+            targets: &[Some(wgpu::ColorTargetState {
+                format: color_format,
+                blend,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
@@ -1,18 +1,73 @@
 use std::sync::Arc;
 
-use crate::gen::object::Object;
+use crate::{engine::model::Model, gen::object::Object};
+
+/**
+ the objects sharing one model, drawn with one instanced draw per mesh. they own a range of slots in the
+ renderer's instance buffer, and animated models the same number of poses in the joint palette
+*/
+pub struct ModelBatch {
+    pub model: Arc<Model>,
+    pub objects: Vec<Arc<Object>>,
+    pub first_instance: u32,
+    //slots reserved for this batch, so it can grow a little without moving every batch after it
+    pub capacity: u32,
+    pub first_joint: u32,
+    //objects were added or removed since the batch's instances were last uploaded
+    pub dirty: bool
+}
+
+impl ModelBatch {
+    /**
+     joints per pose, 0 for models without a skeleton
+    */
+    pub fn joints_per_object(&self) -> u32 {
+        self.model.skeleton.as_ref().map_or(0, |skeleton| skeleton.nodes.len() as u32)
+    }
+
+    /**
+     where an object's pose starts in the joint palette, fixed by its slot so it only changes when the batches are laid out again
+    */
+    pub fn joint_offset(&self, slot: usize) -> u32 {
+        self.first_joint + slot as u32 * self.joints_per_object()
+    }
+}
 
 pub struct RenderStorage {
-    objects: Vec<Arc<Object>>
+    objects: Vec<Arc<Object>>,
+    batches: Vec<ModelBatch>,
+    //a batch came, went or outgrew its slots
+    relayout: bool
 }
 
 impl RenderStorage {
     pub fn new() -> Self {
         Self {
-            objects: Vec::new()
+            objects: Vec::new(),
+            batches: Vec::new(),
+            relayout: false
         }
     }
     pub fn add_object(&mut self, object: Arc<Object>) {
+        match self.batches.iter_mut().find(|batch| Arc::ptr_eq(&batch.model, object.get_model())) {
+            Some(batch) => {
+                batch.objects.push(object.clone());
+                batch.dirty = true;
+                self.relayout |= batch.objects.len() as u32 > batch.capacity;
+            },
+            None => {
+                self.batches.push(ModelBatch {
+                    model: object.get_model().clone(),
+                    objects: vec![object.clone()],
+                    first_instance: 0,
+                    capacity: 0,
+                    first_joint: 0,
+                    dirty: true
+                });
+                self.relayout = true;
+            }
+        }
+
         self.objects.push(object);
     }
     pub fn remove_object<C>(&mut self, callback: C) -> Option<Arc<Object>> where C: Fn(&Arc<Object>) -> bool {
@@ -28,11 +83,24 @@ impl RenderStorage {
         match found {
             Some(f) => {
                 self.objects.remove(i);
+                self.remove_from_batch(&f);
                 Some(f)
             },
             None => None
         }
     }
+    fn remove_from_batch(&mut self, object: &Arc<Object>) {
+        let Some(b) = self.batches.iter().position(|batch| Arc::ptr_eq(&batch.model, object.get_model())) else {return};
+        let batch = &mut self.batches[b];
+
+        batch.objects.retain(|other| !Arc::ptr_eq(other, object));
+        batch.dirty = true;
+
+        if batch.objects.is_empty() {
+            self.batches.remove(b);
+            self.relayout = true;
+        }
+    }
     pub fn get_objects(&self) -> &Vec<Arc<Object>> {
         &self.objects
     }
@@ -44,4 +112,42 @@ impl RenderStorage {
         }
         None
     }
-}
\ No newline at end of file
+    pub fn get_batches(&self) -> &Vec<ModelBatch> {
+        &self.batches
+    }
+    pub fn get_batches_mut(&mut self) -> &mut Vec<ModelBatch> {
+        &mut self.batches
+    }
+
+    /**
+     gives every batch its slots and joints again if anything outgrew them, returns whether it did.
+     after a layout every batch is dirty, since its instances may have moved
+    */
+    pub fn layout_instances(&mut self) -> bool {
+        if !self.relayout {return false};
+        self.relayout = false;
+
+        let (mut instance, mut joint) = (0, 0);
+        for batch in self.batches.iter_mut() {
+            batch.capacity = (batch.objects.len() as u32).next_power_of_two();
+            batch.first_instance = instance;
+            batch.first_joint = joint;
+            batch.dirty = true;
+
+            instance += batch.capacity;
+            joint += batch.capacity * batch.joints_per_object();
+        }
+
+        true
+    }
+
+    /**
+     slots and joints every batch has reserved, what the instance buffer and joint palette have to fit
+    */
+    pub fn reserved(&self) -> (u32, u32) {
+        self.batches.last().map_or((0, 0), |batch| (
+            batch.first_instance + batch.capacity,
+            batch.first_joint + batch.capacity * batch.joints_per_object()
+        ))
+    }
+}
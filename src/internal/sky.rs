@@ -0,0 +1,155 @@
+use std::mem::size_of;
+
+use cgmath::Vector2;
+
+use crate::state::worldclock::WorldLighting;
+
+use super::renderpipeline::create_render_pipeline;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SkyUniform {
+    sun_direction: [f32; 4],
+    sun_tangent: [f32; 4],
+    //xy cloud offset, z coverage, w seconds
+    clouds: [f32; 4]
+}
+
+/**
+ the procedural sky pass: atmosphere, sun and moon, stars and a cloud layer pushed along by the wind.
+ it clears the colour target, so everything else draws on top of it
+*/
+pub struct Sky {
+    pipeline: wgpu::RenderPipeline,
+    pipeline_layout: wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+    uniform_buffer: wgpu::Buffer,
+    bindgroup: wgpu::BindGroup,
+    //blocks per second
+    pub wind: Vector2<f32>,
+    //0 clear, 1 overcast
+    pub cloud_coverage: f32,
+    cloud_offset: Vector2<f32>,
+    last_time: f32
+}
+
+impl Sky {
+    pub fn new(device: &wgpu::Device, color_format: wgpu::TextureFormat, camera_bindgroup_layout: &wgpu::BindGroupLayout, sample_count: u32) -> Self {
+        let bindgroup_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("sky bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None
+                }
+            ]
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sky Buffer"),
+            size: size_of::<SkyUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let bindgroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sky bind group"),
+            layout: &bindgroup_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding()
+                }
+            ]
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("sky pipeline layout"),
+            bind_group_layouts: &[camera_bindgroup_layout, &bindgroup_layout],
+            push_constant_ranges: &[]
+        });
+
+        let pipeline = create_sky_pipeline(device, &pipeline_layout, color_format, sample_count);
+
+        Self {
+            pipeline,
+            pipeline_layout,
+            color_format,
+            uniform_buffer,
+            bindgroup,
+            wind: Vector2::new(3.0, 1.0),
+            cloud_coverage: 0.45,
+            cloud_offset: Vector2::new(0.0, 0.0),
+            last_time: 0.0
+        }
+    }
+
+    /**
+     the sky draws into the scene's colour target, so it has to follow its sample count
+    */
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+        self.pipeline = create_sky_pipeline(device, &self.pipeline_layout, self.color_format, sample_count);
+    }
+
+    /**
+     time is in seconds since the renderer started, the clouds move by the wind since the last call
+    */
+    pub fn update(&mut self, queue: &wgpu::Queue, lighting: &WorldLighting, time: f32) {
+        self.cloud_offset += self.wind * (time - self.last_time).max(0.0);
+        self.last_time = time;
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[SkyUniform {
+            sun_direction: lighting.sun_direction.extend(0.0).into(),
+            sun_tangent: lighting.sun_tangent.extend(0.0).into(),
+            clouds: [self.cloud_offset.x, self.cloud_offset.y, self.cloud_coverage, time]
+        }]));
+    }
+
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, output_view: &wgpu::TextureView, camera_bindgroup: &wgpu::BindGroup) {
+        let mut sky_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("sky render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store
+                }
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None
+        });
+
+        sky_pass.set_pipeline(&self.pipeline);
+        sky_pass.set_bind_group(0, camera_bindgroup, &[]);
+        sky_pass.set_bind_group(1, &self.bindgroup, &[]);
+        sky_pass.draw(0..3, 0..1);
+    }
+}
+
+fn create_sky_pipeline(device: &wgpu::Device, pipeline_layout: &wgpu::PipelineLayout, color_format: wgpu::TextureFormat, sample_count: u32) -> wgpu::RenderPipeline {
+    create_render_pipeline(
+        "sky pipeline",
+        device,
+        pipeline_layout,
+        color_format,
+        None,
+        &[],
+        "shaders/skyshader.wgsl",
+        false,
+        false,
+        None,
+        false,
+        false,
+        false,
+        false,
+        sample_count
+    )
+}
@@ -0,0 +1,483 @@
+use std::mem::size_of;
+
+use cgmath::{ortho, EuclideanSpace, InnerSpace, Matrix4, MetricSpace, Point3, Vector3, Vector4};
+
+use crate::{engine::{surfacevertex::SurfaceVertex, vertex::{ModelVertex, SkinVertex, Vertex}}, gen::object::RawObject, state::worldclock::WorldLighting, vox::{chunk::ChunkState, chunk_manager::ChunkManager, meshpool::create_mesh_pool_bindgroup_layout}};
+
+use super::{camera::{Camera, OPENGL_TO_WGPU_MATRIX}, frustum::Frustum, lights::LightManager, renderpipeline::create_render_pipeline, renderstorage::RenderStorage};
+
+pub const CASCADE_COUNT: usize = 3;
+const CASCADE_RESOLUTION: u32 = 1024;
+//far end of each cascade in blocks from the camera, the next one starts where the last stops
+const CASCADE_SPLITS: [f32; CASCADE_COUNT] = [16.0, 48.0, 128.0];
+//room behind each cascade for terrain that casts into it from outside the camera frustum
+const CASTER_MARGIN: f32 = 256.0;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SunShadowsUniform {
+    cascades: [[[f32; 4]; 4]; CASCADE_COUNT],
+    direction: [f32; 4],
+    texel_sizes: [f32; 4],
+    //x scales baked sunlight for the time of day
+    sky_light: [f32; 4]
+}
+
+struct Cascade {
+    view_proj: Matrix4<f32>,
+    buffer: wgpu::Buffer,
+    bindgroup: wgpu::BindGroup,
+    view: wgpu::TextureView
+}
+
+/**
+ the sun, or the moon at night, with one shadow map per cascade. cascades are fitted to bounding spheres of the camera frustum
+ slices and snapped to whole texels, so the shadows don't swim as the camera turns or moves
+*/
+pub struct SunShadows {
+    pipeline: wgpu::RenderPipeline,
+    object_pipelines: ObjectShadowPipelines,
+    cascades: Vec<Cascade>,
+    uniform_buffer: wgpu::Buffer,
+    pub bindgroup_layout: wgpu::BindGroupLayout,
+    pub bindgroup: wgpu::BindGroup
+}
+
+impl SunShadows {
+    /**
+     the bind group also carries the light manager's lights, group 3 is the last one pipelines have room for.
+     joint_bindgroup_layout is the renderer's joint palette, for shadows of skinned objects
+    */
+    pub fn new(device: &wgpu::Device, lights: &LightManager, joint_bindgroup_layout: &wgpu::BindGroupLayout) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("sun shadow texture"),
+            size: wgpu::Extent3d {
+                width: CASCADE_RESOLUTION,
+                height: CASCADE_RESOLUTION,
+                depth_or_array_layers: CASCADE_COUNT as u32
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[]
+        });
+
+        let array_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("sun shadow sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let cascade_bindgroup_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("shadow cascade bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None
+                }
+            ]
+        });
+
+        let cascades = (0..CASCADE_COUNT).map(|i| {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Shadow Cascade Buffer"),
+                size: size_of::<[[f32; 4]; 4]>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false
+            });
+
+            let bindgroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("shadow cascade bind group"),
+                layout: &cascade_bindgroup_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: buffer.as_entire_binding()
+                    }
+                ]
+            });
+
+            let view = texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("sun shadow cascade"),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_array_layer: i as u32,
+                array_layer_count: Some(1),
+                ..Default::default()
+            });
+
+            Cascade { view_proj: Matrix4::from_scale(1.0), buffer, bindgroup, view }
+        }).collect::<Vec<_>>();
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("sun shadow pipeline layout"),
+            bind_group_layouts: &[&cascade_bindgroup_layout, &create_mesh_pool_bindgroup_layout(device)],
+            push_constant_ranges: &[]
+        });
+
+        let pipeline = create_render_pipeline(
+            "sun shadow pipeline",
+            device,
+            &pipeline_layout,
+            wgpu::TextureFormat::Rgba8Unorm,
+            Some(wgpu::TextureFormat::Depth32Float),
+            &[SurfaceVertex::desc()],
+            "shaders/shadowshader.wgsl",
+            false,
+            true,
+            Some(wgpu::DepthBiasState {
+                constant: 2,
+                slope_scale: 2.0,
+                clamp: 0.0
+            }),
+            true,
+            true,
+            true,
+            false,
+            1
+        );
+
+        let object_pipelines = ObjectShadowPipelines::new(device, &cascade_bindgroup_layout, joint_bindgroup_layout);
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sun Shadows Buffer"),
+            size: size_of::<SunShadowsUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let bindgroup_layout = create_sun_shadows_bindgroup_layout(device);
+
+        let bindgroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sun shadows bind group"),
+            layout: &bindgroup_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding()
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&array_view)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: lights.uniform_buffer().as_entire_binding()
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&lights.shadow_view)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::Sampler(&lights.shadow_sampler)
+                }
+            ]
+        });
+
+        Self {
+            pipeline,
+            object_pipelines,
+            cascades,
+            uniform_buffer,
+            bindgroup_layout,
+            bindgroup
+        }
+    }
+
+    /**
+     fits the cascades to the camera's current view, call after the camera's matrices are updated
+    */
+    pub fn update(&mut self, queue: &wgpu::Queue, camera: &Camera, lighting: &WorldLighting) {
+        let mut uniform = SunShadowsUniform {
+            cascades: [[[0.0; 4]; 4]; CASCADE_COUNT],
+            direction: lighting.light_direction.extend(0.0).into(),
+            texel_sizes: [0.0; 4],
+            sky_light: [lighting.sky_light, 0.0, 0.0, 0.0]
+        };
+
+        let mut near = 0.0;
+        for (i, far) in CASCADE_SPLITS.into_iter().enumerate() {
+            let (view_proj, radius) = fit_cascade(camera, lighting.light_direction, near, far);
+            near = far;
+
+            uniform.cascades[i] = view_proj.into();
+
+            let cascade = &mut self.cascades[i];
+            cascade.view_proj = view_proj;
+            queue.write_buffer(&cascade.buffer, 0, bytemuck::cast_slice(&[uniform.cascades[i]]));
+
+            uniform.texel_sizes[i] = radius * 2.0 / CASCADE_RESOLUTION as f32;
+        }
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    /**
+     depth of every meshed slice inside each cascade, transparent ones included so leaves cast shadows too,
+     then of every render storage object
+    */
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, chunk_manager: &ChunkManager, objects: &ShadowObjects) {
+        for cascade in self.cascades.iter() {
+            let frustum = Frustum::from_matrix(&cascade.view_proj);
+
+            let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("sun shadow pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(
+                    wgpu::RenderPassDepthStencilAttachment {
+                        view: &cascade.view,
+                        depth_ops: Some(
+                            wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(1.0),
+                                store: wgpu::StoreOp::Store
+                            }
+                        ),
+                        stencil_ops: None,
+                    }
+                ),
+                timestamp_writes: None,
+                occlusion_query_set: None
+            });
+
+            shadow_pass.set_pipeline(&self.pipeline);
+            shadow_pass.set_bind_group(0, &cascade.bindgroup, &[]);
+            draw_shadow_casters(&mut shadow_pass, chunk_manager, &frustum);
+            draw_object_shadow_casters(&mut shadow_pass, &self.object_pipelines, objects);
+        }
+    }
+}
+
+/**
+ depth of every meshed slice inside a shadow frustum, for a pass with a shadowshader.wgsl pipeline and its
+ view projection bound at group 0
+*/
+pub fn draw_shadow_casters<'a>(shadow_pass: &mut wgpu::RenderPass<'a>, chunk_manager: &'a ChunkManager, frustum: &Frustum) {
+    let mesh_pool = &chunk_manager.mesh_pool;
+
+    shadow_pass.set_bind_group(1, &mesh_pool.bindgroup, &[]);
+    shadow_pass.set_vertex_buffer(0, mesh_pool.vertex_buffer().slice(..));
+    shadow_pass.set_index_buffer(mesh_pool.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
+
+    for (index, buffers) in chunk_manager.chunk_buffers.iter() {
+        let Some(chunk) = chunk_manager.chunks.get(index) else {continue};
+
+        for y in 0..buffers.slots.len() {
+            if chunk.states[y] != ChunkState::Ready {continue}
+
+            let min = Vector3::new(buffers.position.x as f32, y as f32, buffers.position.y as f32) * 16.0;
+            if !frustum.intersects_aabb(min, min + Vector3::new(16.0, 16.0, 16.0)) {continue}
+
+            let slot = buffers.slots[y];
+            for allocation in [buffers.solid[y], buffers.transparent[y]].into_iter().flatten() {
+                shadow_pass.draw_indexed(0..allocation.index_count(), allocation.offset as i32, slot..slot + 1);
+            }
+        }
+    }
+}
+
+/**
+ depth only pipelines for render storage objects, built against a shadow pass's own layer layout
+*/
+pub struct ObjectShadowPipelines {
+    object: wgpu::RenderPipeline,
+    skinned_object: wgpu::RenderPipeline
+}
+
+impl ObjectShadowPipelines {
+    pub fn new(device: &wgpu::Device, layer_bindgroup_layout: &wgpu::BindGroupLayout, joint_bindgroup_layout: &wgpu::BindGroupLayout) -> Self {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("object shadow pipeline layout"),
+            bind_group_layouts: &[layer_bindgroup_layout, joint_bindgroup_layout],
+            push_constant_ranges: &[]
+        });
+
+        let create = |name: &str, shader_path: &str, vertex_layouts: &[wgpu::VertexBufferLayout]| create_render_pipeline(
+            name,
+            device,
+            &pipeline_layout,
+            wgpu::TextureFormat::Rgba8Unorm,
+            Some(wgpu::TextureFormat::Depth32Float),
+            vertex_layouts,
+            shader_path,
+            false,
+            true,
+            Some(wgpu::DepthBiasState {
+                constant: 2,
+                slope_scale: 2.0,
+                clamp: 0.0
+            }),
+            true,
+            true,
+            true,
+            false,
+            1
+        );
+
+        Self {
+            object: create("object shadow pipeline", "shaders/objectshadowshader.wgsl", &[ModelVertex::desc(), RawObject::desc()]),
+            skinned_object: create("skinned object shadow pipeline", "shaders/skinnedobjectshadowshader.wgsl", &[ModelVertex::desc(), RawObject::desc(), SkinVertex::desc()])
+        }
+    }
+}
+
+/**
+ the render storage objects as shadow passes see them: the instance buffer the renderer keeps up to date
+ and the bind group with this frame's joint palette
+*/
+pub struct ShadowObjects<'a> {
+    pub render_storage: &'a RenderStorage,
+    pub instance_buffer: &'a wgpu::Buffer,
+    pub joint_bindgroup: &'a wgpu::BindGroup
+}
+
+/**
+ depth of every render storage object, for a pass with its view projection bound at group 0. objects aren't culled,
+ like in the main pass every batch is one instanced draw per mesh
+*/
+pub fn draw_object_shadow_casters<'a>(shadow_pass: &mut wgpu::RenderPass<'a>, pipelines: &'a ObjectShadowPipelines, objects: &ShadowObjects<'a>) {
+    let batches = objects.render_storage.get_batches();
+    if batches.is_empty() {return};
+
+    shadow_pass.set_bind_group(1, objects.joint_bindgroup, &[]);
+    shadow_pass.set_vertex_buffer(1, objects.instance_buffer.slice(..));
+
+    for batch in batches {
+        let instances = batch.first_instance..batch.first_instance + batch.objects.len() as u32;
+
+        for mesh in batch.model.meshes.iter() {
+            match &mesh.skin_buffer {
+                Some(skin_buffer) => {
+                    shadow_pass.set_pipeline(&pipelines.skinned_object);
+                    shadow_pass.set_vertex_buffer(2, skin_buffer.slice(..));
+                },
+                None => shadow_pass.set_pipeline(&pipelines.object)
+            }
+
+            shadow_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            shadow_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            shadow_pass.draw_indexed(0..mesh.num_elements, 0, instances.clone());
+        }
+    }
+}
+
+/**
+ light view projection around the bounding sphere of the camera frustum between near and far
+*/
+fn fit_cascade(camera: &Camera, light_direction: Vector3<f32>, near: f32, far: f32) -> (Matrix4<f32>, f32) {
+    let look = camera.look_vector();
+    let right = camera.right_vector();
+    let up = right.cross(look).normalize();
+    let tan_y = (camera.fov.0 * 0.5).tan();
+    let tan_x = tan_y * camera.aspect_ratio;
+
+    let corners = [near, far].into_iter().flat_map(|d| {
+        [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].map(|(sx, sy)| {
+            camera.position + look * d + right * (sx * tan_x * d) + up * (sy * tan_y * d)
+        })
+    }).collect::<Vec<_>>();
+
+    let centre = Point3::from_vec(corners.iter().map(|c| c.to_vec()).sum::<Vector3<f32>>() / corners.len() as f32);
+    //rounded up so turning the camera doesn't change the cascade's size
+    let radius = (corners.iter().map(|c| c.distance(centre)).fold(0.0, f32::max) * 16.0).ceil() / 16.0;
+
+    let eye = centre + light_direction * (radius + CASTER_MARGIN);
+    let view = Matrix4::look_to_rh(eye, -light_direction, Vector3::unit_y());
+    let mut projection = OPENGL_TO_WGPU_MATRIX * ortho(-radius, radius, -radius, radius, 0.0, radius * 2.0 + CASTER_MARGIN);
+
+    //move the world origin onto a texel so the shadow edges don't crawl
+    let origin = (projection * view) * Vector4::new(0.0, 0.0, 0.0, 1.0);
+    let texels = origin.truncate().truncate() * (CASCADE_RESOLUTION as f32 * 0.5);
+    let snapped = texels.map(|v| v.round()) - texels;
+    projection.w.x += snapped.x * 2.0 / CASCADE_RESOLUTION as f32;
+    projection.w.y += snapped.y * 2.0 / CASCADE_RESOLUTION as f32;
+
+    (projection * view, radius)
+}
+
+/**
+ layout of SunShadows::bindgroup, for pipelines that receive shadows and local lights
+ (shaders/include/sun_shadows.wgsl, shaders/include/lights.wgsl)
+*/
+pub fn create_sun_shadows_bindgroup_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("sun shadows bind group layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2Array,
+                    multisampled: false
+                },
+                count: None
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                count: None
+            },
+            //the light manager's lights and their shadow atlas
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2Array,
+                    multisampled: false
+                },
+                count: None
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 5,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                count: None
+            }
+        ]
+    })
+}
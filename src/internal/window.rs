@@ -3,7 +3,7 @@ use egui_wgpu::ScreenDescriptor;
 use instant::Duration;
 use winit::{event_loop::EventLoop, window::Window, window::WindowBuilder};
 
-use crate::{gui::{elements::screenui::ScreenUi, guirenderer::GuiRenderer}, internal::renderer::MainRenderer, state::workspace::Workspace};
+use crate::{gui::{elements::screenui::ScreenUi, guirenderer::GuiRenderer}, internal::{antialiasing::Antialiasing, camera::create_camera_bindgroup_layout, gpu::{request_gpu, GpuContext, GraphicsConfig}, renderer::MainRenderer}, state::workspace::Workspace};
 
 pub struct GameWindow<'a> {
     surface: wgpu::Surface<'a>,
@@ -16,7 +16,8 @@ pub struct GameWindow<'a> {
     pub renderer: MainRenderer,
     pub gui_renderer: GuiRenderer,
     pub camera_bindgroup_layout: wgpu::BindGroupLayout,
-    pub screenui: Box<ScreenUi>
+    pub screenui: Box<ScreenUi>,
+    pub minimised: bool
 }
 
 impl<'a> GameWindow<'a> {
@@ -25,36 +26,13 @@ impl<'a> GameWindow<'a> {
 
         let window_size = window.inner_size();
 
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            flags: wgpu::InstanceFlags::empty(),
-            backends: wgpu::Backends::DX12,
-            dx12_shader_compiler: Default::default(),
-            gles_minor_version: wgpu::Gles3MinorVersion::Automatic
-        });
+        let config = GraphicsConfig::load();
+
+        let instance = config.create_instance();
 
         let surface = instance.create_surface(window.clone()).unwrap();
 
-        let adapter = instance.request_adapter(
-            &wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false
-            }
-        ).await.unwrap();
-
-        println!("ADAPTER: {:?}", adapter.get_info());
-
-        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
-            required_features: wgpu::Features::TEXTURE_BINDING_ARRAY
-             | wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING 
-             | wgpu::Features::BGRA8UNORM_STORAGE | wgpu::Features::DEPTH_CLIP_CONTROL,
-            required_limits: wgpu::Limits {
-                max_sampled_textures_per_shader_stage: 961,
-                max_bind_groups: 5,
-                ..Default::default()
-            },
-            label: None,
-        }, None).await.unwrap();
+        let GpuContext { adapter, device, queue, texture_binding_mode, slice_draw_mode, msaa_sample_counts } = request_gpu(&instance, &config, Some(&surface)).await.expect("Unable to set up a graphics device");
 
         let surface_capabilities = surface.get_capabilities(&adapter);
 
@@ -65,7 +43,7 @@ impl<'a> GameWindow<'a> {
             .unwrap_or(surface_capabilities.formats[surface_capabilities.formats.len() - 1]);
 
         let surface_config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_DST,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | (surface_capabilities.usages & wgpu::TextureUsages::COPY_DST),
             format: surface_format,
             width: window_size.width,
             height: window_size.height,
@@ -75,28 +53,22 @@ impl<'a> GameWindow<'a> {
             desired_maximum_frame_latency: 2
         };
 
-        let camera_bindgroup_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }
-            ],
-            label: Some("camera bind group layout :)"),
-        });
+        let camera_bindgroup_layout = create_camera_bindgroup_layout(&device);
 
+        //started minimised, the surface is configured on the first resize to a real size
+        let minimised = window_size.width == 0 || window_size.height == 0;
+
+        //size dependent targets can't be empty, resize gives them the real size later
         let renderer = MainRenderer::new(
             &device, &queue, surface_format, 
-            &camera_bindgroup_layout, (window_size.width, window_size.height)
+            &camera_bindgroup_layout, (window_size.width.max(1), window_size.height.max(1)),
+            texture_binding_mode, slice_draw_mode, config.post_processing.clone(),
+            Antialiasing::new(config.antialiasing, msaa_sample_counts)
         );
 
-        surface.configure(&device, &surface_config);
+        if !minimised {
+            surface.configure(&device, &surface_config);
+        }
 
         let gui_renderer = GuiRenderer::new(&device, surface_format, None, 1, &window);
 
@@ -113,23 +85,59 @@ impl<'a> GameWindow<'a> {
             renderer,
             camera_bindgroup_layout,
             gui_renderer,
-            screenui
+            screenui,
+            minimised
+        }
+    }
+
+    /**
+     reconfigures the surface and every size dependent target. a zero size means the window was minimised,
+     rendering stays paused until a real size comes back
+    */
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>, workspace: &mut Workspace) {
+        self.window_size = new_size;
+
+        if new_size.width == 0 || new_size.height == 0 {
+            self.minimised = true;
+            return;
         }
+
+        self.minimised = false;
+
+        self.surface_config.width = new_size.width;
+        self.surface_config.height = new_size.height;
+        self.surface.configure(&self.device, &self.surface_config);
+
+        self.renderer.resize(&self.device, new_size.width, new_size.height);
+        workspace.current_camera.resize(new_size.width, new_size.height);
     }
 
     pub fn on_next_frame(&mut self, workspace: &mut Workspace, dt: f32) {
+        if self.minimised {
+            return;
+        }
 
+        workspace.world_clock.advance(dt);
         workspace.current_camera.update_camera(dt);
         workspace.current_camera.update_matrices(&self.queue);
 
-        let mut output = self.surface.get_current_texture().unwrap();
+        let output = match self.surface.get_current_texture() {
+            Ok(output) => output,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                //surface went away (display change, driver reset), set it up again and try next frame
+                self.resize(self.window.inner_size(), workspace);
+                return;
+            },
+            Err(wgpu::SurfaceError::Timeout) => return,
+            Err(e @ wgpu::SurfaceError::OutOfMemory) => panic!("Unable to get the next surface texture: {}", e)
+        };
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Primary Encoder")
         });
 
-        self.renderer.render_surface(&self.device, &self.queue, &mut output, &view, &mut encoder, workspace);
+        self.renderer.render_surface(&self.device, &self.queue, &view, &mut encoder, workspace);
 
         let screen_descriptor = ScreenDescriptor {
             size_in_pixels: [self.window_size.width, self.window_size.height],
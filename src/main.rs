@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 use std::ops::DerefMut;
+use std::path::Path;
 use std::sync::mpsc::{Receiver, Sender};
 use std::sync::{mpsc, Arc};
+use std::time::Duration;
 use parking_lot::RwLock;
 use blocks::stoneblock::StoneBlock;
-use cgmath::{Point3, Vector2, Vector3};
+use cgmath::{Vector2, Vector3};
 use engine::surfacevertex::SurfaceVertex;
 use gen::primitive::PrimitiveBuilder;
 use gui::elements::slider::Slider;
@@ -12,6 +14,7 @@ use gui::elements::textbutton::TextButton;
 use gui::elements::textlabel::TextLabel;
 use gui::uistate::MouseButton;
 use internal::raycaster::raycast_blocks;
+use internal::offscreen::render_world_to_file;
 use internal::window::GameWindow;
 use pollster::FutureExt;
 use state::workspace::Workspace;
@@ -21,8 +24,9 @@ use util::inputservice::{InputService, MouseLockState};
 use vox::chunk::{xz_to_index, Chunk, ChunkGridType, ChunkState};
 use vox::chunk_manager::mesh_slice_arrayed;
 use vox::chunkactionqueue::ChunkAction;
+use vox::structure_exporter::{export_structure, selection_to_structure, unused_structure_name};
 use vox::structure_loader::load_structures;
-use vox::worker_threads::{spawn_chunk_creation_loop, spawn_chunk_meshing_loop};
+use vox::worker_threads::{receive_lod_results, receive_worker_results, spawn_chunk_creation_loop, spawn_chunk_meshing_loop, spawn_lod_loop};
 use winit::event::{DeviceEvent, Event, KeyEvent, WindowEvent};
 use winit::event_loop::EventLoop;
 use winit::keyboard::{KeyCode, PhysicalKey};
@@ -54,6 +58,20 @@ async fn main() {
     });
     //find way to edit arc
 
+    //--offscreen <out.png> [--size <width>x<height>] renders one frame without a window and exits
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(i) = args.iter().position(|a| a == "--offscreen") {
+        let path = args.get(i + 1).map_or("offscreen.png", |v| v.as_str());
+        let (width, height) = args.iter().position(|a| a == "--size")
+            .and_then(|i| args.get(i + 1))
+            .and_then(|v| v.split_once('x'))
+            .and_then(|(w, h)| Some((w.parse::<u32>().ok()?, h.parse::<u32>().ok()?)))
+            .unwrap_or((1280, 720));
+        load_structures();
+        render_world_to_file(Path::new(path), width, height, Duration::from_secs(600)).unwrap();
+        return;
+    }
+
     let event_loop = EventLoop::new().unwrap();
 
     let window = Arc::new(WindowBuilder::new().build(&event_loop).unwrap());
@@ -65,7 +83,7 @@ async fn main() {
     let workspace_arc = Arc::new(RwLock::new(Workspace::new(
         &gamewindow.device, &gamewindow.camera_bindgroup_layout, 
         gamewindow.window_size.width, gamewindow.window_size.height,
-        window.clone()
+        Some(window.clone()), gamewindow.renderer.antialiasing.clone()
     )));
 
     let mut workspace = workspace_arc.write();
@@ -73,6 +91,8 @@ async fn main() {
     let (sendmesh, getmesh) = spawn_chunk_meshing_loop(3);
     let (sendchunk, getchunk) = spawn_chunk_creation_loop(4, workspace.chunk_manager.seed);
     workspace.chunk_manager.generate_chunks(&gamewindow.device, &sendchunk, Vector2::new(0., 0.));
+    let (sendlod, getlod) = spawn_lod_loop(2, workspace.chunk_manager.seed);
+    workspace.chunk_manager.lod.request_tiles(&sendlod, Vector2::new(0, 0));
 
     {
         let wa = workspace_arc.clone();
@@ -111,13 +131,6 @@ async fn main() {
         });
     }
 
-    {
-        let light = gamewindow.renderer.create_spotlight(
-            Point3::new(15., 25., 15.), 
-            Point3::new(0., 0., 0.)
-        );
-    }
-
     let mut debugger = Debugger::new(workspace.chunk_manager.seed, &mut gamewindow.screenui);
 
 
@@ -145,6 +158,75 @@ async fn main() {
         });
     }
 
+    {
+        let wa = workspace_arc.clone();
+        workspace.input_service.on_key_pressed.connect(move |(code, consumed)| {
+            if consumed {return};
+            if code != KeyCode::KeyC && code != KeyCode::KeyV && code != KeyCode::KeyB && code != KeyCode::KeyN {return};
+
+            if code == KeyCode::KeyB {
+                //only read the blocks under the lock, writing the files happens without it
+                let data = {
+                    let lock = wa.read();
+                    selection_to_structure(&lock.structure_selection, &lock.chunk_manager)
+                };
+
+                let name = unused_structure_name(&format!("export_{}", std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()));
+                match data.and_then(|data| export_structure(data, &name)) {
+                    Ok(path) => println!("Exported selection to {}", path),
+                    Err(e) => println!("Unable to export selection: {}", e)
+                }
+                return;
+            }
+
+            let lock = &mut *wa.write();
+
+            if code == KeyCode::KeyN {
+                lock.structure_selection.clear();
+                return;
+            }
+
+            let p = lock.current_camera.position;
+
+            let pos = Vector3::new(p.x, p.y, p.z);
+
+            let res = raycast_blocks(pos, lock.current_camera.look_vector(), 400.0, &lock.chunk_manager, |_| false);
+
+            if res.is_none() {return};
+
+            let abs = res.unwrap().hit.get_absolute_position();
+
+            if code == KeyCode::KeyC {
+                lock.structure_selection.corner_a = Some(abs);
+            }
+            else {
+                lock.structure_selection.corner_b = Some(abs);
+            }
+        });
+    }
+
+    {
+        let wa = workspace_arc.clone();
+        workspace.input_service.on_key_pressed.connect(move |(code, consumed)| {
+            if consumed {return};
+
+            let lock = &mut *wa.write();
+            let clock = &mut lock.world_clock;
+
+            match code {
+                KeyCode::KeyP => clock.toggle_pause(),
+                KeyCode::BracketRight => clock.set_time(clock.time() + 1.0),
+                KeyCode::BracketLeft => clock.set_time(clock.time() - 1.0),
+                KeyCode::Minus => clock.set_day_length(clock.day_length * 0.5),
+                KeyCode::Equal => clock.set_day_length(clock.day_length * 2.0),
+                KeyCode::KeyF => lock.fog.mode = lock.fog.mode.next(),
+                KeyCode::KeyM => lock.antialiasing.next_msaa(),
+                KeyCode::KeyG => lock.antialiasing.fxaa = !lock.antialiasing.fxaa,
+                _ => {}
+            }
+        });
+    }
+
     let mut last_update = instant::Instant::now();
 
     drop(workspace);
@@ -185,7 +267,11 @@ async fn main() {
                             control_flow.exit()
                         },
                         WindowEvent::Resized(physical_size) => {
-                            //win.resize(*physical_size);
+                            gamewindow.resize(*physical_size, &mut workspace);
+                        },
+                        WindowEvent::ScaleFactorChanged { .. } => {
+                            //egui picks up the new scale itself, the physical size may have changed with it
+                            gamewindow.resize(gamewindow.window.inner_size(), &mut workspace);
                         },
                         WindowEvent::RedrawRequested => {
                             if window_id == window.id() {
@@ -197,39 +283,10 @@ async fn main() {
                                 
                                 workspace.chunk_manager.on_frame_action(&gamewindow.device, &sendmesh);
                                 workspace.input_service.update();
-                                debugger.update(&workspace, &mut gamewindow.screenui);
+                                debugger.update(&workspace, &mut gamewindow.screenui, &gamewindow.renderer.cull_stats);
                                 
-                                for _ in 0..10 {
-                                    if let Ok(res) = getmesh.try_recv() {
-                                        let at = Vector3::new(res.0, res.2 as i32, res.1);
-                                        let index = workspace.chunk_manager.unresolved_meshes.iter().position(|p| *p == at);
-                                        if let Some(i) = index {
-                                            workspace.chunk_manager.unresolved_meshes.swap_remove(i);
-                                        }
-                                        workspace.chunk_manager.finalize_mesh(res.0, res.1, res.2, &gamewindow.device, res.3);
-                                    }
-                                    else {
-                                        break;
-                                    }
-                                }
-                                loop {
-                                    if let Ok(res) = getchunk.try_recv() {
-                                        let chunkbuff = workspace.chunk_manager.chunk_buffers.get_mut(&xz_to_index(res.0, res.1)).unwrap();
-
-                                        chunkbuff.set_slice_vertex_buffers(&gamewindow.device);
-
-                                        workspace.chunk_manager.chunks.insert(xz_to_index(res.0, res.1), res.2);
-
-                                        if workspace.chunk_manager.chunks.len() as u32 == (workspace.chunk_manager.render_distance * 2 + 1).pow(2) {
-                                            println!("Beginning Illumination");
-                                            workspace.chunk_manager.generate_chunk_illumination(&gamewindow.device);
-                                            workspace.chunk_manager.mesh_chunks(&gamewindow.device, &sendmesh, Vector3::new(0., 0., 0.));
-                                        }
-                                    }
-                                    else {
-                                        break;
-                                    }
-                                }
+                                receive_worker_results(&mut workspace.chunk_manager, &gamewindow.device, &gamewindow.queue, &sendmesh, &getmesh, &getchunk);
+                                receive_lod_results(&mut workspace.chunk_manager, &gamewindow.device, &getlod);
                                 //println!("Frame time: {}ms", framestart.elapsed_ms());
                             }
                         }
@@ -1 +1,2 @@
-pub mod workspace;
\ No newline at end of file
+pub mod workspace;
+pub mod worldclock;
\ No newline at end of file
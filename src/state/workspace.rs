@@ -3,20 +3,34 @@ use std::sync::Arc;
 use cgmath::Point3;
 use winit::window::Window;
 
-use crate::{internal::camera::Camera, util::inputservice::InputService, vox::chunk_manager::ChunkManager};
+use crate::{internal::{antialiasing::Antialiasing, camera::Camera, fog::Fog}, state::worldclock::WorldClock, util::inputservice::InputService, vox::{chunk_manager::ChunkManager, structure_exporter::StructureSelection}};
 
 pub struct Workspace {
     pub current_camera: Camera,
     pub chunk_manager: ChunkManager,
-    pub input_service: InputService
+    pub input_service: InputService,
+    pub structure_selection: StructureSelection,
+    pub world_clock: WorldClock,
+    pub fog: Fog,
+    //what the renderer should use, it catches up on the next frame
+    pub antialiasing: Antialiasing
 }
 
 impl Workspace {
-    pub fn new(device: &wgpu::Device, camera_bindgroup_layout: &wgpu::BindGroupLayout, width: u32, height: u32, window: Arc<Window>) -> Self {
+    pub fn new(device: &wgpu::Device, camera_bindgroup_layout: &wgpu::BindGroupLayout, width: u32, height: u32, window: Option<Arc<Window>>, antialiasing: Antialiasing) -> Self {
+        let chunk_manager = ChunkManager::new(device);
+
+        let mut current_camera = Camera::new(Point3::new(0., 140., 0.), -90.0, -20.0, width as f32 / height as f32, 70., device, camera_bindgroup_layout, (width, height));
+        current_camera.set_view_distance(chunk_manager.lod.view_distance());
+
         Self {
-            current_camera: Camera::new(Point3::new(0., 140., 0.), -90.0, -20.0, width as f32 / height as f32, 70., device, camera_bindgroup_layout, (width, height)),
-            chunk_manager: ChunkManager::new(),
-            input_service: InputService::new(window)
+            current_camera,
+            chunk_manager,
+            input_service: InputService::new(window),
+            structure_selection: StructureSelection::new(),
+            world_clock: WorldClock::new(),
+            fog: Fog::new(),
+            antialiasing
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,98 @@
+use std::f32::consts::TAU;
+
+use cgmath::{InnerSpace, Vector3};
+
+//real seconds in one day of world time
+pub const DEFAULT_DAY_LENGTH: f32 = 1200.0;
+
+//horizontal direction the sun rises towards, and where it peaks at noon. the noon sun is the old fixed sun
+const SUNRISE_DIRECTION: Vector3<f32> = Vector3::new(0.70710677, 0.0, -0.70710677);
+const NOON_DIRECTION: Vector3<f32> = Vector3::new(0.4472136, 0.7745967, 0.4472136);
+
+//baked sunlight is scaled down to this under the moon
+const NIGHT_SKY_LIGHT: f32 = 0.12;
+
+/**
+ what the renderer needs from the time of day
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct WorldLighting {
+    //towards whichever of the two is up, moved once per world minute so shadow maps don't shimmer in between
+    pub light_direction: Vector3<f32>,
+    //multiplies the 0..15 sunlight baked into chunk vertices
+    pub sky_light: f32,
+    //exact sun position for the sky, and where it will be six hours later. the stars turn with these two
+    pub sun_direction: Vector3<f32>,
+    pub sun_tangent: Vector3<f32>
+}
+
+/**
+ world time in hours, 0 is midnight and 12 noon
+*/
+pub struct WorldClock {
+    time: f32,
+    pub day_length: f32,
+    pub paused: bool
+}
+
+impl WorldClock {
+    pub fn new() -> Self {
+        Self {
+            time: 12.0,
+            day_length: DEFAULT_DAY_LENGTH,
+            paused: false
+        }
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        if self.paused || self.day_length <= 0.0 {return}
+        self.set_time(self.time + dt / self.day_length * 24.0);
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /**
+     wraps into 0..24
+    */
+    pub fn set_time(&mut self, hours: f32) {
+        self.time = hours.rem_euclid(24.0);
+    }
+
+    pub fn set_day_length(&mut self, seconds: f32) {
+        self.day_length = seconds;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn lighting(&self) -> WorldLighting {
+        let sun_direction = sun_direction_at(self.time);
+
+        let minute = (self.time * 60.0).floor() / 60.0;
+        //the moon is always opposite the sun
+        let light_direction = if sun_direction.y >= 0.0 {sun_direction_at(minute)} else {-sun_direction_at(minute)};
+
+        let day = smoothstep(-0.1, 0.2, sun_direction.y);
+
+        WorldLighting {
+            light_direction,
+            sky_light: NIGHT_SKY_LIGHT + (1.0 - NIGHT_SKY_LIGHT) * day,
+            sun_direction,
+            sun_tangent: sun_direction_at(self.time + 6.0)
+        }
+    }
+}
+
+fn sun_direction_at(hours: f32) -> Vector3<f32> {
+    //rises at 6, peaks at 12, sets at 18
+    let angle = (hours / 24.0 - 0.25) * TAU;
+    (SUNRISE_DIRECTION * angle.cos() + NOON_DIRECTION * angle.sin()).normalize()
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
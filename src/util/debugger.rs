@@ -2,7 +2,7 @@ use std::sync::{Arc, RwLock, RwLockWriteGuard};
 
 use cgmath::Vector3;
 
-use crate::{gui::elements::{frame::Frame, screenui::ScreenUi, textlabel::TextLabel}, internal::raycaster::raycast_blocks, state::workspace::Workspace, vox::chunk_manager::ChunkManager};
+use crate::{gui::elements::{frame::Frame, screenui::ScreenUi, textlabel::TextLabel}, internal::{frustum::CullStats, raycaster::raycast_blocks}, state::workspace::Workspace, vox::chunk_manager::ChunkManager};
 
 use super::helpers::get_typed;
 
@@ -20,10 +20,18 @@ impl Debugger {
         let camera_position_text = TextLabel::new("debugger-camera-position".to_owned(), "Camera Position: (0, 0, 0)".to_owned());
         let camera_lookat_text = TextLabel::new("debugger-camera-lookat".to_owned(), "Looking At: (0, 0, 0)".to_owned());
         let target_block_text = TextLabel::new("debugger-target-block".to_owned(), "Target Block: (0, 0, 0), [blockname]".to_owned());
+        let culling_text = TextLabel::new("debugger-culling".to_owned(), "Slices: 0 drawn, 0 culled".to_owned());
+        let time_text = TextLabel::new("debugger-time".to_owned(), "Time: 12:00".to_owned());
+        let fog_text = TextLabel::new("debugger-fog".to_owned(), "Fog: Linear".to_owned());
+        let antialiasing_text = TextLabel::new("debugger-antialiasing".to_owned(), "MSAA: 1x, FXAA: off".to_owned());
         
         frame.add_child(camera_position_text);
         frame.add_child(camera_lookat_text);
         frame.add_child(target_block_text);
+        frame.add_child(culling_text);
+        frame.add_child(time_text);
+        frame.add_child(fog_text);
+        frame.add_child(antialiasing_text);
 
         screenui.add_child(frame);
         
@@ -36,7 +44,7 @@ impl Debugger {
         }
     }
 
-    pub fn update(&mut self, workspace_write: &parking_lot::lock_api::RwLockWriteGuard<parking_lot::RawRwLock, Workspace>, screengui: &mut ScreenUi) {
+    pub fn update(&mut self, workspace_write: &parking_lot::lock_api::RwLockWriteGuard<parking_lot::RawRwLock, Workspace>, screengui: &mut ScreenUi, cull_stats: &CullStats) {
 
         let p = workspace_write.current_camera.position;
 
@@ -56,5 +64,33 @@ impl Debugger {
                 target_block_text.set_text("Target Block: None".to_string());
             }
         }
+
+        let culling_text = screengui.search_for_mut::<Box<TextLabel>>("debugger-frame/debugger-culling".to_owned()).unwrap();
+
+        culling_text.set_text(format!(
+            "Slices: {} drawn, {} culled, {} occluded, transparent: {} drawn, {} culled, lights: {} visible, {} culled",
+            cull_stats.drawn_slices, cull_stats.culled_slices, cull_stats.occluded_slices,
+            cull_stats.drawn_transparent_slices, cull_stats.culled_transparent_slices,
+            cull_stats.visible_lights, cull_stats.culled_lights
+        ));
+
+        let time_text = screengui.search_for_mut::<Box<TextLabel>>("debugger-frame/debugger-time".to_owned()).unwrap();
+
+        let clock = &workspace_write.world_clock;
+        let minutes = (clock.time() * 60.0) as u32;
+        time_text.set_text(format!(
+            "Time: {:02}:{:02}{}, day length {}s ([ ] hour, - = day length, P pause)",
+            minutes / 60, minutes % 60, if clock.paused {" (paused)"} else {""}, clock.day_length
+        ));
+
+        let fog_text = screengui.search_for_mut::<Box<TextLabel>>("debugger-frame/debugger-fog".to_owned()).unwrap();
+        fog_text.set_text(format!("Fog: {:?} (F)", workspace_write.fog.mode));
+
+        let antialiasing = &workspace_write.antialiasing;
+        let antialiasing_text = screengui.search_for_mut::<Box<TextLabel>>("debugger-frame/debugger-antialiasing".to_owned()).unwrap();
+        antialiasing_text.set_text(format!(
+            "MSAA: {}x (M), FXAA: {} (G)",
+            antialiasing.msaa_samples(), if antialiasing.fxaa {"on"} else {"off"}
+        ));
     }
 }
\ No newline at end of file
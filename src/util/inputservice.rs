@@ -47,7 +47,8 @@ pub struct InputService {
     mouse_lock_state: MouseLockState,
     mouse_visible: bool,
     
-    window: Arc<Window>,
+    //none when running headless
+    window: Option<Arc<Window>>,
 
     fnqueue: Arc<RwLock<VecDeque<InputActionQueue>>>
 }
@@ -56,7 +57,7 @@ unsafe impl Send for InputService {}
 unsafe impl Sync for InputService {}
 
 impl InputService {
-    pub fn new(window: Arc<Window>) -> Self {
+    pub fn new(window: Option<Arc<Window>>) -> Self {
         Self {
             on_key_pressed: MonoThreadSignal::new(),
             on_key_released: MonoThreadSignal::new(),
@@ -77,24 +78,28 @@ impl InputService {
     }
 
     fn iset_mouse_visible(&mut self, visible: bool) {
-        self.window.set_cursor_visible(visible);
+        if let Some(window) = &self.window {
+            window.set_cursor_visible(visible);
+        }
         self.mouse_visible = visible;
     }
 
     pub fn get_mouse_visible(&self) -> bool {self.mouse_visible}
 
     fn iset_mouse_lock_state(&mut self, state: MouseLockState) {
-        match state {
-            MouseLockState::Free => {
-                self.window.set_cursor_grab(winit::window::CursorGrabMode::None).unwrap();
-            },
-            MouseLockState::Contained => {
-                self.window.set_cursor_grab(winit::window::CursorGrabMode::Confined).unwrap();
-            },
-            MouseLockState::LockCenter => {
-                self.window.set_cursor_grab(winit::window::CursorGrabMode::Confined).unwrap();
-            },
-        };
+        if let Some(window) = &self.window {
+            match state {
+                MouseLockState::Free => {
+                    window.set_cursor_grab(winit::window::CursorGrabMode::None).unwrap();
+                },
+                MouseLockState::Contained => {
+                    window.set_cursor_grab(winit::window::CursorGrabMode::Confined).unwrap();
+                },
+                MouseLockState::LockCenter => {
+                    window.set_cursor_grab(winit::window::CursorGrabMode::Confined).unwrap();
+                },
+            };
+        }
         self.mouse_lock_state = state;
     }
 
@@ -119,8 +124,10 @@ impl InputService {
             },
             MouseLockState::Contained => false,
             MouseLockState::LockCenter => {
-                let ws = self.window.inner_size();
-                self.window.set_cursor_position(PhysicalPosition::new(ws.width / 2, ws.height / 2)).unwrap();
+                if let Some(window) = &self.window {
+                    let ws = window.inner_size();
+                    window.set_cursor_position(PhysicalPosition::new(ws.width / 2, ws.height / 2)).unwrap();
+                }
                 true
             },
         }
@@ -5,7 +5,7 @@ use parking_lot::RwLock;
 
 use crate::{blocks::block::{calculate_illumination_bytes, BlockFace, BlockType, Blocks}, engine::surfacevertex::SurfaceVertex, internal::depthsort::Quad, vox::{chunk::xz_to_index, chunk_manager::get_block_at_absolute}};
 
-use super::chunk::Chunk;
+use super::{chunk::Chunk, visibility::SliceConnectivity};
 
 pub fn generate_indices(vertex_count: usize) -> Vec<u32> {
     let indices_count = vertex_count / 4;
@@ -29,7 +29,10 @@ pub enum MeshStageType {
     Fluid
 }
 
-pub fn binary_mesh(chunk_x: i32, chunk_z: i32, y_slice: u32, chunks: &HashMap<u32, Arc<Chunk>>, stage: MeshStageType) -> (Vec<SurfaceVertex>, Vec<u32>, u32, Vec<Quad>) {
+/**
+ the solid stage also works out which faces of the slice connect through open space, other stages return the default
+*/
+pub fn binary_mesh(chunk_x: i32, chunk_z: i32, y_slice: u32, chunks: &HashMap<u32, Arc<Chunk>>, stage: MeshStageType) -> (Vec<SurfaceVertex>, Vec<u32>, u32, Vec<Quad>, SliceConnectivity) {
     let mut axis_columns = [[[0u32; 18]; 18]; 3];
 
     let mut column_face_masks = [[[0u32; 18]; 18]; 6];
@@ -60,11 +63,16 @@ pub fn binary_mesh(chunk_x: i32, chunk_z: i32, y_slice: u32, chunks: &HashMap<u3
 
     let chunk = chunks.get(&xz_to_index(chunk_x, chunk_z)).unwrap();
 
+    let mut opaque = [[0u16; 16]; 16];
+
     for z in 0..16 {
         for y in 0..16 {
             for x in 0..16 {
                 let b = chunk.get_block_at(x as u32, y as u32 + y_slice * 16, z as u32);
                 if b.get_block() == Blocks::AIR {continue;}
+                if !b.has_partial_transparency() && !b.is_fluid() {
+                    opaque[z][y] |= 1 << x;
+                }
                 add_voxel_to_axis_cols(b.has_partial_transparency(), b.is_fluid(), x + 1, y + 1, z + 1, &mut axis_columns, &stage);
             }
         }
@@ -235,8 +243,9 @@ pub fn binary_mesh(chunk_x: i32, chunk_z: i32, y_slice: u32, chunks: &HashMap<u3
     let indices = generate_indices(vertices.len());
     let ilen = indices.len();
 
+    let connectivity = if stage == MeshStageType::Solid {SliceConnectivity::from_opaque(&opaque)} else {SliceConnectivity::default()};
 
-    (vertices, indices, ilen as u32, quads)
+    (vertices, indices, ilen as u32, quads, connectivity)
 }
 
 pub struct GreedyQuad {
@@ -288,12 +297,22 @@ impl GreedyQuad {
             o.into_iter().rev().for_each(|i| new_vertices.push_back(i));
         }
 
-        //todo: actually calculate the center
-        let center = block.get_absolute_position().map(|v| v as f32);
-
+        //corners are local to the slice, the block tells us which slice that is
+        let slice_origin = block.get_absolute_position().map(|v| (v.div_euclid(16) * 16) as f32);
+        let corners = [
+            face_dir.world_to_sample(axis, self.x as i32, self.y as i32),
+            face_dir.world_to_sample(axis, (self.x + self.w) as i32, (self.y + self.h) as i32)
+        ];
+        let center = slice_origin + Vector3::new(
+            (corners[0][0] + corners[1][0]) as f32,
+            (corners[0][1] + corners[1][1]) as f32,
+            (corners[0][2] + corners[1][2]) as f32
+        ) * 0.5;
+
+        //same winding as the mesh so sorted quads can be written straight back over it
         quads.push(Quad {
             center,
-            vertices: [v1, v2, v3, v4]
+            vertices: [new_vertices[0], new_vertices[1], new_vertices[2], new_vertices[3]]
         });
         
         vertices.extend(new_vertices);
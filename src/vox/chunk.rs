@@ -9,7 +9,7 @@ use wgpu::util::DeviceExt;
 
 use crate::{blocks::{airblock::AirBlock, block::{Block, BlockType, Blocks}, dirtblock::DirtBlock, grassblock::GrassBlock, stoneblock::StoneBlock}, engine::vertex::{ModelVertex, Vertex}, internal::depthsort::Quad, vox::{structure_loader::get_blocks_for_structure_at_point, worldgen::{density_map_plane, is_cave}}};
 
-use super::worldgen::generate_surface_height;
+use super::{meshpool::{MeshAllocation, MeshPool}, visibility::SliceConnectivity, worldgen::generate_surface_height};
 
 #[cached]
 pub fn local_xyz_to_index(x: u32, y: u32, z: u32) -> u32 {
@@ -34,28 +34,6 @@ pub fn xz_to_index(x: i32, z: i32) -> u32 {
     (0.5 * (x0 + z0) as f32 * (x0 + z0 + 1) as f32 + z0 as f32) as u32 //cantor pairing https://math.stackexchange.com/questions/3003672/convert-infinite-2d-plane-integer-coords-to-1d-number
 }
 
-#[repr(C)]
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct ChunkDataVertex {
-    pub position_sliced: [i32; 3],
-}
-
-impl Vertex for ChunkDataVertex {
-    fn desc() -> wgpu::VertexBufferLayout<'static> {
-        wgpu::VertexBufferLayout {
-            array_stride: mem::size_of::<ChunkDataVertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Instance,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    offset: 0,
-                    shader_location: 3,
-                    format: wgpu::VertexFormat::Sint32x3,
-                }
-            ],
-        }
-    }
-}
-
 #[derive(PartialEq, Clone, Copy)]
 pub enum ChunkState {
     Ready,
@@ -65,56 +43,43 @@ pub enum ChunkState {
 
 pub struct ChunkBuffers {
     pub position: Vector2<i32>,
-    //(vertex, index, len_indices)
-    pub solid_buffers: Vec<Option<(wgpu::Buffer, wgpu::Buffer, u32)>>,
-    pub transparent_buffers: Vec<Option<(wgpu::Buffer, wgpu::Buffer, u32)>>,
-    pub slice_vertex_buffers: Vec<wgpu::Buffer>,
+    //vertex ranges in the mesh pool, None for empty meshes
+    pub solid: Vec<Option<MeshAllocation>>,
+    pub transparent: Vec<Option<MeshAllocation>>,
+    //instance index of each slice
+    pub slots: Vec<u32>,
+    //bumped every time a slice's transparent mesh is replaced, so late depth sorts of the old one get dropped
+    pub transparent_versions: Vec<u32>
 }
 
 impl ChunkBuffers {
     pub fn new(x: i32, z: i32) -> Self {
         Self {
             position: Vector2::new(x, z),
-            solid_buffers: Vec::from_iter(std::iter::repeat_with(|| None).take(16)),
-            transparent_buffers: Vec::from_iter(std::iter::repeat_with(|| None).take(16)),
-            slice_vertex_buffers: Vec::new()
+            solid: vec![None; 16],
+            transparent: vec![None; 16],
+            slots: Vec::new(),
+            transparent_versions: vec![0; 16]
         }
     }
-    pub fn set_slice_vertex_buffers(&mut self, device: &wgpu::Device) {
-        let slice_vertex_buffers = (0..16).map(|y| {
-            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some(&format!("Chunk Data Buffer")),
-                contents: bytemuck::cast_slice(&[ChunkDataVertex {
-                    position_sliced: [self.position.x, y, self.position.y]
-                }]),
-                usage: wgpu::BufferUsages::VERTEX,
-            })
-        }).collect::<Vec<wgpu::Buffer>>();
-        self.slice_vertex_buffers = slice_vertex_buffers;
-    }
-    pub fn set_solid_buffer(&mut self, slice: u32, buffers: (wgpu::Buffer, wgpu::Buffer, u32)) {
-        self.solid_buffers[slice as usize] = Some(buffers);
+    pub fn assign_slots(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, pool: &mut MeshPool) {
+        self.slots = (0..16).map(|y| {
+            pool.allocate_slot(device, queue, Vector3::new(self.position.x, y, self.position.y))
+        }).collect();
     }
-    pub fn set_solid_buffers(&mut self, buffers: Vec<Option<(wgpu::Buffer, wgpu::Buffer, u32)>>) {
-        self.solid_buffers = buffers;
-    }
-    pub fn get_solid_buffer(&self, slice: u32) -> Option<&(wgpu::Buffer, wgpu::Buffer, u32)> {
-        self.solid_buffers[slice as usize].as_ref()
-    }
-    pub fn get_solid_buffers(&self) -> &Vec<Option<(wgpu::Buffer, wgpu::Buffer, u32)>> {
-        &self.solid_buffers
-    }
-    pub fn set_transparent_buffer(&mut self, slice: u32, buffers: (wgpu::Buffer, wgpu::Buffer, u32)) {
-        self.transparent_buffers[slice as usize] = Some(buffers);
-    }
-    pub fn set_transparent_buffers(&mut self, buffers: Vec<Option< (wgpu::Buffer, wgpu::Buffer, u32)>>) {
-        self.transparent_buffers = buffers;
-    }
-    pub fn get_transparent_buffer(&self, slice: u32) -> Option<&(wgpu::Buffer, wgpu::Buffer, u32)> {
-        self.transparent_buffers[slice as usize].as_ref()
-    }
-    pub fn get_transparent_buffers(&self) -> &Vec<Option<(wgpu::Buffer, wgpu::Buffer, u32)>> {
-        &self.transparent_buffers
+    /**
+     swaps in a new mesh for a slice, giving the old vertex range back to the pool
+    */
+    pub fn set_slice_meshes(&mut self, slice: u32, pool: &mut MeshPool, solid: Option<MeshAllocation>, transparent: Option<MeshAllocation>) {
+        let slice = slice as usize;
+
+        if let Some(old) = std::mem::replace(&mut self.solid[slice], solid) {
+            pool.free(old);
+        }
+        if let Some(old) = std::mem::replace(&mut self.transparent[slice], transparent) {
+            pool.free(old);
+        }
+        self.transparent_versions[slice] += 1;
     }
 }
 
@@ -125,7 +90,9 @@ pub struct Chunk {
     pub grid: ChunkGridType,
     
     pub states: Vec<ChunkState>,
-    pub transparent_quads: Vec<Vec<Quad>>
+    //shared so the transparency sorter can hold a slice's quads without keeping the whole chunk alive
+    pub transparent_quads: Vec<Arc<Vec<Quad>>>,
+    pub connectivity: Vec<SliceConnectivity>
 }
 
 impl Chunk {
@@ -273,7 +240,8 @@ impl Chunk {
             position,
             grid: blocks,
             states: Vec::from_iter(std::iter::repeat(ChunkState::RequiresLighting).take(16)),
-            transparent_quads: Vec::from_iter(std::iter::repeat_with(|| {Vec::new()}).take(16))
+            transparent_quads: Vec::from_iter(std::iter::repeat_with(|| {Arc::new(Vec::new())}).take(16)),
+            connectivity: vec![SliceConnectivity::default(); 16]
         }
     }
 
@@ -10,7 +10,7 @@ use wgpu::util::DeviceExt;
 
 use crate::{blocks::{airblock::AirBlock, block::{Block, BlockFace, BlockType, Blocks}}, engine::surfacevertex::SurfaceVertex, internal::depthsort::Quad, vox::chunkactionqueue::ChunkAction};
 
-use super::{binarymesher::{binary_mesh, MeshStageType}, chunk::{local_xyz_to_index, xz_to_index, Chunk, ChunkBuffers, ChunkGridType, ChunkState}, chunkactionqueue::ChunkActionQueue};
+use super::{binarymesher::{binary_mesh, MeshStageType}, chunk::{local_xyz_to_index, xz_to_index, Chunk, ChunkBuffers, ChunkGridType, ChunkState}, chunkactionqueue::ChunkActionQueue, lod::LodTerrain, meshpool::MeshPool, visibility::SliceConnectivity};
 
 #[derive(PartialEq)]
 struct LightingBFSRemoveNode {
@@ -26,6 +26,8 @@ struct LightingBFSAddNode {
 pub struct ChunkManager {
     pub chunks: HashMap<u32, Arc<Chunk>>,
     pub chunk_buffers: HashMap<u32, ChunkBuffers>,
+    pub mesh_pool: MeshPool,
+    pub lod: LodTerrain,
     pub render_distance: u32,
     pub seed: u32,
     noise_gen: Perlin,
@@ -43,7 +45,7 @@ pub fn get_block_at_absolute(x: i32, y: i32, z: i32, chunks: &HashMap<u32, Arc<C
     chunks.get(&xz_to_index(chunk_x, chunk_z)).map(|v| v.get_block_at(x.rem_euclid(16) as u32, y as u32, z.rem_euclid(16) as u32))
 }
 
-pub fn mesh_slice_arrayed(chunk_x: i32, chunk_z: i32, y_slice: u32, chunks: &HashMap<u32, Arc<Chunk>>) -> ((Vec<SurfaceVertex>, Vec<u32>, u32), (Vec<SurfaceVertex>, Vec<u32>, u32, Vec<Quad>)) {
+pub fn mesh_slice_arrayed(chunk_x: i32, chunk_z: i32, y_slice: u32, chunks: &HashMap<u32, Arc<Chunk>>) -> ((Vec<SurfaceVertex>, Vec<u32>, u32, SliceConnectivity), (Vec<SurfaceVertex>, Vec<u32>, u32, Vec<Quad>)) {
     //let chunk = &chunks[&xz_to_index(chunk_x, chunk_z)].read();
     
     // let mut vertices: Vec<SurfaceVertex> = Vec::with_capacity(16 * 16 * 16 * 6 * 4);
@@ -196,9 +198,10 @@ pub fn mesh_slice_arrayed(chunk_x: i32, chunk_z: i32, y_slice: u32, chunks: &Has
     //     (vertices_transparent, indices_transparent, itlen)
     // )
     let solidmesh = binary_mesh(chunk_x, chunk_z, y_slice, chunks, MeshStageType::Solid);
+    let transparentmesh = binary_mesh(chunk_x, chunk_z, y_slice, chunks, MeshStageType::Transparent);
     (
-        (solidmesh.0, solidmesh.1, solidmesh.2),
-        binary_mesh(chunk_x, chunk_z, y_slice, chunks, MeshStageType::Transparent)
+        (solidmesh.0, solidmesh.1, solidmesh.2, solidmesh.4),
+        (transparentmesh.0, transparentmesh.1, transparentmesh.2, transparentmesh.3)
     )
 }
 
@@ -209,12 +212,16 @@ pub fn push_n(vec: &mut Vec<u32>, start: u32, shifts: [u32; 6]) {
 }
 
 impl ChunkManager {
-    pub fn new() -> Self {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let render_distance = 5;
 
         Self {
             chunks: HashMap::new(),
             chunk_buffers: HashMap::new(),
-            render_distance: 5,
+            mesh_pool: MeshPool::new(device),
+            //lod distance is in chunks too
+            lod: LodTerrain::new(device, 128, render_distance),
+            render_distance,
             seed: 6744464,
             noise_gen: Perlin::new(rand::rngs::StdRng::seed_from_u64(88).next_u32()),
             action_queue: ChunkActionQueue::new(),
@@ -314,39 +321,13 @@ impl ChunkManager {
         }
     }
 
-    pub fn mesh_slice(&self, device: &wgpu::Device, chunk: &Chunk, y_slice: u32) -> ((wgpu::Buffer, wgpu::Buffer, u32), (wgpu::Buffer, wgpu::Buffer, u32, Vec<Quad>)) {
-        let ((vertices, indices, _), (vertices_transparent, indices_transparent, _, quads)) = mesh_slice_arrayed(chunk.position.x, chunk.position.y, y_slice, &self.chunks);
-
-        let ilen = indices.len() as u32;
-
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("Chunk Vertex Buffer")),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("Chunk Index Buffer")),
-            contents: bytemuck::cast_slice(&indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-
-        let ilen_t = indices_transparent.len() as u32;
-
-        let vertex_buffer_t = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("Chunk Vertex Buffer Transparent")),
-            contents: bytemuck::cast_slice(&vertices_transparent),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-
-        let index_buffer_t = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("Chunk Index Buffer Transparent")),
-            contents: bytemuck::cast_slice(&indices_transparent),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-
-        ((vertex_buffer, index_buffer, ilen), ((vertex_buffer_t, index_buffer_t, ilen_t, quads)))
+    /**
+     whether every slice of every loaded chunk has a finished mesh
+    */
+    pub fn all_slices_meshed(&self) -> bool {
+        self.chunks.values().all(|c| c.states.iter().all(|s| matches!(s, ChunkState::Ready)))
     }
+
     #[inline]
     pub fn get_block_at_absolute(&self, x: i32, y: i32, z: i32) -> Option<&BlockType> {
         if y < 0 || y > 255 {return None};
@@ -761,42 +742,20 @@ impl ChunkManager {
 
     }
 
-    pub fn finalize_mesh(&mut self, x: i32, z: i32, slice: u32, device: &wgpu::Device, data: ((Vec<SurfaceVertex>, Vec<u32>, u32), (Vec<SurfaceVertex>, Vec<u32>, u32, Vec<Quad>))) {
-        let ((vertices, indices, ilen), (vertices_transparent, indices_transparent, ilen_t, quads)) = (data.0, data.1);
-
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("Chunk Vertex Buffer")),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("Chunk Index Buffer")),
-            contents: bytemuck::cast_slice(&indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+    pub fn finalize_mesh(&mut self, x: i32, z: i32, slice: u32, device: &wgpu::Device, queue: &wgpu::Queue, data: ((Vec<SurfaceVertex>, Vec<u32>, u32, SliceConnectivity), (Vec<SurfaceVertex>, Vec<u32>, u32, Vec<Quad>))) {
+        let ((vertices, _, _, connectivity), (vertices_transparent, _, _, quads)) = (data.0, data.1);
 
-        let vertex_buffer_t = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("Chunk Vertex Buffer Transparent")),
-            contents: bytemuck::cast_slice(&vertices_transparent),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-
-        let index_buffer_t = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("Chunk Index Buffer Transparent")),
-            contents: bytemuck::cast_slice(&indices_transparent),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+        let solid = self.mesh_pool.upload(device, queue, &vertices);
+        let transparent = self.mesh_pool.upload(device, queue, &vertices_transparent);
 
         let chunk = self.chunk_buffers.get_mut(&xz_to_index(x, z)).unwrap();
 
-        chunk.set_solid_buffer(slice, (vertex_buffer, index_buffer, ilen));
-        
-        chunk.set_transparent_buffer(slice, (vertex_buffer_t, index_buffer_t, ilen_t));
+        chunk.set_slice_meshes(slice, &mut self.mesh_pool, solid, transparent);
 
         let actual_chunk = Arc::make_mut(self.chunks.get_mut(&xz_to_index(x, z)).unwrap());
         actual_chunk.states[slice as usize] = ChunkState::Ready;
-        actual_chunk.transparent_quads[slice as usize] = quads;
+        actual_chunk.transparent_quads[slice as usize] = Arc::new(quads);
+        actual_chunk.connectivity[slice as usize] = connectivity;
 
     }
 
@@ -0,0 +1,301 @@
+use std::{collections::HashMap, mem, sync::{mpsc::Sender, Arc}};
+
+use cgmath::{Vector2, Vector3};
+use noise::OpenSimplex;
+use wgpu::util::DeviceExt;
+
+use crate::{engine::{texture_loader::get_texture_set, vertex::Vertex}, internal::frustum::Frustum};
+
+use super::{chunk::{xz_to_index, Chunk, ChunkState}, worldgen::generate_surface_height};
+
+/**
+ lod tiles cover this many chunks along x and z
+*/
+pub const LOD_TILE_CHUNKS: i32 = 8;
+const LOD_TILE_BLOCKS: i32 = LOD_TILE_CHUNKS * 16;
+
+//(tiles closer than this many chunks, column width in blocks)
+const LOD_RINGS: [(u32, u32); 4] = [(16, 2), (48, 4), (96, 8), (u32::MAX, 16)];
+
+pub fn lod_step_for_distance(distance_chunks: u32) -> u32 {
+    LOD_RINGS.iter().find(|(max, _)| distance_chunks < *max).unwrap().1
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LodVertex {
+    pub position: [f32; 3],
+    //3 bits face (same order as SurfaceVertex), 8 bits diffuse texture from bit 8
+    pub data: u32
+}
+
+impl Vertex for LodVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<LodVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Uint32,
+                }
+            ]
+        }
+    }
+}
+
+fn push_quad(vertices: &mut Vec<LodVertex>, corners: [[f32; 3]; 4], face: u32, texture: usize) {
+    let data = face | ((texture as u32) << 8);
+    for i in [0, 1, 2, 0, 2, 3] {
+        vertices.push(LodVertex { position: corners[i], data });
+    }
+}
+
+/**
+ downsampled voxel columns for one tile, sampled from the surface height at the centre of every step x step column.
+ only the top and the exposed sides of each column are built, textured like the blocks the real terrain would have there
+*/
+pub fn build_lod_tile_mesh(noisegen: OpenSimplex, tile_x: i32, tile_z: i32, step: u32) -> Vec<LodVertex> {
+    let step = step as i32;
+    let cells = LOD_TILE_BLOCKS / step;
+    let origin = Vector2::new(tile_x * LOD_TILE_BLOCKS, tile_z * LOD_TILE_BLOCKS);
+
+    //one extra column on each side so the edges know their neighbours
+    let width = (cells + 2) as usize;
+    let mut heights = vec![0i32; width * width];
+    for i in 0..width {
+        for j in 0..width {
+            let x = origin.x + (i as i32 - 1) * step + step / 2;
+            let z = origin.y + (j as i32 - 1) * step + step / 2;
+            heights[i + j * width] = generate_surface_height(noisegen, x, z);
+        }
+    }
+    let height_at = |i: i32, j: i32| heights[(i + 1) as usize + (j + 1) as usize * width];
+
+    let grass_top = get_texture_set("grass-top").0;
+    let grass_side = get_texture_set("grass-side").0;
+    let dirt = get_texture_set("dirt").0;
+    let stone = get_texture_set("stone").0;
+
+    let mut vertices = Vec::new();
+
+    for i in 0..cells {
+        for j in 0..cells {
+            let h = height_at(i, j);
+            let top = (h + 1) as f32;
+            let x0 = (origin.x + i * step) as f32;
+            let z0 = (origin.y + j * step) as f32;
+            let (x1, z1) = (x0 + step as f32, z0 + step as f32);
+
+            //chunk.rs puts grass on the surface below 160 and stone above
+            let grassy = h < 160;
+
+            push_quad(&mut vertices, [[x0, top, z0], [x1, top, z0], [x1, top, z1], [x0, top, z1]], 0, if grassy {grass_top} else {stone});
+
+            //(neighbour offset, face, the two corners of the shared edge)
+            let sides = [
+                ((1, 0), 2u32, [x1, z0], [x1, z1]),
+                ((-1, 0), 3u32, [x0, z1], [x0, z0]),
+                ((0, 1), 5u32, [x1, z1], [x0, z1]),
+                ((0, -1), 4u32, [x0, z0], [x1, z0])
+            ];
+
+            for ((di, dj), face, a, b) in sides {
+                let neighbour = height_at(i + di, j + dj);
+                if neighbour >= h {continue}
+
+                let bottom = (neighbour + 1) as f32;
+
+                //the top block shows its grass side, everything under it dirt or stone like the real columns
+                let split = if grassy {top - 1.0} else {top};
+                if grassy {
+                    push_quad(&mut vertices, [[a[0], split.max(bottom), a[1]], [b[0], split.max(bottom), b[1]], [b[0], top, b[1]], [a[0], top, a[1]]], face, grass_side);
+                }
+                if split > bottom {
+                    let fill = if h < 100 {dirt} else {stone};
+                    push_quad(&mut vertices, [[a[0], bottom, a[1]], [b[0], bottom, b[1]], [b[0], split, b[1]], [a[0], split, a[1]]], face, fill);
+                }
+            }
+        }
+    }
+
+    vertices
+}
+
+struct LodTile {
+    vertex_buffer: Option<wgpu::Buffer>,
+    vertex_count: u32,
+    ready: bool
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct LodGlobals {
+    mask_origin: [i32; 2],
+    mask_width: u32,
+    _padding: u32
+}
+
+/**
+ low detail terrain out to lod_distance chunks. tiles are drawn everywhere, the shader drops the parts over chunks
+ whose full meshes are ready, so they take over as they come into range
+*/
+pub struct LodTerrain {
+    pub lod_distance: u32,
+    tiles: HashMap<(i32, i32), LodTile>,
+    mask_radius: i32,
+    mask_buffer: wgpu::Buffer,
+    pub bindgroup: wgpu::BindGroup
+}
+
+impl LodTerrain {
+    pub fn new(device: &wgpu::Device, lod_distance: u32, render_distance: u32) -> Self {
+        let mask_radius = render_distance as i32;
+        let mask_width = (mask_radius * 2 + 1) as u32;
+
+        let globals_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("LOD Globals Buffer"),
+            contents: bytemuck::cast_slice(&[LodGlobals {
+                mask_origin: [-mask_radius, -mask_radius],
+                mask_width,
+                _padding: 0
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM
+        });
+
+        let mask_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("LOD Ready Mask Buffer"),
+            size: (mask_width * mask_width) as u64 * mem::size_of::<u32>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let bindgroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("lod bind group"),
+            layout: &create_lod_bindgroup_layout(device),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: globals_buffer.as_entire_binding()
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: mask_buffer.as_entire_binding()
+                }
+            ]
+        });
+
+        Self {
+            lod_distance,
+            tiles: HashMap::new(),
+            mask_radius,
+            mask_buffer,
+            bindgroup
+        }
+    }
+
+    /**
+     furthest point the lod can reach, for the camera's far plane
+    */
+    pub fn view_distance(&self) -> f32 {
+        ((self.lod_distance as i32 + LOD_TILE_CHUNKS) * 16) as f32 * std::f32::consts::SQRT_2
+    }
+
+    /**
+     queues every tile within lod_distance of origin (in chunks), nearest first
+    */
+    pub fn request_tiles(&mut self, send_queue: &Sender<(i32, i32, u32)>, origin: Vector2<i32>) {
+        let radius = (self.lod_distance as i32).div_euclid(LOD_TILE_CHUNKS) + 1;
+        let centre = Vector2::new(origin.x.div_euclid(LOD_TILE_CHUNKS), origin.y.div_euclid(LOD_TILE_CHUNKS));
+
+        let mut tiles = (-radius..=radius).flat_map(|x| (-radius..=radius).map(move |z| Vector2::new(centre.x + x, centre.y + z))).collect::<Vec<_>>();
+        tiles.sort_by_key(|t| (t.x - centre.x).pow(2) + (t.y - centre.y).pow(2));
+
+        for tile in tiles {
+            //distance from the origin chunk to the nearest chunk of the tile
+            let dx = (tile.x * LOD_TILE_CHUNKS - origin.x).max(origin.x - (tile.x + 1) * LOD_TILE_CHUNKS + 1).max(0);
+            let dz = (tile.y * LOD_TILE_CHUNKS - origin.y).max(origin.y - (tile.y + 1) * LOD_TILE_CHUNKS + 1).max(0);
+            let distance = ((dx * dx + dz * dz) as f32).sqrt() as u32;
+
+            if distance > self.lod_distance {continue}
+
+            self.tiles.insert((tile.x, tile.y), LodTile { vertex_buffer: None, vertex_count: 0, ready: false });
+            send_queue.send((tile.x, tile.y, lod_step_for_distance(distance))).unwrap();
+        }
+    }
+
+    pub fn finalize_tile(&mut self, device: &wgpu::Device, x: i32, z: i32, vertices: Vec<LodVertex>) {
+        let Some(tile) = self.tiles.get_mut(&(x, z)) else {return};
+
+        tile.vertex_count = vertices.len() as u32;
+        tile.vertex_buffer = (!vertices.is_empty()).then(|| device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("LOD Tile Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX
+        }));
+        tile.ready = true;
+    }
+
+    pub fn all_tiles_ready(&self) -> bool {
+        self.tiles.values().all(|t| t.ready)
+    }
+
+    /**
+     marks the chunks around the origin whose slices are all meshed
+    */
+    pub fn update_ready_mask(&self, queue: &wgpu::Queue, chunks: &HashMap<u32, Arc<Chunk>>) {
+        let mask = (-self.mask_radius..=self.mask_radius).flat_map(|z| (-self.mask_radius..=self.mask_radius).map(move |x| (x, z))).map(|(x, z)| {
+            chunks.get(&xz_to_index(x, z)).map_or(0u32, |c| c.states.iter().all(|s| *s == ChunkState::Ready) as u32)
+        }).collect::<Vec<u32>>();
+
+        queue.write_buffer(&self.mask_buffer, 0, bytemuck::cast_slice(&mask));
+    }
+
+    /**
+     vertex buffers of finished tiles in view
+    */
+    pub fn visible_tiles<'a>(&'a self, frustum: &'a Frustum) -> impl Iterator<Item = (&'a wgpu::Buffer, u32)> + 'a {
+        self.tiles.iter().filter_map(move |((x, z), tile)| {
+            let buffer = tile.vertex_buffer.as_ref()?;
+
+            let min = Vector3::new((x * LOD_TILE_BLOCKS) as f32, 0.0, (z * LOD_TILE_BLOCKS) as f32);
+            let max = min + Vector3::new(LOD_TILE_BLOCKS as f32, 257.0, LOD_TILE_BLOCKS as f32);
+
+            frustum.intersects_aabb(min, max).then_some((buffer, tile.vertex_count))
+        })
+    }
+}
+
+pub fn create_lod_bindgroup_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("lod bind group layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None
+            }
+        ]
+    })
+}
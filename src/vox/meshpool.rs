@@ -0,0 +1,309 @@
+use std::mem::size_of;
+
+use cgmath::Vector3;
+use wgpu::util::DeviceExt;
+
+use crate::engine::surfacevertex::SurfaceVertex;
+
+use super::binarymesher::generate_indices;
+
+const INITIAL_VERTEX_CAPACITY: u32 = 1 << 20;
+const INITIAL_QUAD_CAPACITY: u32 = 1 << 14;
+const INITIAL_SLICE_CAPACITY: u32 = 2048;
+
+/**
+ how chunk slices are submitted. indirect draws need first_instance to pick the slice's entry in the storage buffer
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliceDrawMode {
+    MultiDrawIndirect,
+    Indirect,
+    Direct
+}
+
+impl SliceDrawMode {
+    pub fn detect(adapter: &wgpu::Adapter) -> Self {
+        let features = adapter.features();
+        let first_instance_works = features.contains(wgpu::Features::INDIRECT_FIRST_INSTANCE) &&
+            adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::VERTEX_AND_INSTANCE_INDEX_RESPECTS_RESPECTIVE_FIRST_VALUE_IN_INDIRECT_DRAW);
+
+        if !first_instance_works {
+            SliceDrawMode::Direct
+        }
+        else if features.contains(wgpu::Features::MULTI_DRAW_INDIRECT) {
+            SliceDrawMode::MultiDrawIndirect
+        }
+        else {
+            SliceDrawMode::Indirect
+        }
+    }
+
+    pub fn required_features(&self) -> wgpu::Features {
+        match self {
+            SliceDrawMode::MultiDrawIndirect => wgpu::Features::INDIRECT_FIRST_INSTANCE | wgpu::Features::MULTI_DRAW_INDIRECT,
+            SliceDrawMode::Indirect => wgpu::Features::INDIRECT_FIRST_INSTANCE,
+            SliceDrawMode::Direct => wgpu::Features::empty()
+        }
+    }
+}
+
+/**
+ a run of vertices in the pool's vertex arena. every 4 vertices are one quad, drawn with the shared quad index buffer
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshAllocation {
+    pub offset: u32,
+    pub vertex_count: u32
+}
+
+impl MeshAllocation {
+    pub fn index_count(&self) -> u32 {
+        self.vertex_count / 4 * 6
+    }
+}
+
+/**
+ first fit over a sorted list of free (start, length) ranges, neighbours are merged when freed
+*/
+struct RangeAllocator {
+    capacity: u32,
+    free: Vec<(u32, u32)>
+}
+
+impl RangeAllocator {
+    fn new(capacity: u32) -> Self {
+        Self {
+            capacity,
+            free: vec![(0, capacity)]
+        }
+    }
+
+    fn allocate(&mut self, length: u32) -> Option<u32> {
+        let i = self.free.iter().position(|(_, free_length)| *free_length >= length)?;
+        let (start, free_length) = self.free[i];
+
+        if free_length == length {
+            self.free.remove(i);
+        }
+        else {
+            self.free[i] = (start + length, free_length - length);
+        }
+
+        Some(start)
+    }
+
+    fn free(&mut self, start: u32, length: u32) {
+        let i = self.free.partition_point(|(s, _)| *s < start);
+        self.free.insert(i, (start, length));
+
+        if i + 1 < self.free.len() && self.free[i].0 + self.free[i].1 == self.free[i + 1].0 {
+            self.free[i].1 += self.free[i + 1].1;
+            self.free.remove(i + 1);
+        }
+        if i > 0 && self.free[i - 1].0 + self.free[i - 1].1 == self.free[i].0 {
+            self.free[i - 1].1 += self.free[i].1;
+            self.free.remove(i);
+        }
+    }
+
+    fn grow(&mut self, capacity: u32) {
+        let added = capacity - self.capacity;
+        self.free(self.capacity, added);
+        self.capacity = capacity;
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct RawSliceData {
+    position_sliced: [i32; 4]
+}
+
+/**
+ every chunk mesh lives in one vertex arena instead of its own buffers. slices get a slot in a storage buffer
+ holding their position, the vertex shader finds it through instance_index
+*/
+pub struct MeshPool {
+    vertex_buffer: wgpu::Buffer,
+    vertices: RangeAllocator,
+    index_buffer: wgpu::Buffer,
+    quad_capacity: u32,
+    slice_buffer: wgpu::Buffer,
+    slice_capacity: u32,
+    slots: RangeAllocator,
+    pub bindgroup_layout: wgpu::BindGroupLayout,
+    pub bindgroup: wgpu::BindGroup
+}
+
+impl MeshPool {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bindgroup_layout = create_mesh_pool_bindgroup_layout(device);
+
+        let vertex_buffer = create_vertex_arena(device, INITIAL_VERTEX_CAPACITY);
+        let index_buffer = create_quad_index_buffer(device, INITIAL_QUAD_CAPACITY);
+        let slice_buffer = create_slice_buffer(device, INITIAL_SLICE_CAPACITY);
+        let bindgroup = create_slice_bindgroup(device, &bindgroup_layout, &slice_buffer);
+
+        Self {
+            vertex_buffer,
+            vertices: RangeAllocator::new(INITIAL_VERTEX_CAPACITY),
+            index_buffer,
+            quad_capacity: INITIAL_QUAD_CAPACITY,
+            slice_buffer,
+            slice_capacity: INITIAL_SLICE_CAPACITY,
+            slots: RangeAllocator::new(INITIAL_SLICE_CAPACITY),
+            bindgroup_layout,
+            bindgroup
+        }
+    }
+
+    pub fn vertex_buffer(&self) -> &wgpu::Buffer {
+        &self.vertex_buffer
+    }
+
+    pub fn index_buffer(&self) -> &wgpu::Buffer {
+        &self.index_buffer
+    }
+
+    /**
+     reserves a slot for a slice at position (in chunk/slice units), the slot is the instance index its draws use
+    */
+    pub fn allocate_slot(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, position: Vector3<i32>) -> u32 {
+        let slot = match self.slots.allocate(1) {
+            Some(slot) => slot,
+            None => {
+                let capacity = self.slice_capacity * 2;
+                let buffer = create_slice_buffer(device, capacity);
+                copy_buffer(device, queue, &self.slice_buffer, &buffer, self.slice_capacity as u64 * size_of::<RawSliceData>() as u64);
+
+                self.bindgroup = create_slice_bindgroup(device, &self.bindgroup_layout, &buffer);
+                self.slice_buffer = buffer;
+                self.slice_capacity = capacity;
+                self.slots.grow(capacity);
+                self.slots.allocate(1).unwrap()
+            }
+        };
+
+        queue.write_buffer(
+            &self.slice_buffer,
+            slot as u64 * size_of::<RawSliceData>() as u64,
+            bytemuck::cast_slice(&[RawSliceData { position_sliced: [position.x, position.y, position.z, 0] }])
+        );
+
+        slot
+    }
+
+    /**
+     copies vertices into the arena, growing it if there's no free run large enough. empty meshes don't take space
+    */
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, vertices: &[SurfaceVertex]) -> Option<MeshAllocation> {
+        if vertices.is_empty() {
+            return None;
+        }
+
+        let vertex_count = vertices.len() as u32;
+
+        let offset = match self.vertices.allocate(vertex_count) {
+            Some(offset) => offset,
+            None => {
+                let capacity = (self.vertices.capacity + vertex_count).next_power_of_two();
+                let buffer = create_vertex_arena(device, capacity);
+                copy_buffer(device, queue, &self.vertex_buffer, &buffer, self.vertices.capacity as u64 * size_of::<SurfaceVertex>() as u64);
+
+                self.vertex_buffer = buffer;
+                self.vertices.grow(capacity);
+                self.vertices.allocate(vertex_count).unwrap()
+            }
+        };
+
+        let quads = vertex_count / 4;
+        if quads > self.quad_capacity {
+            self.quad_capacity = quads.next_power_of_two();
+            self.index_buffer = create_quad_index_buffer(device, self.quad_capacity);
+        }
+
+        queue.write_buffer(&self.vertex_buffer, offset as u64 * size_of::<SurfaceVertex>() as u64, bytemuck::cast_slice(vertices));
+
+        Some(MeshAllocation { offset, vertex_count })
+    }
+
+    /**
+     overwrites an allocation in place, for reordering its quads without moving them
+    */
+    pub fn write(&self, queue: &wgpu::Queue, allocation: MeshAllocation, vertices: &[SurfaceVertex]) {
+        debug_assert_eq!(allocation.vertex_count as usize, vertices.len());
+        queue.write_buffer(&self.vertex_buffer, allocation.offset as u64 * size_of::<SurfaceVertex>() as u64, bytemuck::cast_slice(vertices));
+    }
+
+    pub fn free(&mut self, allocation: MeshAllocation) {
+        self.vertices.free(allocation.offset, allocation.vertex_count);
+    }
+}
+
+/**
+ layout of MeshPool::bindgroup, for pipelines drawing chunk slices
+*/
+pub fn create_mesh_pool_bindgroup_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("mesh pool bind group layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None
+            }
+        ]
+    })
+}
+
+fn create_vertex_arena(device: &wgpu::Device, capacity: u32) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Chunk Vertex Arena"),
+        size: capacity as u64 * size_of::<SurfaceVertex>() as u64,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false
+    })
+}
+
+fn create_quad_index_buffer(device: &wgpu::Device, quads: u32) -> wgpu::Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Chunk Quad Index Buffer"),
+        contents: bytemuck::cast_slice(&generate_indices(quads as usize * 4)),
+        usage: wgpu::BufferUsages::INDEX
+    })
+}
+
+fn create_slice_buffer(device: &wgpu::Device, capacity: u32) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Chunk Slice Data Buffer"),
+        size: capacity as u64 * size_of::<RawSliceData>() as u64,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false
+    })
+}
+
+fn create_slice_bindgroup(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, buffer: &wgpu::Buffer) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("mesh pool bind group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding()
+            }
+        ]
+    })
+}
+
+fn copy_buffer(device: &wgpu::Device, queue: &wgpu::Queue, from: &wgpu::Buffer, to: &wgpu::Buffer, size: u64) {
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Mesh Pool Grow Encoder")
+    });
+    encoder.copy_buffer_to_buffer(from, 0, to, 0, size);
+    queue.submit(std::iter::once(encoder.finish()));
+}
@@ -4,4 +4,10 @@ pub mod chunk_manager;
 pub mod chunkactionqueue;
 pub mod structure_loader;
 pub mod worker_threads;
-pub mod binarymesher;
\ No newline at end of file
+pub mod binarymesher;
+pub mod nbt;
+pub mod schematic_importer;
+pub mod structure_exporter;
+pub mod visibility;
+pub mod meshpool;
+pub mod lod;
\ No newline at end of file
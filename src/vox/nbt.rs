@@ -0,0 +1,202 @@
+use std::{collections::HashMap, io::Read};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NbtTag {
+    End,
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<NbtTag>),
+    Compound(HashMap<String, NbtTag>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>)
+}
+
+impl NbtTag {
+    pub fn get(&self, name: &str) -> Option<&NbtTag> {
+        match self {
+            NbtTag::Compound(map) => map.get(name),
+            _ => None
+        }
+    }
+
+    /**
+     integer tags of any width, widened to i64
+    */
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            NbtTag::Byte(v) => Some(*v as i64),
+            NbtTag::Short(v) => Some(*v as i64),
+            NbtTag::Int(v) => Some(*v as i64),
+            NbtTag::Long(v) => Some(*v),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            NbtTag::String(v) => Some(v),
+            _ => None
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&Vec<NbtTag>> {
+        match self {
+            NbtTag::List(v) => Some(v),
+            _ => None
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&HashMap<String, NbtTag>> {
+        match self {
+            NbtTag::Compound(v) => Some(v),
+            _ => None
+        }
+    }
+
+    pub fn as_byte_array(&self) -> Option<&Vec<i8>> {
+        match self {
+            NbtTag::ByteArray(v) => Some(v),
+            _ => None
+        }
+    }
+}
+
+struct NbtReader<'a> {
+    data: &'a [u8],
+    cursor: usize
+}
+
+impl<'a> NbtReader<'a> {
+    fn take(&mut self, n: usize) -> anyhow::Result<&'a [u8]> {
+        if self.cursor + n > self.data.len() {
+            anyhow::bail!("Unexpected end of nbt data at byte {}", self.cursor);
+        }
+        let slice = &self.data[self.cursor..self.cursor + n];
+        self.cursor += n;
+        Ok(slice)
+    }
+
+    fn read_u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_i16(&mut self) -> anyhow::Result<i16> {
+        Ok(i16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn read_i32(&mut self) -> anyhow::Result<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn read_i64(&mut self) -> anyhow::Result<i64> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn read_length(&mut self) -> anyhow::Result<usize> {
+        let len = self.read_i32()?;
+        //negative lengths show up in empty lists written by some tools
+        Ok(len.max(0) as usize)
+    }
+
+    fn read_string(&mut self) -> anyhow::Result<String> {
+        let len = self.read_i16()? as u16 as usize;
+        //java's modified utf-8 only differs for nulls and supplementary characters, which block names never contain
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn read_payload(&mut self, tag_type: u8) -> anyhow::Result<NbtTag> {
+        Ok(match tag_type {
+            0 => NbtTag::End,
+            1 => NbtTag::Byte(self.read_u8()? as i8),
+            2 => NbtTag::Short(self.read_i16()?),
+            3 => NbtTag::Int(self.read_i32()?),
+            4 => NbtTag::Long(self.read_i64()?),
+            5 => NbtTag::Float(f32::from_bits(self.read_i32()? as u32)),
+            6 => NbtTag::Double(f64::from_bits(self.read_i64()? as u64)),
+            7 => {
+                let len = self.read_length()?;
+                NbtTag::ByteArray(self.take(len)?.iter().map(|v| *v as i8).collect())
+            },
+            8 => NbtTag::String(self.read_string()?),
+            9 => {
+                let element_type = self.read_u8()?;
+                let len = self.read_length()?;
+                let mut list = Vec::with_capacity(len);
+                for _ in 0..len {
+                    list.push(self.read_payload(element_type)?);
+                }
+                NbtTag::List(list)
+            },
+            10 => {
+                let mut map = HashMap::new();
+                loop {
+                    let child_type = self.read_u8()?;
+                    if child_type == 0 {break};
+                    let name = self.read_string()?;
+                    map.insert(name, self.read_payload(child_type)?);
+                }
+                NbtTag::Compound(map)
+            },
+            11 => {
+                let len = self.read_length()?;
+                let mut list = Vec::with_capacity(len);
+                for _ in 0..len {
+                    list.push(self.read_i32()?);
+                }
+                NbtTag::IntArray(list)
+            },
+            12 => {
+                let len = self.read_length()?;
+                let mut list = Vec::with_capacity(len);
+                for _ in 0..len {
+                    list.push(self.read_i64()?);
+                }
+                NbtTag::LongArray(list)
+            },
+            _ => anyhow::bail!("Unknown nbt tag type {} at byte {}", tag_type, self.cursor - 1)
+        })
+    }
+}
+
+/**
+ reads a named root tag from raw, gzipped or zlib compressed nbt bytes
+*/
+pub fn read_nbt(bytes: &[u8]) -> anyhow::Result<(String, NbtTag)> {
+    let mut decompressed: Vec<u8> = Vec::new();
+
+    let data = if bytes.starts_with(&[0x1f, 0x8b]) {
+        GzDecoder::new(bytes).read_to_end(&mut decompressed)?;
+        &decompressed[..]
+    }
+    else if bytes.first() == Some(&0x78) {
+        ZlibDecoder::new(bytes).read_to_end(&mut decompressed)?;
+        &decompressed[..]
+    }
+    else {
+        bytes
+    };
+
+    let mut reader = NbtReader {
+        data,
+        cursor: 0
+    };
+
+    let root_type = reader.read_u8()?;
+
+    if root_type != 10 {
+        anyhow::bail!("Nbt root must be a compound, found tag type {}", root_type);
+    }
+
+    let name = reader.read_string()?;
+    let root = reader.read_payload(root_type)?;
+
+    Ok((name, root))
+}
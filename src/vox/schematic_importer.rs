@@ -0,0 +1,226 @@
+use std::{collections::{HashMap, HashSet}, path::Path};
+
+use crate::blocks::block::Blocks;
+
+use super::{nbt::{read_nbt, NbtTag}, structure_loader::StructureData};
+
+//biggest structure we import, anything above is most likely a broken or malicious file
+pub const MAX_STRUCTURE_VOLUME: u64 = 256 * 256 * 256;
+
+/**
+ maps namespaced block ids ("minecraft:oak_log") to our own blocks.
+ block states ("[axis=y]") are stripped before the lookup
+*/
+pub struct BlockNameMapping {
+    names: HashMap<String, Blocks>,
+    missing: HashSet<String>
+}
+
+impl BlockNameMapping {
+    pub fn new(names: HashMap<String, Blocks>) -> Self {
+        Self {
+            names,
+            missing: HashSet::new()
+        }
+    }
+
+    pub fn resolve(&mut self, name: &str) -> Blocks {
+        let stripped = name.split('[').next().unwrap_or(name).trim();
+
+        let namespaced = if stripped.contains(':') {stripped.to_owned()} else {format!("minecraft:{}", stripped)};
+
+        match self.names.get(&namespaced) {
+            Some(block) => *block,
+            None => {
+                if self.missing.insert(namespaced.clone()) {
+                    println!("No block mapping for {}, it will be imported as air", namespaced);
+                }
+                Blocks::AIR
+            }
+        }
+    }
+}
+
+/**
+ turns a dense x/y/z grid into StructureData with a deduplicated block palette.
+ schematic layers are stored top down, the same way the hand written struct files are
+*/
+pub fn build_structure_data<F>(widthx: u32, height: u32, widthz: u32, mut block_at: F) -> StructureData where F: FnMut(u32, u32, u32) -> Blocks {
+    let mut blocks: Vec<Blocks> = vec![Blocks::AIR];
+
+    let schematic = (0..height).rev().map(|y| {
+        let mut layer = Vec::with_capacity((widthx * widthz) as usize);
+        for z in 0..widthz {
+            for x in 0..widthx {
+                let block = block_at(x, y, z);
+                let index = match blocks.iter().position(|b| *b == block) {
+                    Some(i) => i,
+                    None => {
+                        blocks.push(block);
+                        blocks.len() - 1
+                    }
+                };
+                layer.push(index as u32);
+            }
+        }
+        layer
+    }).collect::<Vec<Vec<u32>>>();
+
+    StructureData {
+        blocks,
+        widthx,
+        widthz,
+        height,
+        schematic
+    }
+}
+
+fn read_varints(data: &[i8]) -> anyhow::Result<Vec<u32>> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut value: u32 = 0;
+    let mut shift = 0;
+
+    for byte in data {
+        let byte = *byte as u8;
+        value |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            out.push(value);
+            value = 0;
+            shift = 0;
+        }
+        else {
+            shift += 7;
+            if shift > 28 {
+                anyhow::bail!("Varint in schematic block data is too long");
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn nbt_dimension(tag: &NbtTag, name: &str) -> anyhow::Result<u32> {
+    let value = tag.get(name).and_then(|v| v.as_i64()).ok_or_else(|| anyhow::anyhow!("Schematic is missing {}", name))?;
+    //widths are stored as signed shorts, so anything above 32767 wraps
+    Ok(value as u16 as u32)
+}
+
+/**
+ blocks in a structure of the given size, an error if it's empty or too big to import
+*/
+pub fn structure_volume(widthx: u32, height: u32, widthz: u32) -> anyhow::Result<usize> {
+    let volume = (widthx as u64).checked_mul(height as u64).and_then(|v| v.checked_mul(widthz as u64))
+        .ok_or_else(|| anyhow::anyhow!("Structure size {}x{}x{} is too big", widthx, height, widthz))?;
+
+    if volume == 0 || volume > MAX_STRUCTURE_VOLUME {
+        anyhow::bail!("Structure size {}x{}x{} must hold between 1 and {} blocks", widthx, height, widthz, MAX_STRUCTURE_VOLUME);
+    }
+
+    Ok(volume as usize)
+}
+
+/**
+ sponge schematic versions 1 through 3 (.schem)
+*/
+pub fn import_sponge_schematic(root: &NbtTag, mapping: &mut BlockNameMapping) -> anyhow::Result<StructureData> {
+    //version 3 nests everything inside of a "Schematic" compound
+    let schematic = root.get("Schematic").unwrap_or(root);
+
+    let widthx = nbt_dimension(schematic, "Width")?;
+    let height = nbt_dimension(schematic, "Height")?;
+    let widthz = nbt_dimension(schematic, "Length")?;
+    let volume = structure_volume(widthx, height, widthz)?;
+
+    let container = schematic.get("Blocks").unwrap_or(schematic);
+
+    let palette = container.get("Palette").and_then(|v| v.as_compound())
+        .ok_or_else(|| anyhow::anyhow!("Schematic has no block palette"))?;
+
+    let data = container.get("BlockData").or_else(|| container.get("Data")).and_then(|v| v.as_byte_array())
+        .ok_or_else(|| anyhow::anyhow!("Schematic has no block data"))?;
+
+    let mut palette_blocks: HashMap<u32, Blocks> = HashMap::new();
+
+    for (name, id) in palette {
+        let id = id.as_i64().ok_or_else(|| anyhow::anyhow!("Palette entry {} is not an integer", name))?;
+        palette_blocks.insert(id as u32, mapping.resolve(name));
+    }
+
+    let indices = read_varints(data)?;
+
+    if indices.len() != volume {
+        anyhow::bail!("Schematic block data has {} entries, expected {}", indices.len(), volume);
+    }
+
+    Ok(build_structure_data(widthx, height, widthz, |x, y, z| {
+        let id = indices[((y * widthz + z) * widthx + x) as usize];
+        *palette_blocks.get(&id).unwrap_or(&Blocks::AIR)
+    }))
+}
+
+/**
+ vanilla structure block files (.nbt)
+*/
+pub fn import_vanilla_structure(root: &NbtTag, mapping: &mut BlockNameMapping) -> anyhow::Result<StructureData> {
+    let size = root.get("size").and_then(|v| v.as_list())
+        .ok_or_else(|| anyhow::anyhow!("Structure has no size"))?
+        .iter().filter_map(|v| v.as_i64()).collect::<Vec<i64>>();
+
+    if size.len() != 3 {
+        anyhow::bail!("Structure size must have 3 components");
+    }
+
+    let dimension = |v: i64| u32::try_from(v).map_err(|_| anyhow::anyhow!("Structure size {:?} is negative or too big", size));
+    let (widthx, height, widthz) = (dimension(size[0])?, dimension(size[1])?, dimension(size[2])?);
+    let volume = structure_volume(widthx, height, widthz)?;
+
+    //files with several palettes (shipwrecks etc.) use "palettes", we only import the first one
+    let palette = match root.get("palette").and_then(|v| v.as_list()) {
+        Some(p) => p,
+        None => root.get("palettes").and_then(|v| v.as_list()).and_then(|v| v.first()).and_then(|v| v.as_list())
+            .ok_or_else(|| anyhow::anyhow!("Structure has no palette"))?
+    };
+
+    let palette_blocks = palette.iter().map(|state| {
+        let name = state.get("Name").and_then(|v| v.as_str()).unwrap_or("minecraft:air");
+        mapping.resolve(name)
+    }).collect::<Vec<Blocks>>();
+
+    let mut grid = vec![Blocks::AIR; volume];
+
+    for block in root.get("blocks").and_then(|v| v.as_list()).ok_or_else(|| anyhow::anyhow!("Structure has no blocks"))? {
+        let state = block.get("state").and_then(|v| v.as_i64()).ok_or_else(|| anyhow::anyhow!("Structure block has no state"))?;
+        let pos = block.get("pos").and_then(|v| v.as_list()).ok_or_else(|| anyhow::anyhow!("Structure block has no pos"))?
+            .iter().filter_map(|v| v.as_i64()).collect::<Vec<i64>>();
+
+        if pos.len() != 3 || pos.iter().any(|v| *v < 0) || pos[0] >= widthx as i64 || pos[1] >= height as i64 || pos[2] >= widthz as i64 {
+            anyhow::bail!("Structure block position {:?} is outside of the structure", pos);
+        }
+
+        grid[((pos[1] * widthz as i64 + pos[2]) * widthx as i64 + pos[0]) as usize] = *palette_blocks.get(state as usize).unwrap_or(&Blocks::AIR);
+    }
+
+    Ok(build_structure_data(widthx, height, widthz, |x, y, z| {
+        grid[((y * widthz + z) * widthx + x) as usize]
+    }))
+}
+
+/**
+ picks the importer based on the file extension and converts the file into StructureData
+*/
+pub fn import_structure_file(path: &Path, bytes: &[u8], mapping: &mut BlockNameMapping) -> anyhow::Result<StructureData> {
+    let extension = path.extension().and_then(|v| v.to_str()).unwrap_or("").to_lowercase();
+
+    //the old mcedit format stores numeric block ids instead of a palette
+    if extension == "schematic" {
+        anyhow::bail!("{} is a legacy MCEdit schematic, only Sponge schematics (.schem) are supported", path.display());
+    }
+
+    let (_, root) = read_nbt(bytes)?;
+
+    match extension.as_str() {
+        "schem" => import_sponge_schematic(&root, mapping),
+        "nbt" => import_vanilla_structure(&root, mapping),
+        _ => anyhow::bail!("{} is not a supported structure format", path.display())
+    }
+}
@@ -0,0 +1,144 @@
+use std::mem;
+
+use cgmath::Vector3;
+
+use crate::{blocks::block::Blocks, engine::{assetfs::{read_asset, write_asset}, vertex::Vertex}};
+
+use super::{chunk_manager::ChunkManager, schematic_importer::{build_structure_data, structure_volume}, structure_loader::{register_structure, StructureData}};
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct OutlineVertex {
+    pub position: [f32; 3]
+}
+
+impl Vertex for OutlineVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<OutlineVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                }
+            ]
+        }
+    }
+}
+
+/**
+ two block corners picked in the world, used for exporting structures
+*/
+pub struct StructureSelection {
+    pub corner_a: Option<Vector3<i32>>,
+    pub corner_b: Option<Vector3<i32>>
+}
+
+impl StructureSelection {
+    pub fn new() -> Self {
+        Self {
+            corner_a: None,
+            corner_b: None
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.corner_a = None;
+        self.corner_b = None;
+    }
+
+    /**
+     inclusive (min, max) block positions, only once both corners are set
+    */
+    pub fn get_bounds(&self) -> Option<(Vector3<i32>, Vector3<i32>)> {
+        match (self.corner_a, self.corner_b) {
+            (Some(a), Some(b)) => Some((
+                Vector3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+                Vector3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z))
+            )),
+            _ => None
+        }
+    }
+
+    /**
+     line list of the selection box edges. with only one corner set the box covers that block
+    */
+    pub fn get_outline_vertices(&self) -> Vec<OutlineVertex> {
+        let (min, max) = match self.get_bounds() {
+            Some(b) => b,
+            None => match self.corner_a.or(self.corner_b) {
+                Some(c) => (c, c),
+                None => return Vec::new()
+            }
+        };
+
+        //pushed out slightly so the lines don't z-fight with the block faces
+        let lo = min.map(|v| v as f32 - 0.005);
+        let hi = max.map(|v| v as f32 + 1.005);
+
+        let corners = [
+            [lo.x, lo.y, lo.z], [hi.x, lo.y, lo.z], [hi.x, lo.y, hi.z], [lo.x, lo.y, hi.z],
+            [lo.x, hi.y, lo.z], [hi.x, hi.y, lo.z], [hi.x, hi.y, hi.z], [lo.x, hi.y, hi.z],
+        ];
+
+        let edges = [
+            (0, 1), (1, 2), (2, 3), (3, 0),
+            (4, 5), (5, 6), (6, 7), (7, 4),
+            (0, 4), (1, 5), (2, 6), (3, 7),
+        ];
+
+        edges.iter().flat_map(|(a, b)| [
+            OutlineVertex { position: corners[*a] },
+            OutlineVertex { position: corners[*b] }
+        ]).collect()
+    }
+}
+
+/**
+ reads the selected blocks out of the loaded chunks. blocks in chunks that aren't loaded are exported as air.
+ selections bigger than the importer accepts are refused
+*/
+pub fn selection_to_structure(selection: &StructureSelection, chunk_manager: &ChunkManager) -> anyhow::Result<StructureData> {
+    let (min, max) = selection.get_bounds().ok_or_else(|| anyhow::anyhow!("Both selection corners must be set before exporting"))?;
+
+    let size = max - min + Vector3::new(1, 1, 1);
+    structure_volume(size.x as u32, size.y as u32, size.z as u32)?;
+
+    Ok(build_structure_data(size.x as u32, size.y as u32, size.z as u32, |x, y, z| {
+        chunk_manager.get_block_at_absolute(min.x + x as i32, min.y + y as i32, min.z + z as i32)
+            .map_or(Blocks::AIR, |b| b.get_block())
+    }))
+}
+
+/**
+ writes a structure read out of a selection to structures/{name}.struct.json of the highest priority writable pack and registers it in the
+ structure manifest. does disk io, so don't hold the workspace while calling it
+*/
+pub fn export_structure(data: StructureData, name: &str) -> anyhow::Result<String> {
+    let relative_path = structure_path(name);
+
+    if read_asset(&relative_path).is_ok() {
+        anyhow::bail!("A structure named {} already exists", name);
+    }
+
+    write_asset(&relative_path, serde_json::to_string_pretty(&data)?.as_bytes())?;
+
+    register_structure(name, &relative_path, data)?;
+
+    Ok(relative_path)
+}
+
+fn structure_path(name: &str) -> String {
+    format!("structures/{}.struct.json", name)
+}
+
+/**
+ prefix, or prefix_2, prefix_3... whichever isn't taken by a structure yet
+*/
+pub fn unused_structure_name(prefix: &str) -> String {
+    (1..).map(|i| if i == 1 {prefix.to_owned()} else {format!("{}_{}", prefix, i)})
+        .find(|name| read_asset(&structure_path(name)).is_err())
+        .unwrap()
+}
@@ -1,26 +1,28 @@
-use std::{collections::HashMap, env, fs::File, io::{BufReader, Read}, sync::RwLock};
+use std::{collections::HashMap, path::Path, sync::RwLock};
 
 use cgmath::Vector3;
 use once_cell::sync::Lazy;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::blocks::block::{create_block_default, BlockType, Blocks};
+use crate::{blocks::block::{create_block_default, BlockType, Blocks}, engine::assetfs::{read_asset, write_asset}};
 
-#[derive(Deserialize)]
-struct StructureData {
+use super::schematic_importer::{import_structure_file, BlockNameMapping};
+
+#[derive(Deserialize, Serialize)]
+pub struct StructureData {
     pub blocks: Vec<Blocks>,
     pub widthx: u32,
     pub widthz: u32,
     pub height: u32,
     pub schematic: Vec<Vec<u32>>
 }
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 struct StructurePointerInner {
     weight: f32,
     path: String
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 struct StructurePointer {
     name: String,
     paths: Vec<StructurePointerInner>,
@@ -40,8 +42,8 @@ pub fn get_blocks_for_structure_at_point(structure: &str, variant: usize, positi
     let h = structure.height as i32;
 
     (0..h).flat_map(|y| {
-        (-wz / 2..wz / 2 + 1).flat_map(|z| {
-            (-wx / 2..wx / 2 + 1).map(|x| {
+        (-wz / 2..wz - wz / 2).flat_map(|z| {
+            (-wx / 2..wx - wx / 2).map(|x| {
                 let abs = Vector3::new(x, h - 1- y, z) + position;
 
                 let (rx, rz) = (x + wx / 2, z + wz / 2);
@@ -64,12 +66,12 @@ static LOADED_STRUCTURE_FILES: Lazy<RwLock<HashMap<String, Vec<StructureData>>>>
 });
 
 pub fn load_structures() {
-    let mut dir = env::current_dir().unwrap();
-    dir.push("res/data/structure_manifest.json");
+    let manifest = read_asset("data/structure_manifest.json").expect("Unable to open structure_manifest");
+    let data: Vec<StructurePointer> = serde_json::from_slice(&manifest).expect("Invalid structure_manifest data");
 
-    let file = File::open(dir).expect("Unable to open structure_manifest");
-    let reader = BufReader::new(file);
-    let data: Vec<StructurePointer> = serde_json::from_reader(reader).expect("Invalid structure_manifest data");
+    let mapping_bytes = read_asset("data/block_mapping.json").expect("Unable to open block_mapping");
+    let mapping_data: HashMap<String, Blocks> = serde_json::from_slice(&mapping_bytes).expect("Invalid block_mapping data");
+    let mut mapping = BlockNameMapping::new(mapping_data);
 
     let mut lock = LOADED_STRUCTURE_FILES.write().unwrap();
 
@@ -77,17 +79,47 @@ pub fn load_structures() {
         let mut structs: Vec<StructureData> = Vec::new();
 
         for path in item.paths {
-            let mut dir = env::current_dir().unwrap();
-            dir.push("res\\");
-            dir.push(path.path);
-            let cloned = dir.clone();
-            let as_str = cloned.to_str().unwrap();
-
-            let file = File::open(dir).expect(&format!("Unable to open {}", as_str));
-            let reader = BufReader::new(file);
-            let data: StructureData = serde_json::from_reader(reader).expect(&format!("{} does not have correct formatting", as_str));
+            let as_str = path.path.as_str();
+
+            let bytes = read_asset(as_str).expect(&format!("Unable to open {}", as_str));
+
+            let data: StructureData = if as_str.ends_with(".json") {
+                serde_json::from_slice(&bytes).expect(&format!("{} does not have correct formatting", as_str))
+            }
+            else {
+                import_structure_file(Path::new(as_str), &bytes, &mut mapping).expect(&format!("Unable to import {}", as_str))
+            };
             structs.push(data);
         }
         lock.insert(item.name, structs);
     }
-}
\ No newline at end of file
+}
+
+/**
+ adds a structure to the loaded structures and appends it to structure_manifest.json.
+ if a structure with the same name exists the new one is added as another variant
+*/
+pub fn register_structure(name: &str, path: &str, data: StructureData) -> anyhow::Result<()> {
+    //the manifest the loader sees, which may come from a user pack overriding the base one
+    let mut manifest: Vec<StructurePointer> = serde_json::from_slice(&read_asset("data/structure_manifest.json")?)?;
+
+    let entry = StructurePointerInner {
+        weight: 1.,
+        path: path.to_owned()
+    };
+
+    match manifest.iter_mut().find(|v| v.name == name) {
+        Some(pointer) => pointer.paths.push(entry),
+        None => manifest.push(StructurePointer {
+            name: name.to_owned(),
+            paths: vec![entry],
+            density: 0.
+        })
+    }
+
+    write_asset("data/structure_manifest.json", serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+
+    LOADED_STRUCTURE_FILES.write().unwrap().entry(name.to_owned()).or_default().push(data);
+
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use std::{collections::{HashMap, HashSet, VecDeque}, sync::Arc};
+
+use cgmath::{Point3, Vector3};
+
+use crate::{blocks::block::BlockFace, internal::frustum::Frustum};
+
+use super::chunk::{xz_to_index, Chunk};
+
+const FACES: [BlockFace; 6] = [BlockFace::Left, BlockFace::Right, BlockFace::Bottom, BlockFace::Top, BlockFace::Front, BlockFace::Back];
+
+fn opposite(face: usize) -> usize {
+    //faces come in -/+ pairs when indexed by normal_index
+    face ^ 1
+}
+
+/**
+ which faces of a 16x16x16 slice can see each other through non opaque blocks, one bit per pair of faces (a * 6 + b).
+ defaults to fully connected so slices that aren't meshed yet never hide anything
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SliceConnectivity(u64);
+
+impl Default for SliceConnectivity {
+    fn default() -> Self {
+        Self((1u64 << 36) - 1)
+    }
+}
+
+impl SliceConnectivity {
+    /**
+     opaque holds one bit per x for every (z, y) row. flood fills the open space and connects every pair of faces
+     a single pocket touches
+    */
+    pub fn from_opaque(opaque: &[[u16; 16]; 16]) -> Self {
+        if opaque.iter().flatten().all(|row| *row == 0) {
+            return Self::default();
+        }
+
+        let is_open = |x: usize, y: usize, z: usize| opaque[z][y] & (1 << x) == 0;
+
+        let mut visited = [false; 4096];
+        let mut bits = 0u64;
+        let mut stack = Vec::new();
+
+        for start in 0..4096 {
+            let (x, y, z) = (start % 16, (start / 16) % 16, start / 256);
+            if visited[start] || !is_open(x, y, z) {continue}
+
+            visited[start] = true;
+            stack.push((x, y, z));
+
+            let mut touched = 0u8;
+
+            while let Some((x, y, z)) = stack.pop() {
+                if x == 0 {touched |= 1 << BlockFace::Left.normal_index()}
+                if x == 15 {touched |= 1 << BlockFace::Right.normal_index()}
+                if y == 0 {touched |= 1 << BlockFace::Bottom.normal_index()}
+                if y == 15 {touched |= 1 << BlockFace::Top.normal_index()}
+                if z == 0 {touched |= 1 << BlockFace::Front.normal_index()}
+                if z == 15 {touched |= 1 << BlockFace::Back.normal_index()}
+
+                for face in FACES {
+                    let n = face.normal();
+                    let (nx, ny, nz) = (x as i32 + n.x, y as i32 + n.y, z as i32 + n.z);
+                    if !(0..16).contains(&nx) || !(0..16).contains(&ny) || !(0..16).contains(&nz) {continue}
+
+                    let (nx, ny, nz) = (nx as usize, ny as usize, nz as usize);
+                    let index = nx + ny * 16 + nz * 256;
+                    if visited[index] || !is_open(nx, ny, nz) {continue}
+
+                    visited[index] = true;
+                    stack.push((nx, ny, nz));
+                }
+            }
+
+            for a in 0..6 {
+                if touched & (1 << a) == 0 {continue}
+                for b in 0..6 {
+                    if touched & (1 << b) != 0 {
+                        bits |= 1 << (a * 6 + b);
+                    }
+                }
+            }
+        }
+
+        Self(bits)
+    }
+
+    pub fn connects(&self, a: usize, b: usize) -> bool {
+        self.0 & (1 << (a * 6 + b)) != 0
+    }
+}
+
+/**
+ breadth first walk from the camera's slice through open faces. a slice is only entered through a face that its
+ previous slice can see out of, and the walk never turns back towards the camera, so caves sealed off by terrain
+ are never reached. returns None when the camera isn't inside a loaded slice and everything should be drawn
+*/
+pub fn find_visible_slices(chunks: &HashMap<u32, Arc<Chunk>>, camera_position: Point3<f32>, frustum: &Frustum) -> Option<HashSet<Vector3<i32>>> {
+    let start = Vector3::new(
+        (camera_position.x / 16.0).floor() as i32,
+        (camera_position.y / 16.0).floor() as i32,
+        (camera_position.z / 16.0).floor() as i32
+    );
+
+    if !(0..16).contains(&start.y) || !chunks.contains_key(&xz_to_index(start.x, start.z)) {
+        return None;
+    }
+
+    let mut visible = HashSet::new();
+    //slice, face it was entered through, directions already walked
+    let mut queue: VecDeque<(Vector3<i32>, Option<usize>, u8)> = VecDeque::new();
+
+    visible.insert(start);
+    queue.push_back((start, None, 0));
+
+    while let Some((slice, entered, walked)) = queue.pop_front() {
+        let Some(chunk) = chunks.get(&xz_to_index(slice.x, slice.z)) else {continue};
+        let connectivity = chunk.connectivity[slice.y as usize];
+
+        for face in FACES {
+            let out = face.normal_index() as usize;
+
+            if walked & (1 << opposite(out)) != 0 {continue}
+            if let Some(entered) = entered {
+                if !connectivity.connects(entered, out) {continue}
+            }
+
+            let next = slice + face.normal();
+            if !(0..16).contains(&next.y) || visible.contains(&next) {continue}
+            if !chunks.contains_key(&xz_to_index(next.x, next.z)) {continue}
+
+            let min = Vector3::new(next.x as f32, next.y as f32, next.z as f32) * 16.0;
+            if !frustum.intersects_aabb(min, min + Vector3::new(16.0, 16.0, 16.0)) {continue}
+
+            visible.insert(next);
+            queue.push_back((next, Some(opposite(out)), walked | (1 << out)));
+        }
+    }
+
+    Some(visible)
+}
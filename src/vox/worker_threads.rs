@@ -1,16 +1,16 @@
 use std::{collections::HashMap, sync::{atomic::AtomicI64, mpsc::{self, Receiver, Sender}, Arc}, thread};
 
-use cgmath::Vector2;
+use cgmath::{Vector2, Vector3};
 use noise::OpenSimplex;
 use stopwatch::Stopwatch;
 
 use crate::{engine::surfacevertex::SurfaceVertex, internal::depthsort::Quad};
 
-use super::{chunk::Chunk, chunk_manager::mesh_slice_arrayed};
+use super::{chunk::{xz_to_index, Chunk}, chunk_manager::{mesh_slice_arrayed, ChunkManager}, lod::{build_lod_tile_mesh, LodVertex}, visibility::SliceConnectivity};
 
 pub fn spawn_chunk_meshing_worker_thread(
     id: usize,
-    send_back: Sender<(usize, i32, i32, u32, ((Vec<SurfaceVertex>, Vec<u32>, u32), (Vec<SurfaceVertex>, Vec<u32>, u32, Vec<Quad>)))>
+    send_back: Sender<(usize, i32, i32, u32, ((Vec<SurfaceVertex>, Vec<u32>, u32, SliceConnectivity), (Vec<SurfaceVertex>, Vec<u32>, u32, Vec<Quad>)))>
 ) -> Sender<(i32, i32, u32, HashMap<u32, Arc<Chunk>>)> {
     let (send, recv) = mpsc::channel();
     
@@ -19,7 +19,7 @@ pub fn spawn_chunk_meshing_worker_thread(
         while let Ok((chunk_x, chunk_z, y_slice, chunks)) = recv.recv() {
             let t = Stopwatch::start_new();
             let result = mesh_slice_arrayed(chunk_x, chunk_z, y_slice, &chunks);
-            send_back.send((id, chunk_x, chunk_z, y_slice, result)).unwrap();
+            if send_back.send((id, chunk_x, chunk_z, y_slice, result)).is_err() {break}
         }
         
     });
@@ -37,7 +37,7 @@ pub fn spawn_chunk_creation_worker_thread(
     thread::spawn(move || {
         while let Ok((chunk_x, chunk_z)) = recv.recv() {
             let result = Arc::new(Chunk::new(Vector2::new(chunk_x, chunk_z), noisegen, &mut HashMap::new()));
-            send_back.send((id, chunk_x, chunk_z, result)).unwrap();
+            if send_back.send((id, chunk_x, chunk_z, result)).is_err() {break}
         }
         
     });
@@ -45,6 +45,23 @@ pub fn spawn_chunk_creation_worker_thread(
     send
 }
 
+pub fn spawn_lod_worker_thread(
+    id: usize,
+    seed: u32,
+    send_back: Sender<(usize, i32, i32, Vec<LodVertex>)>
+) -> Sender<(i32, i32, u32)> {
+    let (send, recv) = mpsc::channel();
+    let noisegen = OpenSimplex::new(seed);
+    thread::spawn(move || {
+        while let Ok((tile_x, tile_z, step)) = recv.recv() {
+            let result = build_lod_tile_mesh(noisegen, tile_x, tile_z, step);
+            if send_back.send((id, tile_x, tile_z, result)).is_err() {break}
+        }
+    });
+
+    send
+}
+
 pub fn spawn_chunk_creation_loop(
     num_workers: usize,
     seed: u32
@@ -65,11 +82,11 @@ pub fn spawn_chunk_creation_loop(
 
     thread::spawn(move || {
         let send_idle_worker = send_idle_worker.clone();
-        loop {
-            let data: (usize, i32, i32, Arc<Chunk>) = worker_recv_finished_chunks.recv().unwrap();
+        //every loop stops once the other end hangs up, so dropping the channels from main shuts the workers down
+        while let Ok(data) = worker_recv_finished_chunks.recv() {
+            let data: (usize, i32, i32, Arc<Chunk>) = data;
             let id = data.0.clone();
-            tomain.send((data.1, data.2, data.3)).unwrap();
-            send_idle_worker.send(id).unwrap();
+            if tomain.send((data.1, data.2, data.3)).is_err() || send_idle_worker.send(id).is_err() {break}
         }
     });
 
@@ -78,11 +95,10 @@ pub fn spawn_chunk_creation_loop(
             spawn_chunk_creation_worker_thread(id, seed, worker_send_finished_chunks.clone())
         }).collect::<Vec<_>>();
 
-        loop {
-            let next_data = frommainrecv.recv().unwrap();
-            let next = recv_idle_worker.recv().unwrap();
+        while let Ok(next_data) = frommainrecv.recv() {
+            let Ok(next) = recv_idle_worker.recv() else {break};
             let worker = &mut workers[next];
-            worker.send(next_data).unwrap();
+            if worker.send(next_data).is_err() {break}
         }
     });
 
@@ -93,7 +109,7 @@ pub fn spawn_chunk_meshing_loop(
     num_workers: usize
 ) -> (
     Sender<(i32, i32, u32, HashMap<u32, Arc<Chunk>>)>,
-    Receiver<(i32, i32, u32, ((Vec<SurfaceVertex>, Vec<u32>, u32), (Vec<SurfaceVertex>, Vec<u32>, u32, Vec<Quad>)))>
+    Receiver<(i32, i32, u32, ((Vec<SurfaceVertex>, Vec<u32>, u32, SliceConnectivity), (Vec<SurfaceVertex>, Vec<u32>, u32, Vec<Quad>)))>
 ) {
     //unapologetically stolen from elttob
     let (frommain, frommainrecv) = mpsc::channel();
@@ -108,11 +124,10 @@ pub fn spawn_chunk_meshing_loop(
 
     thread::spawn(move || {
         let send_idle_worker = send_idle_worker.clone();
-        loop {
-            let data: (usize, i32, i32, u32, ((Vec<SurfaceVertex>, Vec<u32>, u32), (Vec<SurfaceVertex>, Vec<u32>, u32, Vec<Quad>))) = worker_recv_finished_chunks.recv().unwrap();
+        while let Ok(data) = worker_recv_finished_chunks.recv() {
+            let data: (usize, i32, i32, u32, ((Vec<SurfaceVertex>, Vec<u32>, u32, SliceConnectivity), (Vec<SurfaceVertex>, Vec<u32>, u32, Vec<Quad>))) = data;
             let id = data.0.clone();
-            tomain.send((data.1, data.2, data.3, data.4)).unwrap();
-            send_idle_worker.send(id).unwrap();
+            if tomain.send((data.1, data.2, data.3, data.4)).is_err() || send_idle_worker.send(id).is_err() {break}
         }
     });
 
@@ -121,13 +136,110 @@ pub fn spawn_chunk_meshing_loop(
             spawn_chunk_meshing_worker_thread(id, worker_send_finished_chunks.clone())
         }).collect::<Vec<_>>();
 
-        loop {
-            let next_data = frommainrecv.recv().unwrap();
-            let next = recv_idle_worker.recv().unwrap();
+        while let Ok(next_data) = frommainrecv.recv() {
+            let Ok(next) = recv_idle_worker.recv() else {break};
+            let worker = &mut workers[next];
+            if worker.send(next_data).is_err() {break}
+        }
+    });
+
+    (frommain, tomainrecv)
+}
+
+pub fn spawn_lod_loop(
+    num_workers: usize,
+    seed: u32
+) -> (
+    Sender<(i32, i32, u32)>,
+    Receiver<(i32, i32, Vec<LodVertex>)>
+) {
+    let (frommain, frommainrecv) = mpsc::channel();
+    let (tomain, tomainrecv) = mpsc::channel();
+
+    let (worker_send_finished_tiles, worker_recv_finished_tiles) = mpsc::channel();
+    let (send_idle_worker, recv_idle_worker) = mpsc::channel();
+
+    for id in 0..num_workers {
+        send_idle_worker.send(id).unwrap();
+    }
+
+    thread::spawn(move || {
+        while let Ok(data) = worker_recv_finished_tiles.recv() {
+            let data: (usize, i32, i32, Vec<LodVertex>) = data;
+            if tomain.send((data.1, data.2, data.3)).is_err() || send_idle_worker.send(data.0).is_err() {break}
+        }
+    });
+
+    thread::spawn(move || {
+        let mut workers = (0..num_workers).map(|id| {
+            spawn_lod_worker_thread(id, seed, worker_send_finished_tiles.clone())
+        }).collect::<Vec<_>>();
+
+        while let Ok(next_data) = frommainrecv.recv() {
+            let Ok(next) = recv_idle_worker.recv() else {break};
             let worker = &mut workers[next];
-            worker.send(next_data).unwrap();
+            if worker.send(next_data).is_err() {break}
         }
     });
 
     (frommain, tomainrecv)
-}
\ No newline at end of file
+}
+
+/**
+ moves finished meshes and generated chunks from the worker loops into the chunk manager, once per frame.
+ illumination and the first meshing pass start when the last chunk in render distance arrives
+*/
+pub fn receive_worker_results(
+    chunk_manager: &mut ChunkManager,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    sendmesh: &Sender<(i32, i32, u32, HashMap<u32, Arc<Chunk>>)>,
+    getmesh: &Receiver<(i32, i32, u32, ((Vec<SurfaceVertex>, Vec<u32>, u32, SliceConnectivity), (Vec<SurfaceVertex>, Vec<u32>, u32, Vec<Quad>)))>,
+    getchunk: &Receiver<(i32, i32, Arc<Chunk>)>
+) {
+    for _ in 0..10 {
+        if let Ok(res) = getmesh.try_recv() {
+            let at = Vector3::new(res.0, res.2 as i32, res.1);
+            let index = chunk_manager.unresolved_meshes.iter().position(|p| *p == at);
+            if let Some(i) = index {
+                chunk_manager.unresolved_meshes.swap_remove(i);
+            }
+            chunk_manager.finalize_mesh(res.0, res.1, res.2, device, queue, res.3);
+        }
+        else {
+            break;
+        }
+    }
+    loop {
+        if let Ok(res) = getchunk.try_recv() {
+            let chunkbuff = chunk_manager.chunk_buffers.get_mut(&xz_to_index(res.0, res.1)).unwrap();
+
+            chunkbuff.assign_slots(device, queue, &mut chunk_manager.mesh_pool);
+
+            chunk_manager.chunks.insert(xz_to_index(res.0, res.1), res.2);
+
+            if chunk_manager.chunks.len() as u32 == (chunk_manager.render_distance * 2 + 1).pow(2) {
+                println!("Beginning Illumination");
+                chunk_manager.generate_chunk_illumination(device);
+                chunk_manager.mesh_chunks(device, sendmesh, Vector3::new(0., 0., 0.));
+            }
+        }
+        else {
+            break;
+        }
+    }
+}
+
+/**
+ uploads finished lod tiles, a few per frame so a ring of new tiles doesn't stall one frame
+*/
+pub fn receive_lod_results(
+    chunk_manager: &mut ChunkManager,
+    device: &wgpu::Device,
+    getlod: &Receiver<(i32, i32, Vec<LodVertex>)>
+) {
+    for _ in 0..8 {
+        let Ok((x, z, vertices)) = getlod.try_recv() else {break};
+        chunk_manager.lod.finalize_tile(device, x, z, vertices);
+    }
+}
@@ -0,0 +1,23 @@
+//renders a frame through the --offscreen flag, the same way ci checks that the renderer still produces an image
+
+use std::process::Command;
+
+#[test]
+fn offscreen_frame_is_rendered() {
+    let path = std::env::temp_dir().join(format!("soulspark_offscreen_{}.png", std::process::id()));
+
+    let status = Command::new(env!("CARGO_BIN_EXE_soulspark"))
+        .args(["--offscreen", path.to_str().unwrap(), "--size", "160x90"])
+        .status()
+        .expect("Unable to start soulspark");
+    assert!(status.success(), "soulspark --offscreen exited with {}", status);
+
+    let image = image::open(&path).expect("Offscreen frame wasn't written").to_rgba8();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(image.dimensions(), (160, 90));
+
+    //sky and terrain should give more than one colour, a blank or cleared frame doesn't
+    let first = image.get_pixel(0, 0);
+    assert!(image.pixels().any(|p| p != first), "Offscreen frame is a single colour");
+}